@@ -6,29 +6,69 @@ import Input from "@/components/ui/Input";
 import Slider from "@/components/ui/Slider";
 import { useApp } from "@/lib/context";
 import * as tauri from "@/lib/tauri";
-import type { BehaviorLog } from "@/lib/types";
+import type {
+  ActionSuggestion,
+  ActionSuggestions,
+  AlignmentSuggestion,
+  BehaviorLog,
+  Commitment,
+  CommitmentStatus,
+  CredentialStatus,
+  Language,
+  PlannedAction,
+  PromptTemplate,
+  QueueStatus,
+  QuickLogPreset,
+  Settings,
+} from "@/lib/types";
 import Link from "next/link";
 import { useRouter } from "next/navigation";
 import { useCallback, useEffect, useState } from "react";
 
-function todayISO() {
-  return new Date().toISOString().slice(0, 10);
-}
-
 export default function DashboardPage() {
-  const { user, identities, currentIdentity, setCurrentIdentity, loading: appLoading } = useApp();
+  const {
+    user,
+    identities,
+    currentIdentity,
+    setCurrentIdentity,
+    refreshIdentities,
+    loading: appLoading,
+  } = useApp();
   const router = useRouter();
   const [description, setDescription] = useState("");
   const [alignmentScore, setAlignmentScore] = useState(7);
   const [behaviors, setBehaviors] = useState<BehaviorLog[]>([]);
   const [loading, setLoading] = useState(false);
   const [reflectionLoading, setReflectionLoading] = useState(false);
+  const [reflectionRequestId, setReflectionRequestId] = useState<string | null>(null);
+  const [reflectionPreview, setReflectionPreview] = useState("");
+  const [suggestion, setSuggestion] = useState<AlignmentSuggestion | null>(null);
+  const [suggestLoading, setSuggestLoading] = useState(false);
   const [apiKey, setApiKey] = useState("");
+  const [keyStatus, setKeyStatus] = useState<CredentialStatus | null>(null);
+  const [templates, setTemplates] = useState<PromptTemplate[]>([]);
+  const [languages, setLanguages] = useState<Language[]>([]);
+  const [settings, setSettings] = useState<Settings | null>(null);
+  const [timezoneInput, setTimezoneInput] = useState("");
+  const [dayEndHour, setDayEndHour] = useState(0);
+  const [queue, setQueue] = useState<QueueStatus | null>(null);
+  const [commitments, setCommitments] = useState<Commitment[]>([]);
+  const [planned, setPlanned] = useState<PlannedAction[]>([]);
+  const [presets, setPresets] = useState<QuickLogPreset[]>([]);
+  const [actions, setActions] = useState<ActionSuggestions | null>(null);
+  const [actionsLoading, setActionsLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
-  const date = todayISO();
+  const [date, setDate] = useState<string | null>(null);
+
+  useEffect(() => {
+    tauri
+      .getToday()
+      .then(setDate)
+      .catch((e) => setError(e instanceof Error ? e.message : String(e)));
+  }, []);
 
   const loadBehaviors = useCallback(async () => {
-    if (!currentIdentity) return;
+    if (!currentIdentity || !date) return;
     try {
       const list = await tauri.getBehaviorsForDate(currentIdentity.id, date);
       setBehaviors(list);
@@ -48,8 +88,225 @@ export default function DashboardPage() {
     if (currentIdentity) loadBehaviors();
   }, [currentIdentity, loadBehaviors]);
 
+  const loadCommitments = useCallback(async () => {
+    if (!currentIdentity) return;
+    try {
+      setCommitments(await tauri.listCommitments(currentIdentity.id, "open"));
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    }
+  }, [currentIdentity]);
+
+  useEffect(() => {
+    loadCommitments();
+  }, [loadCommitments]);
+
+  const loadPlan = useCallback(async () => {
+    if (!currentIdentity || !date) return;
+    try {
+      const [p, q] = await Promise.all([
+        tauri.listPlannedActions(currentIdentity.id, date),
+        tauri.listQuickLogPresets(currentIdentity.id),
+      ]);
+      setPlanned(p);
+      setPresets(q);
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    }
+  }, [currentIdentity, date]);
+
+  useEffect(() => {
+    loadPlan();
+  }, [loadPlan]);
+
+  const handleSuggestActions = async () => {
+    if (!currentIdentity || !date) return;
+    setActionsLoading(true);
+    setError(null);
+    try {
+      setActions(await tauri.suggestActions(currentIdentity.id, date));
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    } finally {
+      setActionsLoading(false);
+    }
+  };
+
+  // Turns a suggestion into a planned item for its day or a quick-log preset.
+  const handleKeepSuggestion = async (action: ActionSuggestion, as: "plan" | "preset") => {
+    if (!currentIdentity || !actions) return;
+    try {
+      if (as === "plan") {
+        await tauri.planAction({
+          identity_id: currentIdentity.id,
+          date: actions.date,
+          description: action.description,
+          reason: action.reason,
+          trait_ids: action.traits.map((t) => t.id),
+          expected_score: action.expected_score,
+        });
+      } else {
+        await tauri.createQuickLogPreset({
+          identity_id: currentIdentity.id,
+          description: action.description,
+          alignment_score: action.expected_score,
+          trait_ids: action.traits.map((t) => t.id),
+        });
+      }
+      setActions({
+        ...actions,
+        actions: actions.actions.filter((a) => a !== action),
+      });
+      await loadPlan();
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    }
+  };
+
+  const handleCompletePlanned = async (id: number) => {
+    try {
+      await tauri.completePlannedAction(id);
+      await Promise.all([loadPlan(), loadBehaviors()]);
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    }
+  };
+
+  const handleLogPreset = async (id: number) => {
+    if (!date) return;
+    try {
+      await tauri.logQuickLogPreset(id, date);
+      await loadBehaviors();
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    }
+  };
+
+  // Marking a commitment kept links today's behaviors as what fulfilled it.
+  const handleResolve = async (commitment: Commitment, status: CommitmentStatus) => {
+    try {
+      if (status === "kept" && behaviors.length > 0) {
+        await tauri.linkCommitmentBehaviors(
+          commitment.id,
+          behaviors.map((b) => b.id)
+        );
+      }
+      await tauri.resolveCommitment(commitment.id, status);
+      await loadCommitments();
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    }
+  };
+
+  const loadKeyStatus = useCallback(async () => {
+    if (!currentIdentity) return;
+    try {
+      const config = await tauri.getEffectiveLlmConfig(currentIdentity.id);
+      setKeyStatus(await tauri.getApiKeyStatus(config.provider));
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    }
+  }, [currentIdentity]);
+
+  useEffect(() => {
+    loadKeyStatus();
+  }, [loadKeyStatus]);
+
+  useEffect(() => {
+    tauri
+      .listPromptTemplates()
+      .then(setTemplates)
+      .catch((e) => setError(e instanceof Error ? e.message : String(e)));
+    tauri.listLanguages().then(setLanguages).catch(() => {});
+    tauri
+      .getSettings()
+      .then((s) => {
+        setSettings(s);
+        setTimezoneInput(s.timezone ?? "");
+        setDayEndHour(s.day_end_hour);
+      })
+      .catch(() => {});
+  }, []);
+
+  const defaultLanguageName =
+    languages.find((l) => l.code === settings?.language)?.name ?? "English";
+
+  // A new timezone or day boundary can change which day "today" is.
+  const handleSaveDaySettings = async () => {
+    setError(null);
+    try {
+      const s = await tauri.updateSettings({
+        timezone: timezoneInput.trim(),
+        day_end_hour: dayEndHour,
+      });
+      setSettings(s);
+      setTimezoneInput(s.timezone ?? "");
+      setDate(await tauri.getToday());
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    }
+  };
+
+  useEffect(() => {
+    tauri.getReflectionQueue().then(setQueue).catch(() => {});
+    const unlisten = tauri.onReflectionQueueChanged(setQueue);
+    // Retry right away instead of waiting for the next periodic attempt.
+    const retry = () => {
+      tauri.processReflectionQueue().then(setQueue).catch(() => {});
+    };
+    window.addEventListener("online", retry);
+    return () => {
+      unlisten.then((f) => f());
+      window.removeEventListener("online", retry);
+    };
+  }, []);
+
+  const handlePersonaChange = async (value: string) => {
+    if (!currentIdentity) return;
+    setError(null);
+    try {
+      await tauri.setIdentityPromptTemplate(currentIdentity.id, value ? Number(value) : null);
+      await refreshIdentities();
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    }
+  };
+
+  const handleLanguageChange = async (value: string) => {
+    if (!currentIdentity) return;
+    setError(null);
+    try {
+      await tauri.setIdentityLanguage(currentIdentity.id, value || null);
+      await refreshIdentities();
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    }
+  };
+
+  const handleSaveApiKey = async () => {
+    if (!keyStatus || !apiKey.trim()) return;
+    setError(null);
+    try {
+      setKeyStatus(await tauri.setApiKey(keyStatus.provider, apiKey.trim()));
+      setApiKey("");
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    }
+  };
+
+  // Only used when the OS has no keyring; the file is guarded by its
+  // permissions alone, so the user has to opt in.
+  const handleAllowFileCredentials = async (allow: boolean) => {
+    setError(null);
+    try {
+      setSettings(await tauri.updateSettings({ allow_file_credentials: allow }));
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    }
+  };
+
   const handleLogBehavior = async () => {
-    if (!currentIdentity || !description.trim()) return;
+    if (!currentIdentity || !date || !description.trim()) return;
     setLoading(true);
     setError(null);
     try {
@@ -58,9 +315,12 @@ export default function DashboardPage() {
         description: description.trim(),
         identity_id: currentIdentity.id,
         alignment_score: alignmentScore,
+        suggested_score: suggestion?.score ?? null,
+        trait_ids: suggestion?.traits.map((t) => t.id) ?? [],
       });
       setDescription("");
       setAlignmentScore(7);
+      setSuggestion(null);
       await loadBehaviors();
     } catch (e) {
       setError(e instanceof Error ? e.message : String(e));
@@ -69,34 +329,48 @@ export default function DashboardPage() {
     }
   };
 
-  const handleGenerateReflection = async () => {
-    if (!currentIdentity || !apiKey.trim()) {
-      setError("OpenAI API key is required. Add it in Settings or in the field above.");
-      return;
+  const handleSuggestAlignment = async () => {
+    if (!currentIdentity || !description.trim()) return;
+    setSuggestLoading(true);
+    setError(null);
+    try {
+      const s = await tauri.suggestAlignment(currentIdentity.id, description.trim());
+      setSuggestion(s);
+      setAlignmentScore(s.score);
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    } finally {
+      setSuggestLoading(false);
     }
+  };
+
+  const handleGenerateReflection = async () => {
+    if (!currentIdentity || !date) return;
+    const requestId = crypto.randomUUID();
     setReflectionLoading(true);
+    setReflectionRequestId(requestId);
+    setReflectionPreview("");
     setError(null);
     try {
-      const traitsList = await tauri.listTraits(currentIdentity.id);
-      await tauri.generateReflection(apiKey, {
-        identity_id: currentIdentity.id,
-        date,
-        identity_name: currentIdentity.name,
-        identity_description: currentIdentity.description,
-        traits: traitsList.map((t) => t.name),
-        behaviors: behaviors.map((b) => ({
-          description: b.description,
-          alignment_score: b.alignment_score,
-        })),
+      await tauri.generateReflection(currentIdentity.id, date, {
+        requestId,
+        onDelta: setReflectionPreview,
       });
       router.push("/reflection?date=" + date + "&identityId=" + currentIdentity.id);
     } catch (e) {
-      setError(e instanceof Error ? e.message : String(e));
+      const message = e instanceof Error ? e.message : String(e);
+      if (message !== "Reflection cancelled") setError(message);
     } finally {
       setReflectionLoading(false);
+      setReflectionRequestId(null);
+      setReflectionPreview("");
     }
   };
 
+  const handleCancelReflection = async () => {
+    if (reflectionRequestId) await tauri.cancelReflection(reflectionRequestId);
+  };
+
   if (appLoading || !user) {
     return (
       <div className="flex min-h-screen items-center justify-center">
@@ -170,7 +444,10 @@ export default function DashboardPage() {
             <Input
               placeholder="e.g. Shipped the feature without context-switching"
               value={description}
-              onChange={(e) => setDescription(e.target.value)}
+              onChange={(e) => {
+                setDescription(e.target.value);
+                setSuggestion(null);
+              }}
               onKeyDown={(e) => e.key === "Enter" && handleLogBehavior()}
             />
             <Slider
@@ -180,14 +457,45 @@ export default function DashboardPage() {
               min={1}
               max={10}
             />
-            <Button
-              className="w-full"
-              onClick={handleLogBehavior}
-              loading={loading}
-              disabled={!description.trim()}
-            >
-              Log
-            </Button>
+            {suggestion && (
+              <div className="rounded-apple bg-surface-overlay px-3 py-2 text-sm">
+                <p className="text-label-primary">
+                  Suggested {suggestion.score}/10 — {suggestion.rationale}
+                </p>
+                {suggestion.traits.length > 0 && (
+                  <p className="mt-1 text-label-secondary">
+                    {suggestion.traits.map((t) => t.name).join(", ")}
+                  </p>
+                )}
+              </div>
+            )}
+            {presets.length > 0 && (
+              <div className="flex flex-wrap gap-2">
+                {presets.map((p) => (
+                  <Button key={p.id} variant="ghost" size="sm" onClick={() => handleLogPreset(p.id)}>
+                    {p.description}
+                  </Button>
+                ))}
+              </div>
+            )}
+            <div className="flex gap-3">
+              <Button
+                variant="secondary"
+                onClick={handleSuggestAlignment}
+                loading={suggestLoading}
+                disabled={!description.trim()}
+              >
+                Suggest
+              </Button>
+              <Button
+                className="flex-1"
+                onClick={handleLogBehavior}
+                loading={loading}
+                disabled={!description.trim()}
+              >
+                Log
+              </Button>
+            </div>
           </CardContent>
         </Card>
 
@@ -214,6 +522,99 @@ export default function DashboardPage() {
           </Card>
         )}
 
+        {planned.length > 0 && (
+          <Card className="mb-6">
+            <CardHeader>
+              <CardTitle>Planned for today</CardTitle>
+            </CardHeader>
+            <CardContent>
+              <ul className="space-y-2">
+                {planned.map((p) => (
+                  <li
+                    key={p.id}
+                    className="flex items-center justify-between gap-3 rounded-apple bg-surface-overlay px-3 py-2 text-sm"
+                  >
+                    <span className={p.status === "planned" ? "text-label-primary" : "text-label-secondary line-through"}>
+                      {p.description}
+                    </span>
+                    {p.status === "planned" && (
+                      <Button variant="ghost" onClick={() => handleCompletePlanned(p.id)}>
+                        Done
+                      </Button>
+                    )}
+                  </li>
+                ))}
+              </ul>
+            </CardContent>
+          </Card>
+        )}
+
+        <Card className="mb-6">
+          <CardHeader>
+            <CardTitle>Tomorrow</CardTitle>
+            <p className="text-sm text-label-secondary">
+              Small next steps based on neglected traits, low scores and unkept commitments.
+            </p>
+          </CardHeader>
+          <CardContent className="space-y-3">
+            <Button variant="secondary" onClick={handleSuggestActions} loading={actionsLoading}>
+              Suggest actions
+            </Button>
+            {actions && (
+              <ul className="space-y-2">
+                {actions.actions.map((a, i) => (
+                  <li key={i} className="rounded-apple bg-surface-overlay px-3 py-2 text-sm">
+                    <p className="text-label-primary">
+                      {a.description}
+                      {a.minutes ? ` · ${a.minutes} min` : ""}
+                    </p>
+                    <p className="mt-1 text-label-secondary">{a.reason}</p>
+                    <div className="mt-2 flex gap-2">
+                      <Button variant="ghost" size="sm" onClick={() => handleKeepSuggestion(a, "plan")}>
+                        Plan for {actions.date}
+                      </Button>
+                      <Button variant="ghost" size="sm" onClick={() => handleKeepSuggestion(a, "preset")}>
+                        Save as preset
+                      </Button>
+                    </div>
+                  </li>
+                ))}
+              </ul>
+            )}
+          </CardContent>
+        </Card>
+
+        {commitments.length > 0 && (
+          <Card className="mb-6">
+            <CardHeader>
+              <CardTitle>Commitments</CardTitle>
+            </CardHeader>
+            <CardContent>
+              <ul className="space-y-2">
+                {commitments.map((c) => (
+                  <li
+                    key={c.id}
+                    className="flex items-center justify-between gap-3 rounded-apple bg-surface-overlay px-3 py-2 text-sm"
+                  >
+                    <span className="text-label-primary">
+                      {c.text}
+                      <span className="ml-2 text-label-secondary">due {c.due_date}</span>
+                    </span>
+                    <span className="flex gap-2">
+                      <Button variant="ghost" onClick={() => handleResolve(c, "kept")}>
+                        Kept
+                      </Button>
+                      <Button variant="ghost" onClick={() => handleResolve(c, "broken")}>
+                        Broken
+                      </Button>
+                    </span>
+                  </li>
+                ))}
+              </ul>
+            </CardContent>
+          </Card>
+        )}
+
         <Card className="mb-6">
           <CardHeader>
             <CardTitle>AI Reflection</CardTitle>
@@ -222,22 +623,133 @@ export default function DashboardPage() {
             </p>
           </CardHeader>
           <CardContent className="space-y-4">
-            <Input
-              label="OpenAI API key"
-              type="password"
-              placeholder="sk-..."
-              value={apiKey}
-              onChange={(e) => setApiKey(e.target.value)}
-            />
+            {keyStatus && !keyStatus.configured && (
+              <div className="flex items-end gap-3">
+                <div className="flex-1">
+                  <Input
+                    label="API key"
+                    type="password"
+                    placeholder="sk-..."
+                    value={apiKey}
+                    onChange={(e) => setApiKey(e.target.value)}
+                  />
+                </div>
+                <Button variant="secondary" onClick={handleSaveApiKey} disabled={!apiKey.trim()}>
+                  Save key
+                </Button>
+              </div>
+            )}
+            {keyStatus && !keyStatus.configured && settings && (
+              <label className="flex items-center gap-2 text-sm text-label-secondary">
+                <input
+                  type="checkbox"
+                  checked={settings.allow_file_credentials}
+                  onChange={(e) => handleAllowFileCredentials(e.target.checked)}
+                />
+                Without an OS keyring, store the key in a plain file protected only by file
+                permissions
+              </label>
+            )}
+            <label className="block text-sm">
+              <span className="mb-1 block text-label-secondary">Coaching persona</span>
+              <select
+                value={currentIdentity?.prompt_template_id ?? ""}
+                onChange={(e) => handlePersonaChange(e.target.value)}
+                className="h-10 w-full rounded-apple border border-border bg-white px-3 text-sm focus:outline-none focus:ring-2 focus:ring-neutral-400 focus:ring-offset-2"
+              >
+                <option value="">Default</option>
+                {templates.map((t) => (
+                  <option key={t.id} value={t.id}>
+                    {t.name}
+                  </option>
+                ))}
+              </select>
+            </label>
+            <label className="block text-sm">
+              <span className="mb-1 block text-label-secondary">Reflection language</span>
+              <select
+                value={currentIdentity?.language ?? ""}
+                onChange={(e) => handleLanguageChange(e.target.value)}
+                className="h-10 w-full rounded-apple border border-border bg-white px-3 text-sm focus:outline-none focus:ring-2 focus:ring-neutral-400 focus:ring-offset-2"
+              >
+                <option value="">
+                  App default ({defaultLanguageName})
+                </option>
+                {languages.map((l) => (
+                  <option key={l.code} value={l.code}>
+                    {l.name}
+                  </option>
+                ))}
+              </select>
+            </label>
             <Button
               className="w-full"
               variant="secondary"
               onClick={handleGenerateReflection}
               loading={reflectionLoading}
-              disabled={!apiKey.trim()}
             >
               Generate reflection
             </Button>
+            {queue && queue.pending > 0 && (
+              <p className="text-xs text-label-secondary">
+                {queue.pending} reflection{queue.pending === 1 ? "" : "s"} waiting for the
+                network; they will be generated when the connection is back.
+              </p>
+            )}
+            {reflectionLoading && (
+              <div className="mt-3 space-y-2">
+                {reflectionPreview && (
+                  <pre className="max-h-48 overflow-auto whitespace-pre-wrap rounded-apple bg-surface-overlay p-3 text-xs text-label-secondary">
+                    {reflectionPreview}
+                  </pre>
+                )}
+                <Button variant="ghost" onClick={handleCancelReflection}>
+                  Cancel
+                </Button>
+              </div>
+            )}
+          </CardContent>
+        </Card>
+
+        <Card className="mb-6">
+          <CardHeader>
+            <CardTitle>Day</CardTitle>
+            <p className="text-sm text-label-secondary">
+              Which timezone your days follow, and the hour a new day starts.
+            </p>
+          </CardHeader>
+          <CardContent className="space-y-3">
+            <Input
+              label="Timezone"
+              placeholder="System clock, e.g. Europe/Berlin"
+              value={timezoneInput}
+              onChange={(e) => setTimezoneInput(e.target.value)}
+            />
+            <label className="block text-sm">
+              <span className="mb-1 block text-label-secondary">New day starts at</span>
+              <select
+                value={dayEndHour}
+                onChange={(e) => setDayEndHour(Number(e.target.value))}
+                className="h-10 w-full rounded-apple border border-border bg-white px-3 text-sm focus:outline-none focus:ring-2 focus:ring-neutral-400 focus:ring-offset-2"
+              >
+                {Array.from({ length: 24 }, (_, h) => (
+                  <option key={h} value={h}>
+                    {h === 0 ? "Midnight" : `${String(h).padStart(2, "0")}:00`}
+                  </option>
+                ))}
+              </select>
+            </label>
+            <Button
+              variant="secondary"
+              onClick={handleSaveDaySettings}
+              disabled={
+                !!settings &&
+                timezoneInput.trim() === (settings.timezone ?? "") &&
+                dayEndHour === settings.day_end_hour
+              }
+            >
+              Save
+            </Button>
           </CardContent>
         </Card>
 
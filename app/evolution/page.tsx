@@ -4,24 +4,28 @@ import Button from "@/components/ui/Button";
 import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/Card";
 import { useApp } from "@/lib/context";
 import * as tauri from "@/lib/tauri";
-import type { AlignmentTrend } from "@/lib/types";
+import type {
+  AlignmentTrend,
+  BackfillReport,
+  CommitmentStats,
+  Period,
+  PeriodReflection,
+} from "@/lib/types";
 import Link from "next/link";
 import { useCallback, useEffect, useState } from "react";
 
-function getWeekRange(): { from: string; to: string } {
-  const now = new Date();
-  const to = now.toISOString().slice(0, 10);
-  const from = new Date(now);
-  from.setDate(from.getDate() - 6);
-  return { from: from.toISOString().slice(0, 10), to };
-}
-
 export default function EvolutionPage() {
   const { currentIdentity, loading: appLoading } = useApp();
   const [trends, setTrends] = useState<AlignmentTrend[]>([]);
   const [weekly, setWeekly] = useState<Array<{ date: string; avg_score: number; count: number }>>([]);
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
+  const [report, setReport] = useState<PeriodReflection | null>(null);
+  const [reportLoading, setReportLoading] = useState<Period | null>(null);
+  const [backfillId, setBackfillId] = useState<string | null>(null);
+  const [backfillProgress, setBackfillProgress] = useState<string | null>(null);
+  const [backfillReport, setBackfillReport] = useState<BackfillReport | null>(null);
+  const [commitmentStats, setCommitmentStats] = useState<CommitmentStats | null>(null);
 
   const load = useCallback(async () => {
     if (!currentIdentity) {
@@ -31,19 +35,17 @@ export default function EvolutionPage() {
     setLoading(true);
     setError(null);
     try {
-      const [t, w] = await Promise.all([
+      const today = await tauri.getToday();
+      const [t, w, r, c] = await Promise.all([
         tauri.getAlignmentTrends(currentIdentity.id, 14),
-        (async () => {
-          const { from, to } = getWeekRange();
-          return tauri.getWeeklyAlignment(
-            currentIdentity.id,
-            from,
-            to
-          );
-        })(),
+        tauri.getWeeklyAlignment(currentIdentity.id),
+        tauri.getPeriodReflection(currentIdentity.id, "week", today),
+        tauri.getCommitmentStats(currentIdentity.id, 8),
       ]);
       setTrends(t);
       setWeekly(w);
+      setReport(r);
+      setCommitmentStats(c);
     } catch (e) {
       setError(e instanceof Error ? e.message : String(e));
     } finally {
@@ -55,6 +57,51 @@ export default function EvolutionPage() {
     load();
   }, [load]);
 
+  const handleGenerateReport = async (period: Period) => {
+    if (!currentIdentity) return;
+    setReportLoading(period);
+    setError(null);
+    try {
+      const today = await tauri.getToday();
+      setReport(await tauri.generatePeriodReflection(currentIdentity.id, period, today));
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    } finally {
+      setReportLoading(null);
+    }
+  };
+
+  // Fills in reflections for the last 30 days that have behaviors but none.
+  const handleBackfill = async () => {
+    if (!currentIdentity) return;
+    const requestId = crypto.randomUUID();
+    setBackfillId(requestId);
+    setBackfillReport(null);
+    setBackfillProgress(null);
+    setError(null);
+    try {
+      const today = await tauri.getToday();
+      const from = new Date(today + "T00:00:00");
+      from.setDate(from.getDate() - 29);
+      const report = await tauri.backfillReflections(
+        currentIdentity.id,
+        from.toLocaleDateString("en-CA"),
+        today,
+        {
+          requestId,
+          onProgress: (day, completed, total) =>
+            setBackfillProgress(`${completed} of ${total} · ${day.date} ${day.status}`),
+        }
+      );
+      setBackfillReport(report);
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    } finally {
+      setBackfillId(null);
+      setBackfillProgress(null);
+    }
+  };
+
   if (appLoading) {
     return (
       <div className="flex min-h-screen items-center justify-center">
@@ -202,6 +249,132 @@ export default function EvolutionPage() {
           </CardContent>
         </Card>
 
+        {commitmentStats && commitmentStats.kept + commitmentStats.broken + commitmentStats.open > 0 && (
+          <Card className="mb-6">
+            <CardHeader>
+              <CardTitle>Commitments</CardTitle>
+            </CardHeader>
+            <CardContent className="space-y-3 text-sm">
+              <p>
+                {commitmentStats.kept_rate != null
+                  ? `${Math.round(commitmentStats.kept_rate * 100)}% kept`
+                  : "None resolved yet"}{" "}
+                · {commitmentStats.kept} kept, {commitmentStats.broken} broken,{" "}
+                {commitmentStats.open} open
+                {commitmentStats.overdue > 0 ? ` (${commitmentStats.overdue} overdue)` : ""}
+              </p>
+              <ul className="space-y-1 text-label-secondary">
+                {commitmentStats.weeks
+                  .filter((w) => w.kept + w.broken + w.open > 0)
+                  .map((w) => (
+                    <li key={w.week_start}>
+                      Week of {w.week_start}:{" "}
+                      {w.kept_rate != null ? `${Math.round(w.kept_rate * 100)}% kept` : "—"} (
+                      {w.kept}/{w.kept + w.broken})
+                    </li>
+                  ))}
+              </ul>
+            </CardContent>
+          </Card>
+        )}
+
+        <Card className="mb-6">
+          <CardHeader>
+            <CardTitle>Missed days</CardTitle>
+          </CardHeader>
+          <CardContent className="space-y-3 text-sm">
+            <div className="flex gap-3">
+              <Button variant="secondary" onClick={handleBackfill} loading={backfillId !== null}>
+                Fill in the last 30 days
+              </Button>
+              {backfillId && (
+                <Button variant="ghost" onClick={() => tauri.cancelBackfill(backfillId)}>
+                  Cancel
+                </Button>
+              )}
+            </div>
+            {backfillProgress && <p className="text-label-secondary">{backfillProgress}</p>}
+            {backfillReport && (
+              <div>
+                <p>
+                  {backfillReport.total === 0
+                    ? "No missed days."
+                    : `${backfillReport.succeeded} generated, ${backfillReport.failed} failed` +
+                      (backfillReport.cancelled ? ", cancelled" : "") +
+                      (backfillReport.stopped ? ", stopped: " + backfillReport.stopped : "")}
+                </p>
+                <ul className="mt-2 space-y-1 text-label-secondary">
+                  {backfillReport.days
+                    .filter((d) => d.status !== "done")
+                    .map((d) => (
+                      <li key={d.date}>
+                        {d.date}: {d.status}
+                        {d.error ? " — " + d.error : ""}
+                      </li>
+                    ))}
+                </ul>
+              </div>
+            )}
+          </CardContent>
+        </Card>
+
+        <Card className="mb-6">
+          <CardHeader>
+            <CardTitle>AI report</CardTitle>
+          </CardHeader>
+          <CardContent>
+            <div className="mb-4 flex gap-3">
+              <Button
+                variant="secondary"
+                onClick={() => handleGenerateReport("week")}
+                loading={reportLoading === "week"}
+                disabled={reportLoading !== null}
+              >
+                Weekly report
+              </Button>
+              <Button
+                variant="secondary"
+                onClick={() => handleGenerateReport("month")}
+                loading={reportLoading === "month"}
+                disabled={reportLoading !== null}
+              >
+                Monthly report
+              </Button>
+            </div>
+            {report ? (
+              <div className="space-y-3 text-sm">
+                <p className="font-medium">{report.report.title}</p>
+                <p className="text-label-tertiary">
+                  {report.start_date} – {report.end_date} · {report.report.trend}
+                </p>
+                <p>{report.report.trendSummary}</p>
+                <div>
+                  <p className="font-medium">Wins</p>
+                  <ul className="list-disc pl-5">
+                    {report.report.wins.map((w, i) => (
+                      <li key={i}>{w}</li>
+                    ))}
+                  </ul>
+                </div>
+                <div>
+                  <p className="font-medium">Recurring misalignments</p>
+                  <ul className="list-disc pl-5">
+                    {report.report.misalignments.map((m, i) => (
+                      <li key={i}>{m}</li>
+                    ))}
+                  </ul>
+                </div>
+                <p>
+                  <span className="font-medium">Focus next: </span>
+                  {report.report.focus}
+                </p>
+              </div>
+            ) : (
+              <p className="text-sm text-label-tertiary">No report for this week yet.</p>
+            )}
+          </CardContent>
+        </Card>
+
         <Card>
           <CardHeader>
             <CardTitle>Last 14 days</CardTitle>
@@ -6,6 +6,7 @@ import Input from "@/components/ui/Input";
 import Textarea from "@/components/ui/Textarea";
 import { useApp } from "@/lib/context";
 import * as tauri from "@/lib/tauri";
+import type { TraitSuggestion } from "@/lib/types";
 import { useRouter } from "next/navigation";
 import { useState } from "react";
 
@@ -18,8 +19,12 @@ export default function OnboardingPage() {
   const [name, setName] = useState("");
   const [identityName, setIdentityName] = useState("");
   const [identityDescription, setIdentityDescription] = useState("");
-  const [traits, setTraits] = useState<string[]>([]);
+  const [identityId, setIdentityId] = useState<number | null>(null);
+  const [traits, setTraits] = useState<{ name: string; definition: string | null }[]>([]);
   const [traitInput, setTraitInput] = useState("");
+  const [suggestions, setSuggestions] = useState<TraitSuggestion[]>([]);
+  const [selected, setSelected] = useState<string[]>([]);
+  const [suggesting, setSuggesting] = useState(false);
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
 
@@ -40,14 +45,47 @@ export default function OnboardingPage() {
 
   const addTrait = () => {
     const t = traitInput.trim();
-    if (t && !traits.includes(t)) {
-      setTraits((prev) => [...prev, t]);
+    if (t && !traits.some((x) => x.name === t)) {
+      setTraits((prev) => [...prev, { name: t, definition: null }]);
       setTraitInput("");
     }
   };
 
   const removeTrait = (t: string) => {
-    setTraits((prev) => prev.filter((x) => x !== t));
+    setTraits((prev) => prev.filter((x) => x.name !== t));
+  };
+
+  const handleSuggestTraits = async () => {
+    if (identityId === null) return;
+    setSuggesting(true);
+    setError(null);
+    try {
+      const list = await tauri.suggestTraits(identityId);
+      const taken = traits.map((t) => t.name.toLowerCase());
+      const fresh = list.filter((s) => !taken.includes(s.name.toLowerCase()));
+      setSuggestions(fresh);
+      setSelected(fresh.map((s) => s.name));
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    } finally {
+      setSuggesting(false);
+    }
+  };
+
+  const toggleSuggestion = (name: string) => {
+    setSelected((prev) => (prev.includes(name) ? prev.filter((x) => x !== name) : [...prev, name]));
+  };
+
+  const addSelectedSuggestions = () => {
+    const picked = suggestions.filter((s) => selected.includes(s.name));
+    setTraits((prev) => [
+      ...prev,
+      ...picked
+        .filter((s) => !prev.some((x) => x.name.toLowerCase() === s.name.toLowerCase()))
+        .map((s) => ({ name: s.name, definition: s.definition })),
+    ]);
+    setSuggestions([]);
+    setSelected([]);
   };
 
   const { user } = useApp();
@@ -61,10 +99,11 @@ export default function OnboardingPage() {
         setError("User not found.");
         return;
       }
-      await tauri.createIdentity(user.id, {
+      const identity = await tauri.createIdentity(user.id, {
         name: identityName.trim(),
         description: identityDescription.trim() || undefined,
       });
+      setIdentityId(identity.id);
       await refreshIdentities();
       setStep("traits");
     } catch (e) {
@@ -89,9 +128,7 @@ export default function OnboardingPage() {
         setError("Identity not found.");
         return;
       }
-      for (const t of traits) {
-        await tauri.createTrait(identity.id, t);
-      }
+      await tauri.acceptTraitSuggestions(identity.id, traits);
       await refreshIdentities();
       router.replace("/dashboard");
     } catch (e) {
@@ -195,19 +232,62 @@ export default function OnboardingPage() {
                   Add
                 </Button>
               </div>
+              <Button
+                variant="ghost"
+                onClick={handleSuggestTraits}
+                loading={suggesting}
+                disabled={identityId === null}
+              >
+                Suggest traits for {identityName.trim() || "this identity"}
+              </Button>
+              {suggestions.length > 0 && (
+                <div className="space-y-2">
+                  <ul className="space-y-2">
+                    {suggestions.map((s) => (
+                      <li key={s.name} className="rounded-apple bg-surface-overlay px-3 py-2 text-sm">
+                        <label className="flex items-start gap-2">
+                          <input
+                            type="checkbox"
+                            className="mt-1"
+                            checked={selected.includes(s.name)}
+                            onChange={() => toggleSuggestion(s.name)}
+                          />
+                          <span>
+                            <span className="font-medium text-label-primary">{s.name}</span>
+                            <span className="text-label-secondary"> — {s.definition}</span>
+                            {s.examples.length > 0 && (
+                              <span className="mt-1 block text-label-tertiary">
+                                e.g. {s.examples.join("; ")}
+                              </span>
+                            )}
+                          </span>
+                        </label>
+                      </li>
+                    ))}
+                  </ul>
+                  <Button
+                    variant="secondary"
+                    onClick={addSelectedSuggestions}
+                    disabled={selected.length === 0}
+                  >
+                    Add {selected.length} selected
+                  </Button>
+                </div>
+              )}
               {traits.length > 0 && (
                 <ul className="flex flex-wrap gap-2">
                   {traits.map((t) => (
                     <li
-                      key={t}
+                      key={t.name}
+                      title={t.definition ?? undefined}
                       className="inline-flex items-center rounded-apple bg-surface-overlay px-3 py-1.5 text-sm"
                     >
-                      {t}
+                      {t.name}
                       <button
                         type="button"
-                        onClick={() => removeTrait(t)}
+                        onClick={() => removeTrait(t.name)}
                         className="ml-2 text-label-tertiary hover:text-label-primary"
-                        aria-label={`Remove ${t}`}
+                        aria-label={`Remove ${t.name}`}
                       >
                         ×
                       </button>
@@ -4,21 +4,48 @@ import Button from "@/components/ui/Button";
 import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/Card";
 import { useApp } from "@/lib/context";
 import * as tauri from "@/lib/tauri";
+import type {
+  DailyReflection,
+  ObservationFeedback,
+  Reaction,
+  ReflectionFeedback,
+  ReflectionFeedbackInput,
+  ReflectionMessage,
+  ReflectionPayload,
+} from "@/lib/types";
 import Link from "next/link";
 import { useSearchParams } from "next/navigation";
 import { Suspense, useCallback, useEffect, useState } from "react";
 
 function ReflectionContent() {
   const searchParams = useSearchParams();
-  const date = searchParams.get("date") ?? new Date().toISOString().slice(0, 10);
+  const dateParam = searchParams.get("date");
+  const [date, setDate] = useState<string | null>(dateParam);
   const identityIdParam = searchParams.get("identityId");
   const { currentIdentity, identities } = useApp();
   const identityId = identityIdParam ? Number(identityIdParam) : currentIdentity?.id;
-  const [content, setContent] = useState<string | null>(null);
+  const [reflection, setReflection] = useState<DailyReflection | null>(null);
+  const [versions, setVersions] = useState<DailyReflection[]>([]);
+  const [feedback, setFeedback] = useState<ReflectionFeedback | null>(null);
+  const [feedbackComment, setFeedbackComment] = useState("");
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
+  const [payload, setPayload] = useState<ReflectionPayload | null>(null);
+  const [messages, setMessages] = useState<ReflectionMessage[]>([]);
+  const [question, setQuestion] = useState("");
+  const [asking, setAsking] = useState(false);
+  const [committed, setCommitted] = useState(false);
+
+  useEffect(() => {
+    if (dateParam) return;
+    tauri
+      .getToday()
+      .then(setDate)
+      .catch((e) => setError(e instanceof Error ? e.message : String(e)));
+  }, [dateParam]);
 
   const load = useCallback(async () => {
+    if (!date) return;
     if (identityId == null) {
       setLoading(false);
       return;
@@ -26,8 +53,12 @@ function ReflectionContent() {
     setLoading(true);
     setError(null);
     try {
-      const r = await tauri.getReflectionForDate(identityId, date);
-      setContent(r?.content ?? null);
+      const [r, v] = await Promise.all([
+        tauri.getReflectionForDate(identityId, date),
+        tauri.listReflectionVersions(identityId, date),
+      ]);
+      setReflection(r);
+      setVersions(v);
     } catch (e) {
       setError(e instanceof Error ? e.message : String(e));
     } finally {
@@ -39,6 +70,97 @@ function ReflectionContent() {
     load();
   }, [load]);
 
+  const reflectionId = reflection?.id;
+  useEffect(() => {
+    setFeedback(null);
+    setFeedbackComment("");
+    if (reflectionId == null) return;
+    tauri
+      .getReflectionFeedback(reflectionId)
+      .then((f) => {
+        setFeedback(f);
+        setFeedbackComment(f?.comment ?? "");
+      })
+      .catch((e) => setError(e instanceof Error ? e.message : String(e)));
+  }, [reflectionId]);
+
+  useEffect(() => {
+    setCommitted(false);
+    setMessages([]);
+    if (reflectionId == null) return;
+    tauri
+      .listReflectionMessages(reflectionId)
+      .then(setMessages)
+      .catch((e) => setError(e instanceof Error ? e.message : String(e)));
+  }, [reflectionId]);
+
+  const ask = async () => {
+    if (reflectionId == null || !question.trim()) return;
+    setAsking(true);
+    try {
+      await tauri.chatWithReflection(reflectionId, question);
+      setQuestion("");
+      setMessages(await tauri.listReflectionMessages(reflectionId));
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    } finally {
+      setAsking(false);
+    }
+  };
+
+  const saveFeedback = async (patch: Partial<ReflectionFeedbackInput>) => {
+    if (reflectionId == null) return;
+    try {
+      setFeedback(
+        await tauri.setReflectionFeedback(reflectionId, {
+          rating: feedback?.rating ?? null,
+          comment: feedbackComment.trim() || null,
+          observations: feedback?.observations ?? [],
+          ...patch,
+        })
+      );
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    }
+  };
+
+  const react = (index: number, reaction: Reaction) => {
+    const current = feedback?.observations.find((o) => o.index === index);
+    const next: ObservationFeedback = {
+      index,
+      reaction: current?.reaction === reaction ? null : reaction,
+      comment: current?.comment ?? null,
+    };
+    saveFeedback({
+      observations: [
+        ...(feedback?.observations ?? []).filter((o) => o.index !== index),
+        next,
+      ],
+    });
+  };
+
+  const togglePayload = async () => {
+    if (payload) {
+      setPayload(null);
+      return;
+    }
+    if (identityId == null || !date) return;
+    try {
+      setPayload(await tauri.previewReflectionPayload(identityId, date));
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    }
+  };
+
+  const pin = async (id: number) => {
+    try {
+      await tauri.pinReflection(id);
+      await load();
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    }
+  };
+
   const identity = identityId
     ? identities.find((i) => i.id === identityId) ?? currentIdentity
     : currentIdentity;
@@ -83,11 +205,112 @@ function ReflectionContent() {
           </Card>
         )}
 
-        {!loading && !error && content && (
+        {!loading && !error && reflection && (
           <Card>
+            {reflection.reflection && (
+              <CardHeader>
+                <CardTitle>{reflection.reflection.title}</CardTitle>
+              </CardHeader>
+            )}
             <CardContent className="pt-6">
-              <div className="prose prose-sm max-w-none text-label-primary whitespace-pre-wrap">
-                {content}
+              {reflection.reflection ? (
+                <div className="space-y-4 text-sm text-label-primary">
+                  {reflection.local && (
+                    <p className="rounded-apple bg-surface-overlay px-3 py-2 text-xs text-label-secondary">
+                      Generated locally without AI
+                      {reflection.fallback_reason ? ": " + reflection.fallback_reason : ""}
+                    </p>
+                  )}
+                  {reflection.reflection.followUp &&
+                    reflection.reflection.followUp.status !== "no_previous" && (
+                      <p className="text-label-secondary">
+                        <span className="font-medium">Follow-up: </span>
+                        {reflection.reflection.followUp.note}
+                      </p>
+                    )}
+                  <p>{reflection.reflection.alignmentSummary}</p>
+                  <ul className="list-disc space-y-1 pl-5">
+                    {reflection.reflection.observations.map((o, i) => {
+                      const reaction = feedback?.observations.find(
+                        (f) => f.index === i
+                      )?.reaction;
+                      return (
+                        <li key={i}>
+                          {o}
+                          <span className="ml-2 inline-flex gap-2 text-xs">
+                            {(["resonated", "missed"] as const).map((r) => (
+                              <button
+                                key={r}
+                                type="button"
+                                onClick={() => react(i, r)}
+                                className={
+                                  reaction === r
+                                    ? "font-medium text-label-primary underline"
+                                    : "text-label-secondary hover:underline"
+                                }
+                              >
+                                {r === "resonated" ? "Resonated" : "Missed"}
+                              </button>
+                            ))}
+                          </span>
+                        </li>
+                      );
+                    })}
+                  </ul>
+                  <p>
+                    <span className="font-medium">Correction: </span>
+                    {reflection.reflection.identityCorrection}
+                    <button
+                      type="button"
+                      disabled={committed}
+                      onClick={() =>
+                        tauri
+                          .createCommitmentFromReflection(reflection.id)
+                          .then(() => setCommitted(true))
+                          .catch((e) => setError(e instanceof Error ? e.message : String(e)))
+                      }
+                      className="ml-2 text-xs text-label-secondary hover:underline"
+                    >
+                      {committed ? "Committed" : "Commit to this"}
+                    </button>
+                  </p>
+                  <p className="italic">{reflection.reflection.closingStatement}</p>
+                </div>
+              ) : (
+                <div className="prose prose-sm max-w-none text-label-primary whitespace-pre-wrap">
+                  {reflection.content}
+                </div>
+              )}
+              <div className="mt-6 space-y-2 border-t border-border pt-4 text-sm">
+                <div className="flex items-center gap-2">
+                  <span className="text-label-secondary">How useful was this?</span>
+                  {[1, 2, 3, 4, 5].map((n) => (
+                    <button
+                      key={n}
+                      type="button"
+                      onClick={() =>
+                        saveFeedback({ rating: feedback?.rating === n ? null : n })
+                      }
+                      className={
+                        "h-7 w-7 rounded-full border border-border " +
+                        (feedback?.rating === n ? "bg-neutral-900 text-white" : "")
+                      }
+                    >
+                      {n}
+                    </button>
+                  ))}
+                </div>
+                <div className="flex gap-2">
+                  <input
+                    value={feedbackComment}
+                    onChange={(e) => setFeedbackComment(e.target.value)}
+                    placeholder="What was off-base? (optional)"
+                    className="h-9 flex-1 rounded-apple border border-border bg-white px-3 text-sm"
+                  />
+                  <Button variant="ghost" onClick={() => saveFeedback({})}>
+                    Save
+                  </Button>
+                </div>
               </div>
               <div className="mt-6 flex gap-3">
                 <Link href="/dashboard">
@@ -108,7 +331,120 @@ function ReflectionContent() {
           </Card>
         )}
 
-        {!loading && !error && !content && (
+        {!loading && !error && reflection && (
+          <Card className="mt-6">
+            <CardHeader>
+              <CardTitle>Ask about this reflection</CardTitle>
+            </CardHeader>
+            <CardContent>
+              {messages.length > 0 && (
+                <div className="mb-4 space-y-3 text-sm">
+                  {messages.map((m) => (
+                    <p
+                      key={m.id}
+                      className={
+                        "whitespace-pre-wrap " +
+                        (m.role === "user" ? "font-medium" : "text-label-primary")
+                      }
+                    >
+                      {m.content}
+                    </p>
+                  ))}
+                </div>
+              )}
+              <div className="flex gap-2">
+                <input
+                  value={question}
+                  onChange={(e) => setQuestion(e.target.value)}
+                  onKeyDown={(e) => e.key === "Enter" && ask()}
+                  placeholder="Why did you say that? Plan for tomorrow?"
+                  className="h-9 flex-1 rounded-apple border border-border bg-white px-3 text-sm"
+                />
+                <Button onClick={ask} loading={asking} disabled={!question.trim()}>
+                  Ask
+                </Button>
+              </div>
+              {messages.length > 0 && (
+                <Button
+                  variant="ghost"
+                  className="mt-2"
+                  onClick={async () => {
+                    await tauri.clearReflectionMessages(reflection.id);
+                    setMessages([]);
+                  }}
+                >
+                  Clear conversation
+                </Button>
+              )}
+            </CardContent>
+          </Card>
+        )}
+
+        {!loading && !error && versions.length > 1 && (
+          <Card className="mt-6">
+            <CardHeader>
+              <CardTitle>Versions</CardTitle>
+            </CardHeader>
+            <CardContent>
+              <ul className="space-y-2 text-sm">
+                {versions.map((v) => (
+                  <li key={v.id} className="flex items-center justify-between gap-3">
+                    <button
+                      type="button"
+                      className="text-left hover:underline"
+                      onClick={() => setReflection(v)}
+                    >
+                      v{v.version}
+                      {v.model ? " · " + v.model : ""}
+                      {v.local ? " · local" : ""} · {v.created_at}
+                      {v.id === reflection?.id ? " (shown)" : ""}
+                    </button>
+                    {v.pinned ? (
+                      <span className="text-label-secondary">Pinned</span>
+                    ) : (
+                      <Button variant="ghost" onClick={() => pin(v.id)}>
+                        Pin
+                      </Button>
+                    )}
+                  </li>
+                ))}
+              </ul>
+            </CardContent>
+          </Card>
+        )}
+
+        {!loading && !error && identityId != null && (
+          <Card className="mt-6">
+            <CardHeader>
+              <CardTitle>What gets sent</CardTitle>
+            </CardHeader>
+            <CardContent>
+              <Button variant="ghost" onClick={togglePayload}>
+                {payload ? "Hide payload" : "Preview payload"}
+              </Button>
+              {payload && (
+                <div className="mt-4 space-y-4 text-sm">
+                  <p className="text-label-secondary">
+                    {payload.provider} · {payload.model}
+                  </p>
+                  {payload.replacements.length > 0 && (
+                    <ul className="space-y-1">
+                      {payload.replacements.map((r) => (
+                        <li key={r.placeholder}>
+                          <code>{r.placeholder}</code> stands for {r.original}
+                        </li>
+                      ))}
+                    </ul>
+                  )}
+                  <pre className="whitespace-pre-wrap text-xs">{payload.system}</pre>
+                  <pre className="whitespace-pre-wrap text-xs">{payload.user}</pre>
+                </div>
+              )}
+            </CardContent>
+          </Card>
+        )}
+
+        {!loading && !error && !reflection && (
           <Card>
             <CardContent className="py-10 text-center">
               <p className="text-label-secondary">
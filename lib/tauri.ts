@@ -1,4 +1,47 @@
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import type {
+  ActionSuggestions,
+  AiPrice,
+  AiUsage,
+  BackfillDay,
+  BackfillReport,
+  BehaviorLog,
+  Commitment,
+  CommitmentStats,
+  CommitmentStatus,
+  CredentialStatus,
+  DailyReflection,
+  DayAlignment,
+  FollowUpStats,
+  Identity,
+  ImportReport,
+  Language,
+  LlmConfig,
+  Metric,
+  Period,
+  PeriodReflection,
+  PlannedAction,
+  PlannedActionStatus,
+  PromptTemplate,
+  PromptTemplateInput,
+  PromptTemplateVersion,
+  ProviderKind,
+  QueueStatus,
+  QuickLogPreset,
+  RedactionKind,
+  RedactionRule,
+  ReflectionDiff,
+  ReflectionFeedback,
+  ReflectionFeedbackInput,
+  ReflectionMessage,
+  ReflectionPayload,
+  RenderedPrompt,
+  Settings,
+  Trait,
+  TraitSuggestion,
+  UsageRange,
+} from "./types";
 
 export async function createUser(name: string) {
   return invoke<{ id: number; name: string; created_at: string }>("create_user", {
@@ -14,48 +57,22 @@ export async function createIdentity(
   userId: number,
   input: { name: string; description?: string }
 ) {
-  return invoke<{
-    id: number;
-    name: string;
-    description: string;
-    user_id: number;
-    created_at: string;
-  }>("create_identity", { userId, input });
+  return invoke<Identity>("create_identity", { userId, input });
 }
 
 export async function listIdentities(userId: number) {
-  return invoke<
-    Array<{
-      id: number;
-      name: string;
-      description: string;
-      user_id: number;
-      created_at: string;
-    }>
-  >("list_identities", { userId });
+  return invoke<Identity[]>("list_identities", { userId });
 }
 
 export async function getIdentity(id: number) {
-  return invoke<{
-    id: number;
-    name: string;
-    description: string;
-    user_id: number;
-    created_at: string;
-  } | null>("get_identity", { id });
+  return invoke<Identity | null>("get_identity", { id });
 }
 
 export async function updateIdentity(
   id: number,
   updates: { name?: string; description?: string }
 ) {
-  return invoke<{
-    id: number;
-    name: string;
-    description: string;
-    user_id: number;
-    created_at: string;
-  }>("update_identity", {
+  return invoke<Identity>("update_identity", {
     id,
     name: updates.name ?? null,
     description: updates.description ?? null,
@@ -67,6 +84,7 @@ export async function createTrait(identityId: number, name: string) {
     id: number;
     name: string;
     identity_id: number;
+    definition: string | null;
     created_at: string;
   }>("create_trait", { identityId, name });
 }
@@ -77,6 +95,7 @@ export async function listTraits(identityId: number) {
       id: number;
       name: string;
       identity_id: number;
+      definition: string | null;
       created_at: string;
     }>
   >("list_traits", { identityId });
@@ -86,11 +105,41 @@ export async function deleteTrait(id: number) {
   return invoke<void>("delete_trait", { id });
 }
 
+export async function suggestTraits(identityId: number) {
+  return invoke<TraitSuggestion[]>("suggest_traits", { identityId });
+}
+
+export async function acceptTraitSuggestions(
+  identityId: number,
+  traits: Array<{ name: string; definition?: string | null }>
+) {
+  return invoke<Trait[]>("accept_trait_suggestions", { identityId, traits });
+}
+
+export async function createMetric(
+  identityId: number,
+  input: { name: string; unit: string; target: number; period: "daily" | "weekly" }
+) {
+  return invoke<Metric>("create_metric", { identityId, input });
+}
+
+export async function listMetrics(identityId: number) {
+  return invoke<Metric[]>("list_metrics", { identityId });
+}
+
+export async function deleteMetric(id: number) {
+  return invoke<void>("delete_metric", { id });
+}
+
 export async function logBehavior(input: {
   date: string;
   description: string;
   identity_id: number;
   alignment_score: number;
+  suggested_score?: number | null;
+  value?: number | null;
+  unit?: string | null;
+  trait_ids?: number[];
 }) {
   return invoke<{
     id: number;
@@ -98,10 +147,79 @@ export async function logBehavior(input: {
     description: string;
     identity_id: number;
     alignment_score: number;
+    suggested_score: number | null;
+    value: number | null;
+    unit: string | null;
+    trait_ids: number[];
     created_at: string;
   }>("log_behavior", { input });
 }
 
+export async function logBehaviors(
+  inputs: Array<{
+    date: string;
+    description: string;
+    identity_id: number;
+    alignment_score: number;
+    suggested_score?: number | null;
+    value?: number | null;
+    unit?: string | null;
+    trait_ids?: number[];
+  }>
+) {
+  return invoke<
+    Array<{
+      id: number;
+      date: string;
+      description: string;
+      identity_id: number;
+      alignment_score: number;
+      suggested_score: number | null;
+      value: number | null;
+      unit: string | null;
+      trait_ids: number[];
+      created_at: string;
+    }>
+  >("log_behaviors", { inputs });
+}
+
+export async function importBehaviors(input: {
+  path: string;
+  format?: "csv" | "json";
+  mapping?: {
+    date?: string;
+    description?: string;
+    score?: string;
+    identity?: string | null;
+    tags?: string | null;
+    value?: string | null;
+    unit?: string | null;
+  };
+  default_identity_id?: number | null;
+  dry_run?: boolean;
+}) {
+  return invoke<ImportReport>("import_behaviors", { input });
+}
+
+export async function parseBehaviors(
+  identityId: number,
+  date: string,
+  text: string
+) {
+  return invoke<
+    Array<{
+      date: string;
+      description: string;
+      identity_id: number;
+      alignment_score: number;
+      suggested_score: number | null;
+      value: number | null;
+      unit: string | null;
+      trait_ids: number[];
+    }>
+  >("parse_behaviors", { identityId, date, text });
+}
+
 export async function getBehaviorsForDate(
   identityId: number,
   date: string
@@ -113,6 +231,10 @@ export async function getBehaviorsForDate(
       description: string;
       identity_id: number;
       alignment_score: number;
+      suggested_score: number | null;
+      value: number | null;
+      unit: string | null;
+      trait_ids: number[];
       created_at: string;
     }>
   >("get_behaviors_for_date", { identityId, date });
@@ -130,6 +252,10 @@ export async function listBehaviorsForIdentity(
       description: string;
       identity_id: number;
       alignment_score: number;
+      suggested_score: number | null;
+      value: number | null;
+      unit: string | null;
+      trait_ids: number[];
       created_at: string;
     }>
   >("list_behaviors_for_identity", {
@@ -139,65 +265,418 @@ export async function listBehaviorsForIdentity(
   });
 }
 
+export async function suggestAlignment(
+  identityId: number,
+  description: string
+) {
+  return invoke<{
+    score: number;
+    traits: Array<{
+      id: number;
+      name: string;
+      identity_id: number;
+      created_at: string;
+    }>;
+    rationale: string;
+  }>("suggest_alignment", { identityId, description });
+}
+
+// Streams the reflection as it is generated. `onDelta` receives the text so
+// far; it restarts from scratch if the first reply had to be repaired.
 export async function generateReflection(
-  apiKey: string,
-  input: {
-    identity_id: number;
-    date: string;
-    identity_name: string;
-    identity_description: string;
-    traits: string[];
-    behaviors: Array<{ description: string; alignment_score: number }>;
+  identityId: number,
+  date: string,
+  options?: { requestId?: string; onDelta?: (text: string) => void }
+) {
+  const requestId = options?.requestId ?? crypto.randomUUID();
+  let attempt = 1;
+  let text = "";
+  const unlisten = await listen<{ request_id: string; attempt: number; text: string }>(
+    "reflection://delta",
+    (event) => {
+      if (event.payload.request_id !== requestId) return;
+      if (event.payload.attempt !== attempt) {
+        attempt = event.payload.attempt;
+        text = "";
+      }
+      text += event.payload.text;
+      options?.onDelta?.(text);
+    }
+  );
+  try {
+    return await invoke<DailyReflection>("generate_reflection", {
+      requestId,
+      identityId,
+      date,
+    });
+  } finally {
+    unlisten();
+  }
+}
+
+export async function cancelReflection(requestId: string) {
+  return invoke<boolean>("cancel_reflection", { requestId });
+}
+
+// Generates reflections for days in the range that have behaviors but none
+// yet. `onProgress` receives each finished day with the running count.
+export async function backfillReflections(
+  identityId: number,
+  from: string,
+  to: string,
+  options?: {
+    requestId?: string;
+    concurrency?: number;
+    onProgress?: (day: BackfillDay, completed: number, total: number) => void;
   }
 ) {
-  return invoke<{
-    id: number;
-    date: string;
-    content: string;
-    identity_id: number;
-    created_at: string;
-  }>("generate_reflection", { apiKey, input });
+  const requestId = options?.requestId ?? crypto.randomUUID();
+  const unlisten = await listen<{
+    request_id: string;
+    day: BackfillDay;
+    completed: number;
+    total: number;
+  }>("backfill://progress", (event) => {
+    if (event.payload.request_id !== requestId) return;
+    options?.onProgress?.(event.payload.day, event.payload.completed, event.payload.total);
+  });
+  try {
+    return await invoke<BackfillReport>("backfill_reflections", {
+      requestId,
+      identityId,
+      from,
+      to,
+      concurrency: options?.concurrency ?? null,
+    });
+  } finally {
+    unlisten();
+  }
+}
+
+export async function cancelBackfill(requestId: string) {
+  return invoke<boolean>("cancel_backfill", { requestId });
 }
 
 export async function getReflectionForDate(
   identityId: number,
   date: string
 ) {
-  return invoke<{
-    id: number;
-    date: string;
-    content: string;
-    identity_id: number;
-    created_at: string;
-  } | null>("get_reflection_for_date", { identityId, date });
+  return invoke<DailyReflection | null>("get_reflection_for_date", { identityId, date });
 }
 
 export async function listReflections(
   identityId: number,
   limit?: number
 ) {
-  return invoke<
-    Array<{
-      id: number;
-      date: string;
-      content: string;
-      identity_id: number;
-      created_at: string;
-    }>
-  >("list_reflections", { identityId, limit: limit ?? null });
+  return invoke<DailyReflection[]>("list_reflections", {
+    identityId,
+    limit: limit ?? null,
+  });
+}
+
+export async function listReflectionVersions(identityId: number, date: string) {
+  return invoke<DailyReflection[]>("list_reflection_versions", {
+    identityId,
+    date,
+  });
+}
+
+export async function pinReflection(id: number) {
+  return invoke<DailyReflection>("pin_reflection", { id });
+}
+
+export async function diffReflections(fromId: number, toId: number) {
+  return invoke<ReflectionDiff>("diff_reflections", { fromId, toId });
+}
+
+export async function generatePeriodReflection(
+  identityId: number,
+  period: Period,
+  start: string
+) {
+  return invoke<PeriodReflection>("generate_period_reflection", {
+    identityId,
+    period,
+    start,
+  });
+}
+
+export async function getPeriodReflection(
+  identityId: number,
+  period: Period,
+  start: string
+) {
+  return invoke<PeriodReflection | null>("get_period_reflection", {
+    identityId,
+    period,
+    start,
+  });
+}
+
+export async function listPeriodReflections(
+  identityId: number,
+  period?: Period,
+  limit?: number
+) {
+  return invoke<PeriodReflection[]>("list_period_reflections", {
+    identityId,
+    period: period ?? null,
+    limit: limit ?? null,
+  });
+}
+
+export async function setReflectionFeedback(
+  reflectionId: number,
+  input: ReflectionFeedbackInput
+) {
+  return invoke<ReflectionFeedback>("set_reflection_feedback", {
+    reflectionId,
+    input,
+  });
+}
+
+export async function getReflectionFeedback(reflectionId: number) {
+  return invoke<ReflectionFeedback | null>("get_reflection_feedback", {
+    reflectionId,
+  });
+}
+
+export async function deleteReflectionFeedback(reflectionId: number) {
+  return invoke<void>("delete_reflection_feedback", { reflectionId });
+}
+
+export async function listReflectionFeedback(
+  identityId: number,
+  options?: { negativeOnly?: boolean; limit?: number }
+) {
+  return invoke<ReflectionFeedback[]>("list_reflection_feedback", {
+    identityId,
+    negativeOnly: options?.negativeOnly ?? null,
+    limit: options?.limit ?? null,
+  });
+}
+
+export async function listPromptTemplates() {
+  return invoke<PromptTemplate[]>("list_prompt_templates");
+}
+
+export async function getDefaultPromptTemplate() {
+  return invoke<PromptTemplateInput>("get_default_prompt_template");
+}
+
+export async function createPromptTemplate(input: PromptTemplateInput) {
+  return invoke<PromptTemplate>("create_prompt_template", { input });
+}
+
+export async function updatePromptTemplate(id: number, input: PromptTemplateInput) {
+  return invoke<PromptTemplate>("update_prompt_template", { id, input });
+}
+
+export async function getPromptTemplateVersion(templateId: number, version: number) {
+  return invoke<PromptTemplateVersion>("get_prompt_template_version", {
+    templateId,
+    version,
+  });
+}
+
+export async function deletePromptTemplate(id: number) {
+  return invoke<void>("delete_prompt_template", { id });
+}
+
+export async function previewPromptTemplate(
+  identityId: number,
+  date: string,
+  input: PromptTemplateInput
+) {
+  return invoke<RenderedPrompt>("preview_prompt_template", {
+    identityId,
+    date,
+    input,
+  });
+}
+
+export async function listRedactionRules() {
+  return invoke<RedactionRule[]>("list_redaction_rules");
+}
+
+export async function addRedactionRule(input: {
+  kind: RedactionKind;
+  label: string;
+  value: string;
+}) {
+  return invoke<RedactionRule>("add_redaction_rule", { input });
+}
+
+export async function deleteRedactionRule(id: number) {
+  return invoke<void>("delete_redaction_rule", { id });
+}
+
+export async function previewReflectionPayload(identityId: number, date: string) {
+  return invoke<ReflectionPayload>("preview_reflection_payload", {
+    identityId,
+    date,
+  });
+}
+
+export async function chatWithReflection(reflectionId: number, message: string) {
+  return invoke<ReflectionMessage>("chat_with_reflection", {
+    reflectionId,
+    message,
+  });
+}
+
+export async function listReflectionMessages(reflectionId: number) {
+  return invoke<ReflectionMessage[]>("list_reflection_messages", { reflectionId });
+}
+
+export async function clearReflectionMessages(reflectionId: number) {
+  return invoke<void>("clear_reflection_messages", { reflectionId });
+}
+
+export async function setIdentityLanguage(identityId: number, language: string | null) {
+  return invoke<Identity>("set_identity_language", { identityId, language });
+}
+
+export async function setIdentityPromptTemplate(
+  identityId: number,
+  templateId: number | null
+) {
+  return invoke<Identity>("set_identity_prompt_template", {
+    identityId,
+    templateId,
+  });
+}
+
+export async function getFollowUpStats(identityId: number, days?: number) {
+  return invoke<FollowUpStats>("get_follow_up_stats", {
+    identityId,
+    days: days ?? null,
+  });
+}
+
+export async function createCommitment(input: {
+  identity_id: number;
+  text: string;
+  due_date: string;
+}) {
+  return invoke<Commitment>("create_commitment", { input });
+}
+
+export async function createCommitmentFromReflection(
+  reflectionId: number,
+  dueDate?: string
+) {
+  return invoke<Commitment>("create_commitment_from_reflection", {
+    reflectionId,
+    dueDate: dueDate ?? null,
+  });
+}
+
+export async function listCommitments(identityId: number, status?: CommitmentStatus) {
+  return invoke<Commitment[]>("list_commitments", {
+    identityId,
+    status: status ?? null,
+  });
+}
+
+export async function resolveCommitment(
+  id: number,
+  status: CommitmentStatus,
+  note?: string
+) {
+  return invoke<Commitment>("resolve_commitment", {
+    id,
+    status,
+    note: note ?? null,
+  });
+}
+
+export async function linkCommitmentBehaviors(id: number, behaviorIds: number[]) {
+  return invoke<Commitment>("link_commitment_behaviors", { id, behaviorIds });
+}
+
+export async function deleteCommitment(id: number) {
+  return invoke<void>("delete_commitment", { id });
+}
+
+export async function getCommitmentStats(identityId: number, weeks?: number) {
+  return invoke<CommitmentStats>("get_commitment_stats", {
+    identityId,
+    weeks: weeks ?? null,
+  });
+}
+
+export async function suggestActions(identityId: number, date: string) {
+  return invoke<ActionSuggestions>("suggest_actions", { identityId, date });
+}
+
+export async function planAction(input: {
+  identity_id: number;
+  date: string;
+  description: string;
+  reason?: string | null;
+  trait_ids?: number[];
+  expected_score?: number | null;
+}) {
+  return invoke<PlannedAction>("plan_action", { input });
+}
+
+export async function listPlannedActions(identityId: number, date: string) {
+  return invoke<PlannedAction[]>("list_planned_actions", { identityId, date });
+}
+
+export async function completePlannedAction(id: number, alignmentScore?: number) {
+  return invoke<{ action: PlannedAction; behavior: BehaviorLog }>(
+    "complete_planned_action",
+    { id, alignmentScore: alignmentScore ?? null }
+  );
+}
+
+export async function setPlannedActionStatus(
+  id: number,
+  status: Exclude<PlannedActionStatus, "done">
+) {
+  return invoke<PlannedAction>("set_planned_action_status", { id, status });
+}
+
+export async function deletePlannedAction(id: number) {
+  return invoke<void>("delete_planned_action", { id });
+}
+
+export async function createQuickLogPreset(input: {
+  identity_id: number;
+  description: string;
+  alignment_score: number;
+  trait_ids?: number[];
+}) {
+  return invoke<QuickLogPreset>("create_quick_log_preset", { input });
+}
+
+export async function listQuickLogPresets(identityId: number) {
+  return invoke<QuickLogPreset[]>("list_quick_log_presets", { identityId });
+}
+
+export async function deleteQuickLogPreset(id: number) {
+  return invoke<void>("delete_quick_log_preset", { id });
+}
+
+export async function logQuickLogPreset(id: number, date: string) {
+  return invoke<BehaviorLog>("log_quick_log_preset", { id, date });
+}
+
+export async function getToday() {
+  return invoke<string>("get_today");
 }
 
 export async function getWeeklyAlignment(
   identityId: number,
-  fromDate: string,
-  toDate: string
+  fromDate?: string,
+  toDate?: string
 ) {
-  return invoke<
-    Array<{ date: string; avg_score: number; count: number }>
-  >("get_weekly_alignment", {
+  return invoke<DayAlignment[]>("get_weekly_alignment", {
     identityId,
-    fromDate,
-    toDate,
+    fromDate: fromDate ?? null,
+    toDate: toDate ?? null,
   });
 }
 
@@ -213,3 +692,118 @@ export async function getAlignmentTrends(
     }>
   >("get_alignment_trends", { identityId, days: days ?? null });
 }
+
+export async function getSettings() {
+  return invoke<Settings>("get_settings");
+}
+
+export async function listLanguages() {
+  return invoke<Language[]>("list_languages");
+}
+
+export async function updateSettings(input: {
+  timezone?: string | null;
+  day_end_hour?: number | null;
+  feedback_in_prompt?: boolean | null;
+  redact_builtin?: boolean | null;
+  language?: string | null;
+  allow_file_credentials?: boolean | null;
+}) {
+  return invoke<Settings>("update_settings", {
+    input: {
+      timezone: input.timezone ?? null,
+      day_end_hour: input.day_end_hour ?? null,
+      feedback_in_prompt: input.feedback_in_prompt ?? null,
+      redact_builtin: input.redact_builtin ?? null,
+      language: input.language ?? null,
+      allow_file_credentials: input.allow_file_credentials ?? null,
+    },
+  });
+}
+
+export async function getLlmConfig(identityId?: number) {
+  return invoke<LlmConfig | null>("get_llm_config", {
+    identityId: identityId ?? null,
+  });
+}
+
+export async function getEffectiveLlmConfig(identityId?: number) {
+  return invoke<LlmConfig>("get_effective_llm_config", {
+    identityId: identityId ?? null,
+  });
+}
+
+export async function setLlmConfig(config: LlmConfig, identityId?: number) {
+  return invoke<LlmConfig>("set_llm_config", {
+    identityId: identityId ?? null,
+    config,
+  });
+}
+
+export async function clearLlmConfig(identityId?: number) {
+  return invoke<void>("clear_llm_config", { identityId: identityId ?? null });
+}
+
+export async function setApiKey(provider: ProviderKind, apiKey: string) {
+  return invoke<CredentialStatus>("set_api_key", { provider, apiKey });
+}
+
+export async function getApiKeyStatus(provider: ProviderKind) {
+  return invoke<CredentialStatus>("get_api_key_status", { provider });
+}
+
+export async function deleteApiKey(provider: ProviderKind) {
+  return invoke<void>("delete_api_key", { provider });
+}
+
+export async function testAiConnection(identityId?: number) {
+  return invoke<void>("test_ai_connection", { identityId: identityId ?? null });
+}
+
+export async function getReflectionQueue() {
+  return invoke<QueueStatus>("get_reflection_queue");
+}
+
+export async function processReflectionQueue() {
+  return invoke<QueueStatus>("process_reflection_queue");
+}
+
+export async function retryQueuedReflection(id: number) {
+  return invoke<QueueStatus>("retry_queued_reflection", { id });
+}
+
+export async function removeQueuedReflection(id: number) {
+  return invoke<QueueStatus>("remove_queued_reflection", { id });
+}
+
+// Calls `onChange` whenever the offline queue changes; returns the unlisten
+// function.
+export async function onReflectionQueueChanged(onChange: (status: QueueStatus) => void) {
+  return listen<QueueStatus>("reflection-queue://changed", (event) =>
+    onChange(event.payload)
+  );
+}
+
+export async function getAiUsage(range: UsageRange) {
+  return invoke<AiUsage>("get_ai_usage", { range });
+}
+
+export async function listAiPrices() {
+  return invoke<AiPrice[]>("list_ai_prices");
+}
+
+export async function setAiPrice(price: AiPrice) {
+  return invoke<void>("set_ai_price", {
+    model: price.model,
+    promptPerMillion: price.prompt_per_million,
+    completionPerMillion: price.completion_per_million,
+  });
+}
+
+export async function deleteAiPrice(model: string) {
+  return invoke<void>("delete_ai_price", { model });
+}
+
+export async function setAiBudget(monthlyUsd: number | null) {
+  return invoke<void>("set_ai_budget", { monthlyUsd });
+}
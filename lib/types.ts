@@ -9,6 +9,8 @@ export interface Identity {
   name: string;
   description: string;
   user_id: number;
+  prompt_template_id: number | null;
+  language: string | null;
   created_at: string;
 }
 
@@ -16,30 +18,191 @@ export interface Trait {
   id: number;
   name: string;
   identity_id: number;
+  definition: string | null;
   created_at: string;
 }
 
+export interface TraitSuggestion {
+  name: string;
+  definition: string;
+  examples: string[];
+}
+
 export interface BehaviorLog {
   id: number;
   date: string;
   description: string;
   identity_id: number;
   alignment_score: number;
+  suggested_score: number | null;
+  value: number | null;
+  unit: string | null;
+  trait_ids: number[];
   created_at: string;
 }
 
+export interface LogBehaviorInput {
+  date: string;
+  description: string;
+  identity_id: number;
+  alignment_score: number;
+  suggested_score: number | null;
+  value: number | null;
+  unit: string | null;
+  trait_ids: number[];
+}
+
+export interface AlignmentSuggestion {
+  score: number;
+  traits: Trait[];
+  rationale: string;
+}
+
+export interface Reflection {
+  title: string;
+  alignmentSummary: string;
+  observations: string[];
+  identityCorrection: string;
+  closingStatement: string;
+  followUp?: FollowUp | null;
+}
+
+export interface FollowUp {
+  status: "acted" | "partial" | "not_acted" | "no_previous";
+  note: string;
+}
+
+export interface FollowUpStats {
+  acted: number;
+  partial: number;
+  not_acted: number;
+  follow_through: number | null;
+}
+
 export interface DailyReflection {
   id: number;
   date: string;
   content: string;
   identity_id: number;
+  reflection: Reflection | null;
+  version: number;
+  model: string | null;
+  prompt_hash: string | null;
+  pinned: boolean;
+  prompt_template_id: number | null;
+  prompt_template_version: number | null;
+  local: boolean;
+  fallback_reason: string | null;
+  created_at: string;
+}
+
+export interface PromptTemplate {
+  id: number;
+  name: string;
+  system_template: string;
+  user_template: string;
+  version: number;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface PromptTemplateVersion {
+  template_id: number;
+  version: number;
+  name: string;
+  system_template: string;
+  user_template: string;
+  created_at: string;
+}
+
+export interface PromptTemplateInput {
+  name: string;
+  system_template: string;
+  user_template: string;
+}
+
+export interface RenderedPrompt {
+  system: string;
+  user: string;
+}
+
+export type RedactionKind = "term" | "pattern";
+
+export interface RedactionRule {
+  id: number;
+  kind: RedactionKind;
+  label: string;
+  value: string;
+  created_at: string;
+}
+
+export interface Replacement {
+  placeholder: string;
+  original: string;
+}
+
+export interface ReflectionPayload {
+  provider: string;
+  model: string;
+  system: string;
+  user: string;
+  prompt_hash: string;
+  replacements: Replacement[];
+}
+
+export interface ReflectionMessage {
+  id: number;
+  reflection_id: number;
+  role: "user" | "assistant";
+  content: string;
+  model: string | null;
+  created_at: string;
+}
+
+export interface DiffSegment {
+  tag: "equal" | "delete" | "insert";
+  text: string;
+}
+
+export interface FieldDiff {
+  field: string;
+  changed: boolean;
+  segments: DiffSegment[];
+}
+
+export interface ReflectionDiff {
+  from: DailyReflection;
+  to: DailyReflection;
+  same_prompt: boolean;
+  fields: FieldDiff[];
+}
+
+export interface Metric {
+  id: number;
+  name: string;
+  unit: string;
+  target: number;
+  period: "daily" | "weekly";
+  identity_id: number;
   created_at: string;
 }
 
+export interface MetricProgress {
+  metric_id: number;
+  name: string;
+  unit: string;
+  period: "daily" | "weekly";
+  target: number;
+  total: number;
+  period_total: number;
+  attainment: number;
+}
+
 export interface DayAlignment {
   date: string;
   avg_score: number;
   count: number;
+  metrics: MetricProgress[];
 }
 
 export interface AlignmentTrend {
@@ -47,3 +210,232 @@ export interface AlignmentTrend {
   avg_alignment: number;
   behavior_count: number;
 }
+
+export interface Settings {
+  timezone: string | null;
+  day_end_hour: number;
+  feedback_in_prompt: boolean;
+  redact_builtin: boolean;
+  language: string;
+  allow_file_credentials: boolean;
+}
+
+export interface Language {
+  code: string;
+  name: string;
+}
+
+export type Reaction = "resonated" | "missed";
+
+export interface ObservationFeedback {
+  index: number;
+  reaction: Reaction | null;
+  comment: string | null;
+}
+
+export interface ReflectionFeedback {
+  id: number;
+  reflection_id: number;
+  date: string;
+  identity_id: number;
+  rating: number | null;
+  comment: string | null;
+  observations: ObservationFeedback[];
+  created_at: string;
+  updated_at: string;
+}
+
+export interface ReflectionFeedbackInput {
+  rating: number | null;
+  comment: string | null;
+  observations: ObservationFeedback[];
+}
+
+export interface ImportRowResult {
+  line: number;
+  status: "created" | "skipped" | "rejected";
+  message: string | null;
+}
+
+export interface ImportReport {
+  dry_run: boolean;
+  created: number;
+  skipped: number;
+  rejected: number;
+  rows: ImportRowResult[];
+}
+
+export type ProviderKind = "openai" | "openai_compatible" | "anthropic";
+
+export interface LlmConfig {
+  provider: ProviderKind;
+  model: string;
+  temperature: number;
+  base_url: string | null;
+}
+
+export interface CredentialStatus {
+  provider: ProviderKind;
+  configured: boolean;
+  store: "keyring" | "file" | null;
+}
+
+export type Period = "week" | "month";
+
+export interface PeriodReport {
+  title: string;
+  wins: string[];
+  misalignments: string[];
+  trend: "improving" | "stable" | "declining";
+  trendSummary: string;
+  focus: string;
+}
+
+export interface PeriodReflection {
+  id: number;
+  identity_id: number;
+  period: Period;
+  start_date: string;
+  end_date: string;
+  report: PeriodReport;
+  average_alignment: number | null;
+  model: string | null;
+  created_at: string;
+}
+
+export type UsageRange = "today" | "week" | "month" | "all";
+
+export interface ModelUsage {
+  provider: ProviderKind;
+  model: string;
+  calls: number;
+  prompt_tokens: number;
+  completion_tokens: number;
+  cost: number;
+}
+
+export interface AiUsage {
+  from_date: string | null;
+  to_date: string;
+  calls: number;
+  failed_calls: number;
+  prompt_tokens: number;
+  completion_tokens: number;
+  cost: number;
+  unpriced_calls: number;
+  average_latency_ms: number | null;
+  by_model: ModelUsage[];
+  monthly_budget: number | null;
+  month_spent: number;
+}
+
+export interface AiPrice {
+  model: string;
+  prompt_per_million: number;
+  completion_per_million: number;
+}
+
+export interface QueuedReflection {
+  id: number;
+  identity_id: number;
+  date: string;
+  status: "pending" | "processing" | "done" | "failed";
+  attempts: number;
+  last_error: string | null;
+  reflection_id: number | null;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface ActionSuggestion {
+  description: string;
+  reason: string;
+  traits: Trait[];
+  expected_score: number;
+  minutes: number | null;
+}
+
+export interface ActionSuggestions {
+  date: string;
+  actions: ActionSuggestion[];
+}
+
+export type PlannedActionStatus = "planned" | "done" | "skipped";
+
+export interface PlannedAction {
+  id: number;
+  identity_id: number;
+  date: string;
+  description: string;
+  reason: string | null;
+  trait_ids: number[];
+  expected_score: number | null;
+  status: PlannedActionStatus;
+  behavior_id: number | null;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface QuickLogPreset {
+  id: number;
+  identity_id: number;
+  description: string;
+  alignment_score: number;
+  trait_ids: number[];
+  created_at: string;
+}
+
+export type CommitmentStatus = "open" | "kept" | "broken";
+
+export interface Commitment {
+  id: number;
+  identity_id: number;
+  reflection_id: number | null;
+  text: string;
+  due_date: string;
+  status: CommitmentStatus;
+  note: string | null;
+  behavior_ids: number[];
+  resolved_at: string | null;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface CommitmentWeek {
+  week_start: string;
+  kept: number;
+  broken: number;
+  open: number;
+  kept_rate: number | null;
+}
+
+export interface CommitmentStats {
+  kept: number;
+  broken: number;
+  open: number;
+  overdue: number;
+  kept_rate: number | null;
+  weeks: CommitmentWeek[];
+}
+
+export interface BackfillDay {
+  date: string;
+  status: "done" | "failed" | "skipped";
+  reflection_id: number | null;
+  error: string | null;
+}
+
+export interface BackfillReport {
+  total: number;
+  succeeded: number;
+  failed: number;
+  cancelled: boolean;
+  stopped: string | null;
+  days: BackfillDay[];
+}
+
+export interface QueueStatus {
+  pending: number;
+  failed: number;
+  items: QueuedReflection[];
+}
@@ -0,0 +1,473 @@
+use chrono::{Duration, NaiveDate};
+use rusqlite::{Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::commands::behavior::{
+    get_behavior_by_id, id_list, insert_behavior, match_traits, BehaviorLog, LogBehaviorInput,
+};
+use crate::commands::identity::{get_identity_by_id, Identity};
+use crate::commands::redaction::redactor;
+use crate::commands::reflection::CURRENT_VERSION;
+use crate::commands::trait_::{traits_for_identity, Trait};
+use crate::{dates, db, llm};
+
+// How far back neglected traits and low scores are looked for.
+const LOOKBACK_DAYS: i64 = 14;
+
+// Scores at or below this count as a low-scoring pattern.
+const LOW_SCORE: i32 = 4;
+
+const LOW_SCORE_EXAMPLES: i64 = 10;
+const UNKEPT_EXAMPLES: i64 = 5;
+
+const STATUSES: [&str; 3] = ["planned", "done", "skipped"];
+
+const SUGGEST_ACTIONS_INSTRUCTION: &str = r#"You are a psychologically intelligent identity performance coach.
+Suggest 3 to 5 concrete, small behaviors for tomorrow that move the user toward their identity.
+Favor neglected traits, counter the recent low-scoring patterns, and help keep unkept commitments.
+Each behavior must be doable in one sitting and specific enough to check off. Only name traits from the provided list.
+
+Respond with valid JSON only, in this exact structure:
+{
+  "actions": [
+    { "description": "string", "reason": "one sentence", "traits": ["trait name"], "expectedScore": 8, "minutes": 20 }
+  ]
+}"#;
+
+fn actions_schema() -> serde_json::Value {
+    let text = serde_json::json!({ "type": "string" });
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "actions": {
+                "type": "array",
+                "minItems": 3,
+                "maxItems": 5,
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "description": text,
+                        "reason": text,
+                        "traits": { "type": "array", "items": text },
+                        "expectedScore": { "type": "integer", "minimum": 1, "maximum": 10 },
+                        "minutes": { "type": "integer", "minimum": 1 }
+                    },
+                    "required": ["description", "reason", "traits", "expectedScore", "minutes"],
+                    "additionalProperties": false
+                }
+            }
+        },
+        "required": ["actions"],
+        "additionalProperties": false
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct RawSuggestions {
+    actions: Vec<RawSuggestion>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawSuggestion {
+    description: String,
+    reason: String,
+    #[serde(default)]
+    traits: Vec<String>,
+    expected_score: i32,
+    #[serde(default)]
+    minutes: Option<i32>,
+}
+
+impl RawSuggestions {
+    fn validate(&self) -> Result<(), String> {
+        if !(3..=5).contains(&self.actions.len()) {
+            return Err(format!("expected 3 to 5 actions, got {}", self.actions.len()));
+        }
+        for a in &self.actions {
+            if a.description.trim().is_empty() {
+                return Err("an action has an empty description".to_string());
+            }
+            if !(1..=10).contains(&a.expected_score) {
+                return Err(format!("expectedScore {} is outside 1–10", a.expected_score));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ActionSuggestion {
+    pub description: String,
+    pub reason: String,
+    // Traits the action would demonstrate, from the identity's own traits.
+    pub traits: Vec<Trait>,
+    pub expected_score: i32,
+    pub minutes: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ActionSuggestions {
+    // The day the actions are for: the day after the requested date.
+    pub date: String,
+    pub actions: Vec<ActionSuggestion>,
+}
+
+// Traits with no tagged behavior in the lookback window.
+fn neglected_traits<'a>(
+    conn: &Connection,
+    traits: &'a [Trait],
+    from: &str,
+    to: &str,
+) -> Result<Vec<&'a Trait>, String> {
+    let mut out = Vec::new();
+    for t in traits {
+        let used: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM behavior_trait bt JOIN behavior_log b ON b.id = bt.behavior_id \
+                 WHERE bt.trait_id = ?1 AND b.date BETWEEN ?2 AND ?3",
+                (t.id, from, to),
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if used == 0 {
+            out.push(t);
+        }
+    }
+    Ok(out)
+}
+
+fn lines(conn: &Connection, sql: &str, params: impl rusqlite::Params) -> Result<Vec<String>, String> {
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params, |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(out)
+}
+
+fn or_none(lines: Vec<String>) -> String {
+    if lines.is_empty() {
+        "(none)".to_string()
+    } else {
+        lines.join("\n")
+    }
+}
+
+fn suggestion_prompt(conn: &Connection, identity: &Identity, traits: &[Trait], date: NaiveDate) -> Result<String, String> {
+    let from = dates::format_date(date - Duration::days(LOOKBACK_DAYS - 1));
+    let to = dates::format_date(date);
+    let neglected = neglected_traits(conn, traits, &from, &to)?;
+    let low = lines(
+        conn,
+        "SELECT '- ' || date || ': ' || description || ' (' || alignment_score || '/10)' FROM behavior_log \
+         WHERE identity_id = ?1 AND date BETWEEN ?2 AND ?3 AND alignment_score <= ?4 \
+         ORDER BY date DESC, id DESC LIMIT ?5",
+        (identity.id, &from, &to, LOW_SCORE, LOW_SCORE_EXAMPLES),
+    )?;
+    let mut unkept = lines(
+        conn,
+        "SELECT '- ' || text || ' (due ' || due_date || ', ' || status || ')' FROM commitment \
+         WHERE identity_id = ?1 AND (status = 'broken' OR (status = 'open' AND due_date <= ?2)) \
+         ORDER BY due_date DESC LIMIT ?3",
+        (identity.id, &to, UNKEPT_EXAMPLES),
+    )?;
+    // The most recent correction, whether or not it has been acted on yet.
+    let latest: Option<(String, String)> = conn
+        .query_row(
+            &format!(
+                "SELECT date, identity_correction FROM daily_reflection WHERE identity_id = ?1 AND date <= ?2 \
+                 AND identity_correction IS NOT NULL AND {} ORDER BY date DESC LIMIT 1",
+                CURRENT_VERSION
+            ),
+            (identity.id, &to),
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if let Some((day, correction)) = latest {
+        unkept.push(format!("- Latest identity correction ({}): {}", day, correction));
+    }
+
+    Ok(format!(
+        "Identity: {}\nDescription: {}\nTraits: {}\n\nTomorrow: {}\n\nNeglected traits (no behavior in the last {} days): {}\n\n\
+         Recent low-scoring behaviors:\n{}\n\nUnkept commitments and corrections:\n{}\n",
+        identity.name,
+        identity.description,
+        traits.iter().map(|t| t.name.as_str()).collect::<Vec<_>>().join(", "),
+        dates::format_date(date + Duration::days(1)),
+        LOOKBACK_DAYS,
+        if neglected.is_empty() {
+            "(none)".to_string()
+        } else {
+            neglected.iter().map(|t| t.name.as_str()).collect::<Vec<_>>().join(", ")
+        },
+        or_none(low),
+        or_none(unkept),
+    ))
+}
+
+// Asks for 3–5 small behaviors for the day after `date`, drawing on neglected
+// traits, recent low scores and unkept commitments.
+#[tauri::command]
+pub async fn suggest_actions(app: AppHandle, identity_id: i64, date: String) -> Result<ActionSuggestions, String> {
+    let day = dates::parse_date(&date)?;
+    let (traits, request, redactor, provider) = {
+        let conn = db::get_conn(&app)?;
+        let identity = get_identity_by_id(&conn, identity_id)?;
+        let traits = traits_for_identity(&conn, identity_id)?;
+        let prompt = suggestion_prompt(&conn, &identity, &traits, day)?;
+        let mut redactor = redactor(&conn)?;
+        let request = llm::ChatRequest::new(SUGGEST_ACTIONS_INSTRUCTION, redactor.redact(&prompt))
+            .json_schema("action_suggestions", actions_schema());
+        let provider = llm::provider_for_identity(&app, &conn, identity_id)?;
+        (traits, request, redactor, provider)
+    };
+
+    let (raw, _) =
+        llm::complete_json::<RawSuggestions, _>(provider.as_ref(), &request, RawSuggestions::validate, &mut |_, _| {})
+            .await?;
+    Ok(ActionSuggestions {
+        date: dates::format_date(day + Duration::days(1)),
+        actions: raw
+            .actions
+            .into_iter()
+            .map(|a| ActionSuggestion {
+                description: redactor.restore(a.description.trim()),
+                reason: redactor.restore(a.reason.trim()),
+                traits: match_traits(&traits, &a.traits).cloned().collect(),
+                expected_score: a.expected_score,
+                minutes: a.minutes.filter(|m| *m > 0),
+            })
+            .collect(),
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlannedAction {
+    pub id: i64,
+    pub identity_id: i64,
+    pub date: String,
+    pub description: String,
+    pub reason: Option<String>,
+    pub trait_ids: Vec<i64>,
+    pub expected_score: Option<i32>,
+    // "planned", "done" or "skipped".
+    pub status: String,
+    // The behavior logged when the action was done.
+    pub behavior_id: Option<i64>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlanActionInput {
+    pub identity_id: i64,
+    pub date: String,
+    pub description: String,
+    #[serde(default)]
+    pub reason: Option<String>,
+    #[serde(default)]
+    pub trait_ids: Vec<i64>,
+    #[serde(default)]
+    pub expected_score: Option<i32>,
+}
+
+const PLANNED_COLUMNS: &str = "id, identity_id, date, description, reason, \
+    (SELECT GROUP_CONCAT(trait_id) FROM planned_action_trait WHERE planned_action_id = planned_action.id), \
+    expected_score, status, behavior_id, created_at, updated_at";
+
+fn planned_from_row(row: &Row) -> rusqlite::Result<PlannedAction> {
+    Ok(PlannedAction {
+        id: row.get(0)?,
+        identity_id: row.get(1)?,
+        date: row.get(2)?,
+        description: row.get(3)?,
+        reason: row.get(4)?,
+        trait_ids: id_list(row.get(5)?),
+        expected_score: row.get(6)?,
+        status: row.get(7)?,
+        behavior_id: row.get(8)?,
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
+    })
+}
+
+fn get_planned_by_id(conn: &Connection, id: i64) -> Result<PlannedAction, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM planned_action WHERE id = ?1", PLANNED_COLUMNS),
+        [id],
+        planned_from_row,
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => format!("Planned action {} not found", id),
+        e => e.to_string(),
+    })
+}
+
+// Adds an action to a day's plan, e.g. from a suggestion.
+#[tauri::command]
+pub fn plan_action(app: AppHandle, input: PlanActionInput) -> Result<PlannedAction, String> {
+    let description = input.description.trim();
+    if description.is_empty() {
+        return Err("description is required".to_string());
+    }
+    dates::parse_date(&input.date)?;
+    if matches!(input.expected_score, Some(s) if !(1..=10).contains(&s)) {
+        return Err("expected_score must be between 1 and 10".to_string());
+    }
+    let mut conn = db::get_conn(&app)?;
+    get_identity_by_id(&conn, input.identity_id)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let id = insert_planned(&tx, description, &input)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    get_planned_by_id(&conn, id)
+}
+
+// Trait ids that are not the identity's own are dropped.
+fn insert_planned(conn: &Connection, description: &str, input: &PlanActionInput) -> Result<i64, String> {
+    conn.execute(
+        "INSERT INTO planned_action (identity_id, date, description, reason, expected_score) \
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        (
+            input.identity_id,
+            &input.date,
+            description,
+            input.reason.as_deref().map(str::trim).filter(|r| !r.is_empty()),
+            input.expected_score,
+        ),
+    )
+    .map_err(|e| e.to_string())?;
+    let id = conn.last_insert_rowid();
+    for trait_id in &input.trait_ids {
+        conn.execute(
+            "INSERT OR IGNORE INTO planned_action_trait (planned_action_id, trait_id) \
+             SELECT ?1, id FROM trait WHERE id = ?2 AND identity_id = ?3",
+            (id, trait_id, input.identity_id),
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn list_planned_actions(app: AppHandle, identity_id: i64, date: String) -> Result<Vec<PlannedAction>, String> {
+    dates::parse_date(&date)?;
+    let conn = db::get_conn(&app)?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM planned_action WHERE identity_id = ?1 AND date = ?2 ORDER BY id",
+            PLANNED_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map((identity_id, &date), planned_from_row)
+        .map_err(|e| e.to_string())?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(out)
+}
+
+#[derive(Debug, Serialize)]
+pub struct CompletedAction {
+    pub action: PlannedAction,
+    pub behavior: BehaviorLog,
+}
+
+// Logs a planned action as a behavior on its day and marks it done. Without
+// `alignment_score` the expected score is used.
+#[tauri::command]
+pub fn complete_planned_action(
+    app: AppHandle,
+    id: i64,
+    alignment_score: Option<i32>,
+) -> Result<CompletedAction, String> {
+    let mut conn = db::get_conn(&app)?;
+    let planned = get_planned_by_id(&conn, id)?;
+    if planned.status == "done" {
+        return Err(format!("Planned action {} is already done", id));
+    }
+    let alignment_score = alignment_score
+        .or(planned.expected_score)
+        .ok_or("alignment_score is required for an action without an expected score")?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let behavior_id = insert_behavior(
+        &tx,
+        &LogBehaviorInput {
+            date: planned.date.clone(),
+            description: planned.description.clone(),
+            identity_id: planned.identity_id,
+            alignment_score,
+            suggested_score: planned.expected_score,
+            value: None,
+            unit: None,
+            trait_ids: planned.trait_ids.clone(),
+        },
+    )?;
+    tx.execute(
+        "UPDATE planned_action SET status = 'done', behavior_id = ?2, updated_at = datetime('now') WHERE id = ?1",
+        (id, behavior_id),
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(CompletedAction {
+        action: get_planned_by_id(&conn, id)?,
+        behavior: get_behavior_by_id(&conn, behavior_id)?,
+    })
+}
+
+// Skips a planned action or puts it back in the plan. Use
+// complete_planned_action to mark one done.
+#[tauri::command]
+pub fn set_planned_action_status(app: AppHandle, id: i64, status: String) -> Result<PlannedAction, String> {
+    if !STATUSES.contains(&status.as_str()) || status == "done" {
+        return Err(format!("status must be planned or skipped, got '{}'", status));
+    }
+    let conn = db::get_conn(&app)?;
+    conn.execute(
+        "UPDATE planned_action SET status = ?2, updated_at = datetime('now') WHERE id = ?1 AND status != 'done'",
+        (id, &status),
+    )
+    .map_err(|e| e.to_string())?;
+    get_planned_by_id(&conn, id)
+}
+
+#[tauri::command]
+pub fn delete_planned_action(app: AppHandle, id: i64) -> Result<(), String> {
+    let conn = db::get_conn(&app)?;
+    conn.execute("DELETE FROM planned_action WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn planned_traits_follow_the_trait_table() {
+        let conn = db::test_conn();
+        conn.execute_batch("INSERT INTO trait (id, name, identity_id) VALUES (1, 'Focused', 1), (2, 'Fast', 2);")
+            .unwrap();
+        let input = PlanActionInput {
+            identity_id: 1,
+            date: "2024-05-01".to_string(),
+            description: "Write 500 words".to_string(),
+            reason: None,
+            // Another identity's trait and an unknown id are dropped.
+            trait_ids: vec![1, 2, 99],
+            expected_score: Some(8),
+        };
+        let id = insert_planned(&conn, "Write 500 words", &input).unwrap();
+        assert_eq!(get_planned_by_id(&conn, id).unwrap().trait_ids, vec![1]);
+
+        conn.execute("DELETE FROM trait WHERE id = 1", []).unwrap();
+        assert!(get_planned_by_id(&conn, id).unwrap().trait_ids.is_empty());
+    }
+}
@@ -1,23 +1,59 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDate};
 use serde::Serialize;
 use tauri::AppHandle;
 
-use crate::db;
+use crate::commands::metric::{metrics_for_identity, Metric};
+use crate::commands::reflection::CURRENT_VERSION;
+use crate::{dates, db};
 
 #[derive(Debug, Serialize)]
 pub struct DayAlignment {
     pub date: String,
     pub avg_score: f64,
     pub count: i64,
+    pub metrics: Vec<MetricProgress>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MetricProgress {
+    pub metric_id: i64,
+    pub name: String,
+    pub unit: String,
+    pub period: String,
+    pub target: f64,
+    // Sum logged on this day.
+    pub total: f64,
+    // Sum over the target period up to and including this day (the day itself
+    // for daily metrics, Monday-to-date for weekly ones).
+    pub period_total: f64,
+    pub attainment: f64,
+}
+
+#[tauri::command]
+pub fn get_today(app: AppHandle) -> Result<String, String> {
+    let conn = db::get_conn(&app)?;
+    Ok(dates::format_date(dates::local_today(&conn)?))
 }
 
 #[tauri::command]
 pub fn get_weekly_alignment(
     app: AppHandle,
     identity_id: i64,
-    from_date: String,
-    to_date: String,
+    from_date: Option<String>,
+    to_date: Option<String>,
 ) -> Result<Vec<DayAlignment>, String> {
     let conn = db::get_conn(&app)?;
+    let to_date = match to_date {
+        Some(d) => dates::parse_date(&d)?,
+        None => dates::local_today(&conn)?,
+    };
+    let from_date = match from_date {
+        Some(d) => dates::parse_date(&d)?,
+        None => to_date - Duration::days(6),
+    };
+    let (from_date, to_date) = (dates::format_date(from_date), dates::format_date(to_date));
     let mut stmt = conn
         .prepare(
             "SELECT date, AVG(alignment_score) as avg_score, COUNT(*) as count FROM behavior_log WHERE identity_id = ?1 AND date >= ?2 AND date <= ?3 GROUP BY date ORDER BY date",
@@ -29,6 +65,7 @@ pub fn get_weekly_alignment(
                 date: row.get(0)?,
                 avg_score: row.get(1)?,
                 count: row.get(2)?,
+                metrics: Vec::new(),
             })
         })
         .map_err(|e| e.to_string())?;
@@ -36,6 +73,84 @@ pub fn get_weekly_alignment(
     for r in rows {
         out.push(r.map_err(|e: rusqlite::Error| e.to_string())?);
     }
+    attach_metric_progress(&conn, identity_id, &mut out)?;
+    Ok(out)
+}
+
+fn attach_metric_progress(
+    conn: &rusqlite::Connection,
+    identity_id: i64,
+    days: &mut [DayAlignment],
+) -> Result<(), String> {
+    let metrics = metrics_for_identity(conn, identity_id)?;
+    let (Some(first), Some(last)) = (days.first(), days.last()) else {
+        return Ok(());
+    };
+    if metrics.is_empty() {
+        return Ok(());
+    }
+    let from_date = dates::format_date(dates::week_start(dates::parse_date(&first.date)?));
+    let to_date = last.date.clone();
+
+    let mut totals: Vec<HashMap<NaiveDate, f64>> = Vec::with_capacity(metrics.len());
+    for metric in &metrics {
+        totals.push(daily_metric_totals(conn, identity_id, metric, &from_date, &to_date)?);
+    }
+
+    for day in days.iter_mut() {
+        let date = dates::parse_date(&day.date)?;
+        day.metrics = metrics
+            .iter()
+            .zip(&totals)
+            .map(|(metric, by_date)| {
+                let total = by_date.get(&date).copied().unwrap_or(0.0);
+                let period_total = if metric.period == "weekly" {
+                    by_date
+                        .iter()
+                        .filter(|(d, _)| dates::week_start(**d) == dates::week_start(date) && **d <= date)
+                        .map(|(_, v)| v)
+                        .sum()
+                } else {
+                    total
+                };
+                MetricProgress {
+                    metric_id: metric.id,
+                    name: metric.name.clone(),
+                    unit: metric.unit.clone(),
+                    period: metric.period.clone(),
+                    target: metric.target,
+                    total,
+                    period_total,
+                    attainment: period_total / metric.target,
+                }
+            })
+            .collect();
+    }
+    Ok(())
+}
+
+fn daily_metric_totals(
+    conn: &rusqlite::Connection,
+    identity_id: i64,
+    metric: &Metric,
+    from_date: &str,
+    to_date: &str,
+) -> Result<HashMap<NaiveDate, f64>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT date, SUM(value) FROM behavior_log WHERE identity_id = ?1 AND value IS NOT NULL AND unit = ?2 COLLATE NOCASE AND date >= ?3 AND date <= ?4 GROUP BY date",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map((identity_id, &metric.unit, from_date, to_date), |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+    let mut out = HashMap::new();
+    for r in rows {
+        let (date, total) = r.map_err(|e| e.to_string())?;
+        out.insert(dates::parse_date(&date)?, total);
+    }
     Ok(out)
 }
 
@@ -52,14 +167,18 @@ pub fn get_alignment_trends(
     identity_id: i64,
     days: Option<i32>,
 ) -> Result<Vec<AlignmentTrend>, String> {
-    let days = days.unwrap_or(14);
+    let days = days.unwrap_or(14).max(1);
     let conn = db::get_conn(&app)?;
-    let mut stmt = conn.prepare(&format!(
-        "SELECT date, AVG(alignment_score), COUNT(*) FROM behavior_log WHERE identity_id = ?1 AND date >= date('now', '-{} days') GROUP BY date ORDER BY date",
-        days
-    )).map_err(|e| e.to_string())?;
+    let today = dates::local_today(&conn)?;
+    let from_date = dates::format_date(today - Duration::days(days as i64 - 1));
+    let to_date = dates::format_date(today);
+    let mut stmt = conn
+        .prepare(
+            "SELECT date, AVG(alignment_score), COUNT(*) FROM behavior_log WHERE identity_id = ?1 AND date >= ?2 AND date <= ?3 GROUP BY date ORDER BY date",
+        )
+        .map_err(|e| e.to_string())?;
     let rows = stmt
-        .query_map([identity_id], |row| {
+        .query_map((identity_id, from_date.as_str(), to_date.as_str()), |row| {
             Ok(AlignmentTrend {
                 date: row.get(0)?,
                 avg_alignment: row.get(1)?,
@@ -73,3 +192,210 @@ pub fn get_alignment_trends(
     }
     Ok(out)
 }
+
+#[derive(Debug, Serialize)]
+pub struct FollowUpStats {
+    pub acted: i64,
+    pub partial: i64,
+    pub not_acted: i64,
+    // Share of assessed days where the previous correction was fully or
+    // partly acted on, counting partial as half.
+    pub follow_through: Option<f64>,
+}
+
+// How often corrections were followed, according to the follow-up stored
+// with each day's shown reflection.
+#[tauri::command]
+pub fn get_follow_up_stats(
+    app: AppHandle,
+    identity_id: i64,
+    days: Option<i32>,
+) -> Result<FollowUpStats, String> {
+    let days = days.unwrap_or(30).max(1);
+    let conn = db::get_conn(&app)?;
+    let today = dates::local_today(&conn)?;
+    let from_date = dates::format_date(today - Duration::days(days as i64 - 1));
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT follow_up_status, COUNT(*) FROM daily_reflection WHERE identity_id = ?1 AND date >= ?2 \
+             AND follow_up_status IS NOT NULL AND {} GROUP BY follow_up_status",
+            CURRENT_VERSION
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map((identity_id, from_date.as_str()), |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+    let mut stats = FollowUpStats {
+        acted: 0,
+        partial: 0,
+        not_acted: 0,
+        follow_through: None,
+    };
+    for r in rows {
+        let (status, count) = r.map_err(|e| e.to_string())?;
+        match status.as_str() {
+            "acted" => stats.acted = count,
+            "partial" => stats.partial = count,
+            "not_acted" => stats.not_acted = count,
+            _ => {}
+        }
+    }
+    let assessed = stats.acted + stats.partial + stats.not_acted;
+    if assessed > 0 {
+        stats.follow_through = Some((stats.acted as f64 + stats.partial as f64 / 2.0) / assessed as f64);
+    }
+    Ok(stats)
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommitmentWeek {
+    // Monday of the week the commitments were due.
+    pub week_start: String,
+    pub kept: i64,
+    pub broken: i64,
+    pub open: i64,
+    pub kept_rate: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommitmentStats {
+    pub kept: i64,
+    pub broken: i64,
+    pub open: i64,
+    // Open commitments already past their due date.
+    pub overdue: i64,
+    // Kept share of resolved commitments; None until one is resolved.
+    pub kept_rate: Option<f64>,
+    pub weeks: Vec<CommitmentWeek>,
+}
+
+fn kept_rate(kept: i64, broken: i64) -> Option<f64> {
+    (kept + broken > 0).then(|| kept as f64 / (kept + broken) as f64)
+}
+
+// Kept-commitment rate over the last `weeks` weeks, by due date, overall and
+// per week, oldest week first.
+#[tauri::command]
+pub fn get_commitment_stats(
+    app: AppHandle,
+    identity_id: i64,
+    weeks: Option<i32>,
+) -> Result<CommitmentStats, String> {
+    let weeks = weeks.unwrap_or(12).max(1) as i64;
+    let conn = db::get_conn(&app)?;
+    let today = dates::local_today(&conn)?;
+    let this_week = dates::week_start(today);
+    let first_week = this_week - Duration::weeks(weeks - 1);
+
+    let mut stats = CommitmentStats {
+        kept: 0,
+        broken: 0,
+        open: 0,
+        overdue: 0,
+        kept_rate: None,
+        weeks: (0..weeks)
+            .map(|i| CommitmentWeek {
+                week_start: dates::format_date(first_week + Duration::weeks(i)),
+                kept: 0,
+                broken: 0,
+                open: 0,
+                kept_rate: None,
+            })
+            .collect(),
+    };
+    let mut stmt = conn
+        .prepare(
+            "SELECT due_date, status FROM commitment WHERE identity_id = ?1 AND due_date >= ?2 AND due_date <= ?3",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(
+            (identity_id, dates::format_date(first_week), dates::format_date(this_week + Duration::days(6))),
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        )
+        .map_err(|e| e.to_string())?;
+    for r in rows {
+        let (due_date, status) = r.map_err(|e| e.to_string())?;
+        let due = dates::parse_date(&due_date)?;
+        let week = &mut stats.weeks[((due - first_week).num_days() / 7) as usize];
+        match status.as_str() {
+            "kept" => {
+                stats.kept += 1;
+                week.kept += 1;
+            }
+            "broken" => {
+                stats.broken += 1;
+                week.broken += 1;
+            }
+            _ => {
+                stats.open += 1;
+                week.open += 1;
+                if due < today {
+                    stats.overdue += 1;
+                }
+            }
+        }
+    }
+    for week in &mut stats.weeks {
+        week.kept_rate = kept_rate(week.kept, week.broken);
+    }
+    stats.kept_rate = kept_rate(stats.kept, stats.broken);
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::behavior::{insert_behavior, LogBehaviorInput};
+    use crate::commands::metric::{insert_metric, CreateMetricInput};
+
+    fn log(conn: &rusqlite::Connection, date: &str, value: f64, unit: &str) {
+        insert_behavior(
+            conn,
+            &LogBehaviorInput {
+                date: date.to_string(),
+                description: "x".to_string(),
+                identity_id: 1,
+                alignment_score: 7,
+                suggested_score: None,
+                value: Some(value),
+                unit: Some(unit.to_string()),
+                trait_ids: Vec::new(),
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn metric_totals_only_count_their_own_unit() {
+        let conn = db::test_conn();
+        let metric = |name: &str, unit: &str| {
+            insert_metric(
+                &conn,
+                1,
+                &CreateMetricInput {
+                    name: name.to_string(),
+                    unit: unit.to_string(),
+                    target: 10.0,
+                    period: "daily".to_string(),
+                },
+            )
+            .unwrap()
+        };
+        let pages = metric("pages read", "pages");
+        let minutes = metric("minutes meditated", "min");
+        log(&conn, "2024-03-01", 10.0, "pages");
+        log(&conn, "2024-03-01", 5.0, "Pages");
+        log(&conn, "2024-03-01", 20.0, "min");
+        log(&conn, "2024-03-02", 3.0, "km");
+
+        let day = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let totals = daily_metric_totals(&conn, 1, &pages, "2024-03-01", "2024-03-02").unwrap();
+        assert_eq!(totals.get(&day), Some(&15.0));
+        assert_eq!(totals.len(), 1);
+        let totals = daily_metric_totals(&conn, 1, &minutes, "2024-03-01", "2024-03-02").unwrap();
+        assert_eq!(totals.get(&day), Some(&20.0));
+    }
+}
@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use rusqlite::Connection;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+use tokio::sync::oneshot;
+use tokio::task::{self, JoinSet};
+
+use crate::commands::reflection::generate_with_provider;
+use crate::{dates, db, llm};
+
+const DEFAULT_CONCURRENCY: u32 = 1;
+const MAX_CONCURRENCY: u32 = 3;
+
+// Minimum gap between starting two generations, to stay clear of provider
+// rate limits.
+const MIN_INTERVAL: Duration = Duration::from_secs(2);
+
+// Longest range one backfill may cover.
+const MAX_DAYS: i64 = 366;
+
+// Running backfills by request id; sending on the channel cancels one.
+#[derive(Default)]
+pub struct BackfillJobs {
+    running: Mutex<HashMap<String, oneshot::Sender<()>>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackfillDay {
+    pub date: String,
+    // "done", "failed" (including a generation that crashed), or "skipped"
+    // when cancelled or stopped before the day finished.
+    pub status: String,
+    pub reflection_id: Option<i64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BackfillReport {
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub cancelled: bool,
+    // Set when the network went away and the remaining days were skipped.
+    pub stopped: Option<String>,
+    pub days: Vec<BackfillDay>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ProgressEvent<'a> {
+    request_id: &'a str,
+    day: &'a BackfillDay,
+    completed: usize,
+    total: usize,
+}
+
+// Days in the range with logged behaviors but no reflection, oldest first.
+fn missing_days(conn: &Connection, identity_id: i64, from: &str, to: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT DISTINCT b.date FROM behavior_log b WHERE b.identity_id = ?1 AND b.date BETWEEN ?2 AND ?3 \
+             AND NOT EXISTS (SELECT 1 FROM daily_reflection r WHERE r.identity_id = b.identity_id AND r.date = b.date) \
+             ORDER BY b.date",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map((identity_id, from, to), |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(out)
+}
+
+// Generates reflections for days between `from` and `to` that have behaviors
+// but no reflection. Up to `concurrency` days run at once, starting at least
+// MIN_INTERVAL apart. Each finished day is sent as a backfill://progress event
+// carrying `request_id`; the report lists every day at the end.
+#[tauri::command]
+pub async fn backfill_reflections(
+    app: AppHandle,
+    jobs: State<'_, BackfillJobs>,
+    request_id: String,
+    identity_id: i64,
+    from: String,
+    to: String,
+    concurrency: Option<u32>,
+) -> Result<BackfillReport, String> {
+    let (first, last) = (dates::parse_date(&from)?, dates::parse_date(&to)?);
+    if first > last {
+        return Err("from must not be after to".to_string());
+    }
+    if (last - first).num_days() >= MAX_DAYS {
+        return Err(format!("A backfill can cover at most {} days", MAX_DAYS));
+    }
+    let concurrency = concurrency.unwrap_or(DEFAULT_CONCURRENCY).clamp(1, MAX_CONCURRENCY) as usize;
+    let days = missing_days(&db::get_conn(&app)?, identity_id, &from, &to)?;
+
+    let (cancel_tx, cancel_rx) = oneshot::channel();
+    {
+        let mut jobs = jobs.running.lock().map_err(|e| e.to_string())?;
+        if jobs.contains_key(&request_id) {
+            return Err(format!("Request {} is already running", request_id));
+        }
+        jobs.insert(request_id.clone(), cancel_tx);
+    }
+
+    let report = run_days(
+        &days,
+        concurrency,
+        MIN_INTERVAL,
+        cancel_rx,
+        |date| {
+            let app = app.clone();
+            let day_request = format!("{}:{}", request_id, date);
+            async move {
+                generate_with_provider(&app, &day_request, identity_id, &date)
+                    .await
+                    .map(|r| r.id)
+            }
+        },
+        |day, completed| {
+            let _ = app.emit(
+                "backfill://progress",
+                ProgressEvent {
+                    request_id: &request_id,
+                    day,
+                    completed,
+                    total: days.len(),
+                },
+            );
+        },
+    )
+    .await;
+    if let Ok(mut jobs) = jobs.running.lock() {
+        jobs.remove(&request_id);
+    }
+    Ok(report)
+}
+
+// Runs `generate` for each day, up to `concurrency` at once and starting at
+// least `interval` apart, until every day has run, the run is cancelled or the
+// network goes away. `on_finished` gets each finished day and the number
+// finished so far.
+async fn run_days<F, Fut>(
+    days: &[String],
+    concurrency: usize,
+    interval: Duration,
+    mut cancel_rx: oneshot::Receiver<()>,
+    generate: F,
+    mut on_finished: impl FnMut(&BackfillDay, usize),
+) -> BackfillReport
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<i64, String>> + Send + 'static,
+{
+    let mut pending = days.iter().cloned().peekable();
+    let mut tasks = JoinSet::new();
+    // The day each running task generates, by task id, so a task that
+    // panicked can still be reported against its day.
+    let mut running: HashMap<task::Id, String> = HashMap::new();
+    let mut finished: Vec<BackfillDay> = Vec::new();
+    let mut last_start: Option<Instant> = None;
+    let mut cancelled = false;
+    let mut stopped: Option<String> = None;
+    loop {
+        let can_start =
+            !cancelled && stopped.is_none() && tasks.len() < concurrency && pending.peek().is_some();
+        if !can_start && tasks.is_empty() {
+            break;
+        }
+        let wait = last_start
+            .map(|at| interval.saturating_sub(at.elapsed()))
+            .unwrap_or_default();
+        tokio::select! {
+            // Dropping the running generations aborts their requests before
+            // anything is stored.
+            _ = &mut cancel_rx, if !cancelled => {
+                cancelled = true;
+                tasks.abort_all();
+            }
+            _ = tokio::time::sleep(wait), if can_start => {
+                let Some(date) = pending.next() else { continue };
+                last_start = Some(Instant::now());
+                let handle = tasks.spawn(generate(date.clone()));
+                running.insert(handle.id(), date);
+            }
+            Some(joined) = tasks.join_next_with_id(), if !tasks.is_empty() => {
+                let (id, result) = match joined {
+                    Ok((id, result)) => (id, result),
+                    // Aborted by a cancel: nothing was stored, so the day
+                    // stays skipped.
+                    Err(e) if e.is_cancelled() => {
+                        running.remove(&e.id());
+                        continue;
+                    }
+                    Err(e) => (e.id(), Err(format!("Generation crashed: {}", e))),
+                };
+                let date = running.remove(&id).expect("every task's day is recorded");
+                if let Err(e) = &result {
+                    if llm::http::is_offline(e) {
+                        stopped = Some(e.clone());
+                    }
+                }
+                finished.push(BackfillDay {
+                    date,
+                    status: if result.is_ok() { "done" } else { "failed" }.to_string(),
+                    reflection_id: result.as_ref().ok().copied(),
+                    error: result.err(),
+                });
+                on_finished(finished.last().expect("just pushed"), finished.len());
+            }
+        }
+    }
+
+    let report_days: Vec<BackfillDay> = days
+        .iter()
+        .map(|date| {
+            finished.iter().find(|d| &d.date == date).cloned().unwrap_or_else(|| BackfillDay {
+                date: date.clone(),
+                status: "skipped".to_string(),
+                reflection_id: None,
+                error: None,
+            })
+        })
+        .collect();
+    BackfillReport {
+        total: days.len(),
+        succeeded: report_days.iter().filter(|d| d.status == "done").count(),
+        failed: report_days.iter().filter(|d| d.status == "failed").count(),
+        cancelled,
+        stopped,
+        days: report_days,
+    }
+}
+
+// Stops a running backfill. Days already generated are kept.
+#[tauri::command]
+pub fn cancel_backfill(jobs: State<'_, BackfillJobs>, request_id: String) -> Result<bool, String> {
+    let sender = jobs.running.lock().map_err(|e| e.to_string())?.remove(&request_id);
+    Ok(match sender {
+        Some(sender) => sender.send(()).is_ok(),
+        None => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn days(n: usize) -> Vec<String> {
+        (1..=n).map(|d| format!("2024-05-0{}", d)).collect()
+    }
+
+    fn statuses(report: &BackfillReport) -> Vec<&str> {
+        report.days.iter().map(|d| d.status.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn respects_concurrency_and_reports_panics_as_failed() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let most = Arc::new(AtomicUsize::new(0));
+        let (_cancel_tx, cancel_rx) = oneshot::channel();
+        let mut progress = Vec::new();
+        let report = run_days(
+            &days(5),
+            2,
+            Duration::ZERO,
+            cancel_rx,
+            |date| {
+                let (in_flight, most) = (in_flight.clone(), most.clone());
+                async move {
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    most.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    match date.as_str() {
+                        "2024-05-03" => panic!("boom"),
+                        "2024-05-04" => Err("provider said no".to_string()),
+                        _ => Ok(date[9..].parse::<i64>().unwrap()),
+                    }
+                }
+            },
+            |_, completed| progress.push(completed),
+        )
+        .await;
+
+        assert_eq!(most.load(Ordering::SeqCst), 2);
+        assert_eq!(progress, vec![1, 2, 3, 4, 5]);
+        assert_eq!(statuses(&report), vec!["done", "done", "failed", "failed", "done"]);
+        assert_eq!((report.succeeded, report.failed, report.total), (3, 2, 5));
+        assert_eq!(report.days[0].reflection_id, Some(1));
+        assert!(report.days[2].error.as_deref().unwrap().contains("panicked"));
+        assert_eq!(report.days[3].error.as_deref(), Some("provider said no"));
+        assert!(!report.cancelled && report.stopped.is_none());
+    }
+
+    #[tokio::test]
+    async fn cancel_skips_the_days_not_yet_finished() {
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        let mut cancel_tx = Some(cancel_tx);
+        let report = run_days(
+            &days(4),
+            1,
+            Duration::ZERO,
+            cancel_rx,
+            |_| async {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                Ok(1)
+            },
+            |_, completed| {
+                if completed == 1 {
+                    let _ = cancel_tx.take().unwrap().send(());
+                }
+            },
+        )
+        .await;
+
+        assert!(report.cancelled);
+        assert_eq!(statuses(&report), vec!["done", "skipped", "skipped", "skipped"]);
+        assert_eq!((report.succeeded, report.failed), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn going_offline_stops_the_run() {
+        let (_cancel_tx, cancel_rx) = oneshot::channel();
+        let report = run_days(
+            &days(3),
+            1,
+            Duration::ZERO,
+            cancel_rx,
+            |date| async move {
+                if date == "2024-05-02" {
+                    Err(format!("{}: connection refused", llm::http::OFFLINE))
+                } else {
+                    Ok(1)
+                }
+            },
+            |_, _| {},
+        )
+        .await;
+
+        assert_eq!(statuses(&report), vec!["done", "failed", "skipped"]);
+        assert!(llm::http::is_offline(report.stopped.as_deref().unwrap()));
+    }
+}
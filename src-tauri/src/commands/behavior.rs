@@ -1,7 +1,12 @@
 use serde::{Deserialize, Serialize};
 use tauri::AppHandle;
 
-use crate::db;
+use crate::commands::identity::get_identity_by_id;
+use crate::commands::redaction::redactor;
+use crate::commands::trait_::{traits_for_identity, Trait};
+use crate::llm::redact::Redactor;
+use crate::llm::ReflectionProvider;
+use crate::{dates, db, llm};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BehaviorLog {
@@ -10,47 +15,397 @@ pub struct BehaviorLog {
     pub description: String,
     pub identity_id: i64,
     pub alignment_score: i32,
+    pub suggested_score: Option<i32>,
+    pub value: Option<f64>,
+    pub unit: Option<String>,
+    pub trait_ids: Vec<i64>,
     pub created_at: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct LogBehaviorInput {
     pub date: String,
     pub description: String,
     pub identity_id: i64,
     pub alignment_score: i32,
+    #[serde(default)]
+    pub suggested_score: Option<i32>,
+    #[serde(default)]
+    pub value: Option<f64>,
+    #[serde(default)]
+    pub unit: Option<String>,
+    #[serde(default)]
+    pub trait_ids: Vec<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AlignmentSuggestion {
+    pub score: i32,
+    pub traits: Vec<Trait>,
+    pub rationale: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawAlignmentSuggestion {
+    score: i32,
+    #[serde(default)]
+    traits: Vec<String>,
+    rationale: String,
+}
+
+impl RawAlignmentSuggestion {
+    fn validate(&self) -> Result<(), String> {
+        if !(1..=10).contains(&self.score) {
+            return Err(format!("score {} is outside 1–10", self.score));
+        }
+        if self.rationale.trim().is_empty() {
+            return Err("rationale is empty".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn alignment_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "score": { "type": "integer", "minimum": 1, "maximum": 10 },
+            "traits": { "type": "array", "items": { "type": "string" } },
+            "rationale": { "type": "string" }
+        },
+        "required": ["score", "traits", "rationale"],
+        "additionalProperties": false
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct RawParsedBehaviors {
+    behaviors: Vec<RawParsedBehavior>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawParsedBehavior {
+    description: String,
+    score: i32,
+    #[serde(default)]
+    value: Option<f64>,
+    #[serde(default)]
+    unit: Option<String>,
+    #[serde(default)]
+    traits: Vec<String>,
+}
+
+impl RawParsedBehaviors {
+    fn validate(&self) -> Result<(), String> {
+        if self.behaviors.is_empty() {
+            return Err("no behaviors found".to_string());
+        }
+        for b in &self.behaviors {
+            let description = b.description.trim();
+            if description.is_empty() {
+                return Err("behavior without a description".to_string());
+            }
+            if !(1..=10).contains(&b.score) {
+                return Err(format!("score {} for \"{}\" is outside 1–10", b.score, description));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parsed_behaviors_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "behaviors": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "description": { "type": "string" },
+                        "score": { "type": "integer", "minimum": 1, "maximum": 10 },
+                        "value": { "type": ["number", "null"] },
+                        "unit": { "type": ["string", "null"] },
+                        "traits": { "type": "array", "items": { "type": "string" } }
+                    },
+                    "required": ["description", "score", "value", "unit", "traits"],
+                    "additionalProperties": false
+                }
+            }
+        },
+        "required": ["behaviors"],
+        "additionalProperties": false
+    })
+}
+
+const BEHAVIOR_COLUMNS: &str = "id, date, description, identity_id, alignment_score, suggested_score, value, unit, \
+    (SELECT GROUP_CONCAT(trait_id) FROM behavior_trait WHERE behavior_id = behavior_log.id), created_at";
+
+fn behavior_from_row(row: &rusqlite::Row) -> rusqlite::Result<BehaviorLog> {
+    let trait_ids: Option<String> = row.get(8)?;
+    Ok(BehaviorLog {
+        id: row.get(0)?,
+        date: row.get(1)?,
+        description: row.get(2)?,
+        identity_id: row.get(3)?,
+        alignment_score: row.get(4)?,
+        suggested_score: row.get(5)?,
+        value: row.get(6)?,
+        unit: row.get(7)?,
+        trait_ids: id_list(trait_ids),
+        created_at: row.get(9)?,
+    })
+}
+
+// Parses a GROUP_CONCAT of ids, NULL when there are none.
+pub(crate) fn id_list(ids: Option<String>) -> Vec<i64> {
+    ids.unwrap_or_default()
+        .split(',')
+        .filter_map(|id| id.parse().ok())
+        .collect()
+}
+
+const SUGGEST_INSTRUCTION: &str = r#"You are a psychologically intelligent identity performance coach.
+Score how strongly one behavior signals the stated identity, from 1 (contradicts it) to 10 (embodies it).
+Only pick traits from the provided list that the behavior actually demonstrates.
+
+Respond with valid JSON only, in this exact structure:
+{
+  "score": 7,
+  "traits": ["trait name"],
+  "rationale": "one sentence"
+}"#;
+
+#[tauri::command]
+pub async fn suggest_alignment(
+    app: AppHandle,
+    identity_id: i64,
+    description: String,
+) -> Result<AlignmentSuggestion, String> {
+    let (identity, traits, redactor, provider) = {
+        let conn = db::get_conn(&app)?;
+        (
+            get_identity_by_id(&conn, identity_id)?,
+            traits_for_identity(&conn, identity_id)?,
+            redactor(&conn)?,
+            llm::provider_for_identity(&app, &conn, identity_id)?,
+        )
+    };
+
+    let user_content = format!(
+        r#"Identity: {}
+Description: {}
+Traits: {}
+
+Behavior: {}
+"#,
+        identity.name,
+        identity.description,
+        traits.iter().map(|t| t.name.as_str()).collect::<Vec<_>>().join(", "),
+        description.trim()
+    );
+    ask_alignment(provider.as_ref(), redactor, &user_content, &traits).await
+}
+
+// Sends the prompt redacted and restores the reply, so trait names are
+// matched against the original text.
+async fn ask_alignment(
+    provider: &dyn ReflectionProvider,
+    mut redactor: Redactor,
+    user_content: &str,
+    traits: &[Trait],
+) -> Result<AlignmentSuggestion, String> {
+    let request = llm::ChatRequest::new(SUGGEST_INSTRUCTION, redactor.redact(user_content))
+        .temperature(0.2)
+        .json_schema("alignment_suggestion", alignment_schema());
+    let (raw, _) = llm::complete_json::<RawAlignmentSuggestion, _>(
+        provider,
+        &request,
+        RawAlignmentSuggestion::validate,
+        &mut |_, _| {},
+    )
+    .await?;
+    let names: Vec<String> = raw.traits.iter().map(|t| redactor.restore(t)).collect();
+    Ok(AlignmentSuggestion {
+        score: raw.score,
+        traits: match_traits(traits, &names).cloned().collect(),
+        rationale: redactor.restore(&raw.rationale),
+    })
+}
+
+const PARSE_INSTRUCTION: &str = r#"You are a psychologically intelligent identity performance coach.
+Split the user's free-text day summary into separate behaviors, one per distinct action.
+Keep each description short and in the user's own words.
+Score how strongly each behavior signals the stated identity, from 1 (contradicts it) to 10 (embodies it).
+Only pick traits from the provided list that the behavior actually demonstrates.
+When a behavior states a quantity (e.g. "2h", "30 pages"), set value and unit; otherwise use null.
+
+Respond with valid JSON only, in this exact structure:
+{
+  "behaviors": [
+    { "description": "string", "score": 7, "value": null, "unit": null, "traits": ["trait name"] }
+  ]
+}"#;
+
+#[tauri::command]
+pub async fn parse_behaviors(
+    app: AppHandle,
+    identity_id: i64,
+    date: String,
+    text: String,
+) -> Result<Vec<LogBehaviorInput>, String> {
+    dates::parse_date(&date)?;
+    if text.trim().is_empty() {
+        return Err("Nothing to parse".to_string());
+    }
+    let (identity, traits, redactor, provider) = {
+        let conn = db::get_conn(&app)?;
+        (
+            get_identity_by_id(&conn, identity_id)?,
+            traits_for_identity(&conn, identity_id)?,
+            redactor(&conn)?,
+            llm::provider_for_identity(&app, &conn, identity_id)?,
+        )
+    };
+
+    let user_content = format!(
+        r#"Identity: {}
+Description: {}
+Traits: {}
+
+Day summary:
+{}
+"#,
+        identity.name,
+        identity.description,
+        traits.iter().map(|t| t.name.as_str()).collect::<Vec<_>>().join(", "),
+        text.trim()
+    );
+    let raw = ask_parse(provider.as_ref(), redactor, &user_content).await?;
+    Ok(raw
+        .behaviors
+        .into_iter()
+        .map(|b| LogBehaviorInput {
+            date: date.clone(),
+            description: b.description.trim().to_string(),
+            identity_id,
+            alignment_score: b.score,
+            suggested_score: Some(b.score),
+            value: b.value.filter(|_| b.unit.is_some()),
+            unit: b.unit.filter(|_| b.value.is_some()),
+            trait_ids: match_traits(&traits, &b.traits).map(|t| t.id).collect(),
+        })
+        .collect())
+}
+
+// Sends the day summary redacted and returns the validated reply with the
+// original text restored. Blank units count as no unit.
+async fn ask_parse(
+    provider: &dyn ReflectionProvider,
+    mut redactor: Redactor,
+    user_content: &str,
+) -> Result<RawParsedBehaviors, String> {
+    let request = llm::ChatRequest::new(PARSE_INSTRUCTION, redactor.redact(user_content))
+        .temperature(0.2)
+        .json_schema("parsed_behaviors", parsed_behaviors_schema());
+    let (raw, _) = llm::complete_json::<RawParsedBehaviors, _>(
+        provider,
+        &request,
+        RawParsedBehaviors::validate,
+        &mut |_, _| {},
+    )
+    .await?;
+    Ok(RawParsedBehaviors {
+        behaviors: raw
+            .behaviors
+            .into_iter()
+            .map(|b| RawParsedBehavior {
+                description: redactor.restore(&b.description),
+                unit: b
+                    .unit
+                    .map(|u| redactor.restore(u.trim()))
+                    .filter(|u| !u.is_empty()),
+                traits: b.traits.iter().map(|t| redactor.restore(t)).collect(),
+                ..b
+            })
+            .collect(),
+    })
+}
+
+pub(crate) fn match_traits<'a>(traits: &'a [Trait], names: &'a [String]) -> impl Iterator<Item = &'a Trait> {
+    traits
+        .iter()
+        .filter(|t| names.iter().any(|name| name.trim().eq_ignore_ascii_case(&t.name)))
 }
 
 #[tauri::command]
 pub fn log_behavior(app: AppHandle, input: LogBehaviorInput) -> Result<BehaviorLog, String> {
+    let mut conn = db::get_conn(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let id = insert_behavior(&tx, &input)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    get_behavior_by_id(&conn, id)
+}
+
+#[tauri::command]
+pub fn log_behaviors(
+    app: AppHandle,
+    inputs: Vec<LogBehaviorInput>,
+) -> Result<Vec<BehaviorLog>, String> {
+    let mut conn = db::get_conn(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut ids = Vec::with_capacity(inputs.len());
+    for input in &inputs {
+        ids.push(insert_behavior(&tx, input)?);
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    ids.into_iter().map(|id| get_behavior_by_id(&conn, id)).collect()
+}
+
+pub(crate) fn insert_behavior(conn: &rusqlite::Connection, input: &LogBehaviorInput) -> Result<i64, String> {
     if input.alignment_score < 1 || input.alignment_score > 10 {
         return Err("alignment_score must be between 1 and 10".to_string());
     }
-    let conn = db::get_conn(&app)?;
+    if matches!(input.suggested_score, Some(s) if !(1..=10).contains(&s)) {
+        return Err("suggested_score must be between 1 and 10".to_string());
+    }
+    dates::parse_date(&input.date)?;
+    let unit = input.unit.as_deref().map(str::trim).filter(|u| !u.is_empty());
+    if input.value.is_some() != unit.is_some() {
+        return Err("value and unit must be given together".to_string());
+    }
+    if matches!(input.value, Some(v) if !v.is_finite()) {
+        return Err("value must be a finite number".to_string());
+    }
     conn.execute(
-        "INSERT INTO behavior_log (date, description, identity_id, alignment_score) VALUES (?1, ?2, ?3, ?4)",
-        [
+        "INSERT INTO behavior_log (date, description, identity_id, alignment_score, suggested_score, value, unit) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        (
             &input.date,
             &input.description,
-            &input.identity_id.to_string(),
-            &input.alignment_score.to_string(),
-        ],
+            input.identity_id,
+            input.alignment_score,
+            input.suggested_score,
+            input.value,
+            unit,
+        ),
     )
     .map_err(|e| e.to_string())?;
     let id = conn.last_insert_rowid();
+    for trait_id in &input.trait_ids {
+        conn.execute(
+            "INSERT OR IGNORE INTO behavior_trait (behavior_id, trait_id) SELECT ?1, id FROM trait WHERE id = ?2 AND identity_id = ?3",
+            (id, trait_id, input.identity_id),
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(id)
+}
+
+pub(crate) fn get_behavior_by_id(conn: &rusqlite::Connection, id: i64) -> Result<BehaviorLog, String> {
     conn.query_row(
-        "SELECT id, date, description, identity_id, alignment_score, created_at FROM behavior_log WHERE id = ?1",
+        &format!("SELECT {} FROM behavior_log WHERE id = ?1", BEHAVIOR_COLUMNS),
         [id],
-        |row| {
-            Ok(BehaviorLog {
-                id: row.get(0)?,
-                date: row.get(1)?,
-                description: row.get(2)?,
-                identity_id: row.get(3)?,
-                alignment_score: row.get(4)?,
-                created_at: row.get(5)?,
-            })
-        },
+        behavior_from_row,
     )
     .map_err(|e| e.to_string())
 }
@@ -61,23 +416,24 @@ pub fn get_behaviors_for_date(
     identity_id: i64,
     date: String,
 ) -> Result<Vec<BehaviorLog>, String> {
+    dates::parse_date(&date)?;
     let conn = db::get_conn(&app)?;
+    behaviors_for_date(&conn, identity_id, &date)
+}
+
+pub(crate) fn behaviors_for_date(
+    conn: &rusqlite::Connection,
+    identity_id: i64,
+    date: &str,
+) -> Result<Vec<BehaviorLog>, String> {
     let mut stmt = conn
-        .prepare(
-            "SELECT id, date, description, identity_id, alignment_score, created_at FROM behavior_log WHERE identity_id = ?1 AND date = ?2 ORDER BY created_at",
-        )
+        .prepare(&format!(
+            "SELECT {} FROM behavior_log WHERE identity_id = ?1 AND date = ?2 ORDER BY created_at",
+            BEHAVIOR_COLUMNS
+        ))
         .map_err(|e| e.to_string())?;
     let rows = stmt
-        .query_map((identity_id, date.as_str()), |row| {
-            Ok(BehaviorLog {
-                id: row.get(0)?,
-                date: row.get(1)?,
-                description: row.get(2)?,
-                identity_id: row.get(3)?,
-                alignment_score: row.get(4)?,
-                created_at: row.get(5)?,
-            })
-        })
+        .query_map((identity_id, date), behavior_from_row)
         .map_err(|e| e.to_string())?;
     let mut out = Vec::new();
     for r in rows {
@@ -93,20 +449,14 @@ pub fn list_behaviors_for_identity(
     from_date: Option<String>,
     to_date: Option<String>,
 ) -> Result<Vec<BehaviorLog>, String> {
+    for d in from_date.iter().chain(to_date.iter()) {
+        dates::parse_date(d)?;
+    }
     let conn = db::get_conn(&app)?;
     let out = match (from_date.as_deref(), to_date.as_deref()) {
         (Some(f), Some(t)) => {
-            let mut stmt = conn.prepare("SELECT id, date, description, identity_id, alignment_score, created_at FROM behavior_log WHERE identity_id = ?1 AND date >= ?2 AND date <= ?3 ORDER BY date DESC, created_at").map_err(|e| e.to_string())?;
-            let rows = stmt.query_map((identity_id, f, t), |row| {
-                Ok(BehaviorLog {
-                    id: row.get(0)?,
-                    date: row.get(1)?,
-                    description: row.get(2)?,
-                    identity_id: row.get(3)?,
-                    alignment_score: row.get(4)?,
-                    created_at: row.get(5)?,
-                })
-            }).map_err(|e| e.to_string())?;
+            let mut stmt = conn.prepare(&format!("SELECT {} FROM behavior_log WHERE identity_id = ?1 AND date >= ?2 AND date <= ?3 ORDER BY date DESC, created_at", BEHAVIOR_COLUMNS)).map_err(|e| e.to_string())?;
+            let rows = stmt.query_map((identity_id, f, t), behavior_from_row).map_err(|e| e.to_string())?;
             let mut out = Vec::new();
             for r in rows {
                 out.push(r.map_err(|e: rusqlite::Error| e.to_string())?);
@@ -114,17 +464,8 @@ pub fn list_behaviors_for_identity(
             out
         }
         (Some(f), None) => {
-            let mut stmt = conn.prepare("SELECT id, date, description, identity_id, alignment_score, created_at FROM behavior_log WHERE identity_id = ?1 AND date >= ?2 ORDER BY date DESC, created_at").map_err(|e| e.to_string())?;
-            let rows = stmt.query_map((identity_id, f), |row| {
-                Ok(BehaviorLog {
-                    id: row.get(0)?,
-                    date: row.get(1)?,
-                    description: row.get(2)?,
-                    identity_id: row.get(3)?,
-                    alignment_score: row.get(4)?,
-                    created_at: row.get(5)?,
-                })
-            }).map_err(|e| e.to_string())?;
+            let mut stmt = conn.prepare(&format!("SELECT {} FROM behavior_log WHERE identity_id = ?1 AND date >= ?2 ORDER BY date DESC, created_at", BEHAVIOR_COLUMNS)).map_err(|e| e.to_string())?;
+            let rows = stmt.query_map((identity_id, f), behavior_from_row).map_err(|e| e.to_string())?;
             let mut out = Vec::new();
             for r in rows {
                 out.push(r.map_err(|e: rusqlite::Error| e.to_string())?);
@@ -132,17 +473,8 @@ pub fn list_behaviors_for_identity(
             out
         }
         (None, Some(t)) => {
-            let mut stmt = conn.prepare("SELECT id, date, description, identity_id, alignment_score, created_at FROM behavior_log WHERE identity_id = ?1 AND date <= ?2 ORDER BY date DESC, created_at").map_err(|e| e.to_string())?;
-            let rows = stmt.query_map((identity_id, t), |row| {
-                Ok(BehaviorLog {
-                    id: row.get(0)?,
-                    date: row.get(1)?,
-                    description: row.get(2)?,
-                    identity_id: row.get(3)?,
-                    alignment_score: row.get(4)?,
-                    created_at: row.get(5)?,
-                })
-            }).map_err(|e| e.to_string())?;
+            let mut stmt = conn.prepare(&format!("SELECT {} FROM behavior_log WHERE identity_id = ?1 AND date <= ?2 ORDER BY date DESC, created_at", BEHAVIOR_COLUMNS)).map_err(|e| e.to_string())?;
+            let rows = stmt.query_map((identity_id, t), behavior_from_row).map_err(|e| e.to_string())?;
             let mut out = Vec::new();
             for r in rows {
                 out.push(r.map_err(|e: rusqlite::Error| e.to_string())?);
@@ -150,17 +482,8 @@ pub fn list_behaviors_for_identity(
             out
         }
         (None, None) => {
-            let mut stmt = conn.prepare("SELECT id, date, description, identity_id, alignment_score, created_at FROM behavior_log WHERE identity_id = ?1 ORDER BY date DESC, created_at").map_err(|e| e.to_string())?;
-            let rows = stmt.query_map([identity_id], |row| {
-                Ok(BehaviorLog {
-                    id: row.get(0)?,
-                    date: row.get(1)?,
-                    description: row.get(2)?,
-                    identity_id: row.get(3)?,
-                    alignment_score: row.get(4)?,
-                    created_at: row.get(5)?,
-                })
-            }).map_err(|e| e.to_string())?;
+            let mut stmt = conn.prepare(&format!("SELECT {} FROM behavior_log WHERE identity_id = ?1 ORDER BY date DESC, created_at", BEHAVIOR_COLUMNS)).map_err(|e| e.to_string())?;
+            let rows = stmt.query_map([identity_id], behavior_from_row).map_err(|e| e.to_string())?;
             let mut out = Vec::new();
             for r in rows {
                 out.push(r.map_err(|e: rusqlite::Error| e.to_string())?);
@@ -170,3 +493,70 @@ pub fn list_behaviors_for_identity(
     };
     Ok(out)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::redact::Rule;
+    use crate::llm::tests::scripted;
+
+    fn redactor() -> Redactor {
+        let rules = Rule::terms("PERSON", &["Alice".to_string()]).unwrap();
+        Redactor::new(rules.into_iter().collect())
+    }
+
+    fn trait_(id: i64, name: &str) -> Trait {
+        Trait {
+            id,
+            name: name.to_string(),
+            definition: None,
+            identity_id: 1,
+            created_at: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn alignment_prompt_is_redacted_and_reply_restored() {
+        let provider = scripted(vec![
+            r#"{"score": 8, "traits": ["Loyal to [PERSON_1]"],
+                "rationale": "Helping [PERSON_1] move fits."}"#,
+        ]);
+        let traits = [trait_(1, "Loyal to Alice"), trait_(2, "Focused")];
+        let prompt = "Traits: Loyal to Alice, Focused\n\nBehavior: Helped Alice move house";
+        let suggestion = ask_alignment(&provider, redactor(), prompt, &traits).await.unwrap();
+
+        assert!(!provider.sent().contains("Alice"), "{}", provider.sent());
+        assert!(provider.sent().contains("Helped [PERSON_1] move house"));
+        assert_eq!(suggestion.rationale, "Helping Alice move fits.");
+        assert_eq!(suggestion.traits.iter().map(|t| t.id).collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[tokio::test]
+    async fn day_summary_is_redacted_and_reply_restored() {
+        let provider = scripted(vec![
+            r#"{"behaviors": [{"description": "Called [PERSON_1]", "score": 7, "traits": []}]}"#,
+        ]);
+        let raw = ask_parse(&provider, redactor(), "Day summary:\nCalled Alice, then wrote 500 words")
+            .await
+            .unwrap();
+
+        assert!(!provider.sent().contains("Alice"), "{}", provider.sent());
+        assert_eq!(raw.behaviors[0].description, "Called Alice");
+    }
+
+    #[tokio::test]
+    async fn invalid_day_summary_reply_is_repaired_and_blank_units_dropped() {
+        let provider = scripted(vec![
+            r#"{"behaviors": [{"description": "Ran", "score": 11, "value": 5, "unit": "km", "traits": []}]}"#,
+            r#"{"behaviors": [{"description": "Ran", "score": 9, "value": 5, "unit": " km ", "traits": []},
+                {"description": "Read", "score": 6, "value": 30, "unit": "  ", "traits": []}]}"#,
+        ]);
+        let raw = ask_parse(&provider, redactor(), "Day summary:\nRan 5km, read 30").await.unwrap();
+
+        assert_eq!(provider.seen.lock().unwrap().len(), 2);
+        assert!(provider.sent().contains("outside 1–10"), "{}", provider.sent());
+        assert_eq!(raw.behaviors[0].score, 9);
+        assert_eq!(raw.behaviors[0].unit.as_deref(), Some("km"));
+        assert_eq!(raw.behaviors[1].unit, None);
+    }
+}
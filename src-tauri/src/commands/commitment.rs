@@ -0,0 +1,200 @@
+use chrono::Duration;
+use rusqlite::{Connection, Row};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::commands::behavior::id_list;
+use crate::commands::identity::get_identity_by_id;
+use crate::commands::reflection::get_reflection_by_id;
+use crate::{dates, db};
+
+const STATUSES: [&str; 3] = ["open", "kept", "broken"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Commitment {
+    pub id: i64,
+    pub identity_id: i64,
+    // The reflection whose identity correction this was made from; None when
+    // entered by hand.
+    pub reflection_id: Option<i64>,
+    pub text: String,
+    pub due_date: String,
+    // "open", "kept" or "broken".
+    pub status: String,
+    pub note: Option<String>,
+    // Behaviors that fulfilled the commitment.
+    pub behavior_ids: Vec<i64>,
+    pub resolved_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCommitmentInput {
+    pub identity_id: i64,
+    pub text: String,
+    pub due_date: String,
+}
+
+const COMMITMENT_COLUMNS: &str = "id, identity_id, reflection_id, text, due_date, status, note, \
+    (SELECT GROUP_CONCAT(behavior_id) FROM commitment_behavior WHERE commitment_id = commitment.id), \
+    resolved_at, created_at, updated_at";
+
+fn commitment_from_row(row: &Row) -> rusqlite::Result<Commitment> {
+    Ok(Commitment {
+        id: row.get(0)?,
+        identity_id: row.get(1)?,
+        reflection_id: row.get(2)?,
+        text: row.get(3)?,
+        due_date: row.get(4)?,
+        status: row.get(5)?,
+        note: row.get(6)?,
+        behavior_ids: id_list(row.get(7)?),
+        resolved_at: row.get(8)?,
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
+    })
+}
+
+fn get_commitment_by_id(conn: &Connection, id: i64) -> Result<Commitment, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM commitment WHERE id = ?1", COMMITMENT_COLUMNS),
+        [id],
+        commitment_from_row,
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => format!("Commitment {} not found", id),
+        e => e.to_string(),
+    })
+}
+
+fn insert(
+    conn: &Connection,
+    identity_id: i64,
+    reflection_id: Option<i64>,
+    text: &str,
+    due_date: &str,
+) -> Result<Commitment, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("text is required".to_string());
+    }
+    dates::parse_date(due_date)?;
+    conn.execute(
+        "INSERT INTO commitment (identity_id, reflection_id, text, due_date) VALUES (?1, ?2, ?3, ?4)",
+        (identity_id, reflection_id, text, due_date),
+    )
+    .map_err(|e| e.to_string())?;
+    get_commitment_by_id(conn, conn.last_insert_rowid())
+}
+
+#[tauri::command]
+pub fn create_commitment(app: AppHandle, input: CreateCommitmentInput) -> Result<Commitment, String> {
+    let conn = db::get_conn(&app)?;
+    get_identity_by_id(&conn, input.identity_id)?;
+    insert(&conn, input.identity_id, None, &input.text, &input.due_date)
+}
+
+// Turns a reflection's identity correction into a commitment, due the day
+// after the reflection unless `due_date` is given.
+#[tauri::command]
+pub fn create_commitment_from_reflection(
+    app: AppHandle,
+    reflection_id: i64,
+    due_date: Option<String>,
+) -> Result<Commitment, String> {
+    let conn = db::get_conn(&app)?;
+    let reflection = get_reflection_by_id(&conn, reflection_id)?;
+    let correction = reflection
+        .reflection
+        .map(|r| r.identity_correction)
+        .filter(|c| !c.trim().is_empty())
+        .ok_or_else(|| format!("Reflection {} has no identity correction", reflection_id))?;
+    let due_date = match due_date {
+        Some(d) => d,
+        None => dates::format_date(dates::parse_date(&reflection.date)? + Duration::days(1)),
+    };
+    insert(&conn, reflection.identity_id, Some(reflection_id), &correction, &due_date)
+}
+
+// Commitments for an identity by due date, newest first, optionally only
+// those with `status`.
+#[tauri::command]
+pub fn list_commitments(
+    app: AppHandle,
+    identity_id: i64,
+    status: Option<String>,
+) -> Result<Vec<Commitment>, String> {
+    let conn = db::get_conn(&app)?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM commitment WHERE identity_id = ?1 AND (?2 IS NULL OR status = ?2) \
+             ORDER BY due_date DESC, id DESC",
+            COMMITMENT_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map((identity_id, status), commitment_from_row)
+        .map_err(|e| e.to_string())?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(out)
+}
+
+// Marks a commitment kept or broken, or reopens it.
+#[tauri::command]
+pub fn resolve_commitment(
+    app: AppHandle,
+    id: i64,
+    status: String,
+    note: Option<String>,
+) -> Result<Commitment, String> {
+    if !STATUSES.contains(&status.as_str()) {
+        return Err(format!("status must be one of {}, got '{}'", STATUSES.join(", "), status));
+    }
+    let note = note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+    let conn = db::get_conn(&app)?;
+    let updated = conn
+        .execute(
+            "UPDATE commitment SET status = ?2, note = ?3, \
+             resolved_at = CASE WHEN ?2 = 'open' THEN NULL ELSE datetime('now') END, \
+             updated_at = datetime('now') WHERE id = ?1",
+            (id, &status, note),
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Commitment {} not found", id));
+    }
+    get_commitment_by_id(&conn, id)
+}
+
+// Replaces the behaviors linked to a commitment. Behaviors of other
+// identities are ignored.
+#[tauri::command]
+pub fn link_commitment_behaviors(app: AppHandle, id: i64, behavior_ids: Vec<i64>) -> Result<Commitment, String> {
+    let mut conn = db::get_conn(&app)?;
+    let commitment = get_commitment_by_id(&conn, id)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM commitment_behavior WHERE commitment_id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    for behavior_id in behavior_ids {
+        tx.execute(
+            "INSERT OR IGNORE INTO commitment_behavior (commitment_id, behavior_id) \
+             SELECT ?1, id FROM behavior_log WHERE id = ?2 AND identity_id = ?3",
+            (id, behavior_id, commitment.identity_id),
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    get_commitment_by_id(&conn, id)
+}
+
+#[tauri::command]
+pub fn delete_commitment(app: AppHandle, id: i64) -> Result<(), String> {
+    let conn = db::get_conn(&app)?;
+    conn.execute("DELETE FROM commitment WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::credentials::{self, KeyStore};
+use crate::commands::settings;
+use crate::db;
+use crate::llm::{self, ProviderKind};
+
+// What the webview may learn about a stored key: whether it exists and where
+// it lives, never the key itself.
+#[derive(Debug, Serialize)]
+pub struct CredentialStatus {
+    pub provider: ProviderKind,
+    pub configured: bool,
+    pub store: Option<KeyStore>,
+}
+
+#[tauri::command]
+pub fn set_api_key(
+    app: AppHandle,
+    provider: ProviderKind,
+    api_key: String,
+) -> Result<CredentialStatus, String> {
+    let api_key = api_key.trim();
+    if api_key.is_empty() {
+        return Err("API key is empty".to_string());
+    }
+    let allow_file = settings::load_settings(&db::get_conn(&app)?)?.allow_file_credentials;
+    let store = credentials::store_key(&app, provider.as_str(), api_key, allow_file)?;
+    Ok(CredentialStatus {
+        provider,
+        configured: true,
+        store: Some(store),
+    })
+}
+
+#[tauri::command]
+pub fn get_api_key_status(app: AppHandle, provider: ProviderKind) -> Result<CredentialStatus, String> {
+    let store = credentials::load_key(&app, provider.as_str())?.map(|(_, store)| store);
+    Ok(CredentialStatus {
+        provider,
+        configured: store.is_some(),
+        store,
+    })
+}
+
+#[tauri::command]
+pub fn delete_api_key(app: AppHandle, provider: ProviderKind) -> Result<(), String> {
+    credentials::delete_key(&app, provider.as_str())
+}
+
+#[tauri::command]
+pub async fn test_ai_connection(app: AppHandle, identity_id: Option<i64>) -> Result<(), String> {
+    let provider = {
+        let conn = db::get_conn(&app)?;
+        let config = llm::load_config(&conn, identity_id)?;
+        llm::metered_provider(&app, &config)?
+    };
+    let request = llm::ChatRequest::new("You are a connection check.", "Reply with OK.").temperature(0.0);
+    provider.complete(&request).await.map(|_| ())
+}
@@ -0,0 +1,233 @@
+use rusqlite::{Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::commands::reflection::get_reflection_by_id;
+use crate::{dates, db};
+
+const REACTIONS: [&str; 2] = ["resonated", "missed"];
+
+// Ratings at or below this count as negative feedback.
+const NEGATIVE_RATING: i32 = 2;
+
+// How many negative feedback entries are quoted back to the model.
+const PROMPT_FEEDBACK: usize = 5;
+
+#[derive(Debug, Serialize)]
+pub struct ReflectionFeedback {
+    pub id: i64,
+    pub reflection_id: i64,
+    // Date and identity of the reflection the feedback is about.
+    pub date: String,
+    pub identity_id: i64,
+    // 1 (off-base) to 5 (spot on).
+    pub rating: Option<i32>,
+    pub comment: Option<String>,
+    pub observations: Vec<ObservationFeedback>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObservationFeedback {
+    // Position in the reflection's observations.
+    pub index: usize,
+    // "resonated" or "missed".
+    pub reaction: Option<String>,
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReflectionFeedbackInput {
+    pub rating: Option<i32>,
+    pub comment: Option<String>,
+    #[serde(default)]
+    pub observations: Vec<ObservationFeedback>,
+}
+
+impl ReflectionFeedback {
+    fn is_negative(&self) -> bool {
+        self.rating.is_some_and(|r| r <= NEGATIVE_RATING)
+            || self.observations.iter().any(|o| o.reaction.as_deref() == Some("missed"))
+    }
+}
+
+const FEEDBACK_COLUMNS: &str = "f.id, f.reflection_id, r.date, r.identity_id, f.rating, f.comment, \
+     f.observations, f.created_at, f.updated_at";
+
+const FEEDBACK_FROM: &str = "reflection_feedback f JOIN daily_reflection r ON r.id = f.reflection_id";
+
+fn feedback_from_row(row: &Row) -> rusqlite::Result<ReflectionFeedback> {
+    let observations: String = row.get(6)?;
+    Ok(ReflectionFeedback {
+        id: row.get(0)?,
+        reflection_id: row.get(1)?,
+        date: row.get(2)?,
+        identity_id: row.get(3)?,
+        rating: row.get(4)?,
+        comment: row.get(5)?,
+        observations: serde_json::from_str(&observations).unwrap_or_default(),
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
+
+fn feedback_for_reflection(conn: &Connection, reflection_id: i64) -> Result<Option<ReflectionFeedback>, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM {} WHERE f.reflection_id = ?1", FEEDBACK_COLUMNS, FEEDBACK_FROM),
+        [reflection_id],
+        feedback_from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+fn non_empty(text: Option<String>) -> Option<String> {
+    text.map(|t| t.trim().to_string()).filter(|t| !t.is_empty())
+}
+
+// Records or replaces the feedback on a reflection version.
+#[tauri::command]
+pub fn set_reflection_feedback(
+    app: AppHandle,
+    reflection_id: i64,
+    input: ReflectionFeedbackInput,
+) -> Result<ReflectionFeedback, String> {
+    let conn = db::get_conn(&app)?;
+    let reflection = get_reflection_by_id(&conn, reflection_id)?;
+    let observation_count = reflection.reflection.map(|r| r.observations.len()).unwrap_or(0);
+
+    if let Some(rating) = input.rating {
+        if !(1..=5).contains(&rating) {
+            return Err("rating must be between 1 and 5".to_string());
+        }
+    }
+    let mut observations: Vec<ObservationFeedback> = Vec::new();
+    for o in input.observations {
+        if o.index >= observation_count {
+            return Err(format!("Reflection {} has no observation {}", reflection_id, o.index));
+        }
+        if let Some(reaction) = &o.reaction {
+            if !REACTIONS.contains(&reaction.as_str()) {
+                return Err(format!("reaction must be one of {}, got '{}'", REACTIONS.join(", "), reaction));
+            }
+        }
+        let o = ObservationFeedback {
+            comment: non_empty(o.comment),
+            ..o
+        };
+        if o.reaction.is_none() && o.comment.is_none() {
+            continue;
+        }
+        // The last entry for an observation wins.
+        observations.retain(|existing| existing.index != o.index);
+        observations.push(o);
+    }
+    observations.sort_by_key(|o| o.index);
+
+    conn.execute(
+        "INSERT INTO reflection_feedback (reflection_id, rating, comment, observations) VALUES (?1, ?2, ?3, ?4) \
+         ON CONFLICT(reflection_id) DO UPDATE SET rating = excluded.rating, comment = excluded.comment, \
+         observations = excluded.observations, updated_at = datetime('now')",
+        (
+            reflection_id,
+            input.rating,
+            non_empty(input.comment),
+            serde_json::to_string(&observations).map_err(|e| e.to_string())?,
+        ),
+    )
+    .map_err(|e| e.to_string())?;
+    feedback_for_reflection(&conn, reflection_id)?.ok_or_else(|| "Feedback was not saved".to_string())
+}
+
+#[tauri::command]
+pub fn get_reflection_feedback(app: AppHandle, reflection_id: i64) -> Result<Option<ReflectionFeedback>, String> {
+    let conn = db::get_conn(&app)?;
+    feedback_for_reflection(&conn, reflection_id)
+}
+
+#[tauri::command]
+pub fn delete_reflection_feedback(app: AppHandle, reflection_id: i64) -> Result<(), String> {
+    let conn = db::get_conn(&app)?;
+    conn.execute("DELETE FROM reflection_feedback WHERE reflection_id = ?1", [reflection_id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Feedback for an identity, newest reflection first. With `negative_only`,
+// only low ratings and entries with a missed observation are returned.
+#[tauri::command]
+pub fn list_reflection_feedback(
+    app: AppHandle,
+    identity_id: i64,
+    negative_only: Option<bool>,
+    limit: Option<i64>,
+) -> Result<Vec<ReflectionFeedback>, String> {
+    let conn = db::get_conn(&app)?;
+    let mut out = feedback_before(&conn, identity_id, None)?;
+    if negative_only.unwrap_or(false) {
+        out.retain(ReflectionFeedback::is_negative);
+    }
+    out.truncate(limit.unwrap_or(30).max(1) as usize);
+    Ok(out)
+}
+
+fn feedback_before(
+    conn: &Connection,
+    identity_id: i64,
+    before: Option<&str>,
+) -> Result<Vec<ReflectionFeedback>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM {} WHERE r.identity_id = ?1 AND (?2 IS NULL OR r.date < ?2) \
+             ORDER BY r.date DESC, f.id DESC",
+            FEEDBACK_COLUMNS, FEEDBACK_FROM
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map((identity_id, before), feedback_from_row)
+        .map_err(|e| e.to_string())?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(out)
+}
+
+// Recent negative feedback from before `date`, one line per point, for the
+// reflection prompt.
+pub(crate) fn negative_feedback_lines(
+    conn: &Connection,
+    identity_id: i64,
+    date: chrono::NaiveDate,
+) -> Result<Vec<String>, String> {
+    let before = dates::format_date(date);
+    let mut lines = Vec::new();
+    for feedback in feedback_before(conn, identity_id, Some(&before))?
+        .into_iter()
+        .filter(ReflectionFeedback::is_negative)
+        .take(PROMPT_FEEDBACK)
+    {
+        let reflection = get_reflection_by_id(conn, feedback.reflection_id)?.reflection;
+        if let Some(rating) = feedback.rating.filter(|r| *r <= NEGATIVE_RATING) {
+            let mut line = format!("- {}: reflection rated {}/5", feedback.date, rating);
+            if let Some(comment) = &feedback.comment {
+                line.push_str(&format!(" (\"{}\")", comment));
+            }
+            lines.push(line);
+        }
+        for o in feedback.observations.iter().filter(|o| o.reaction.as_deref() == Some("missed")) {
+            let text = reflection
+                .as_ref()
+                .and_then(|r| r.observations.get(o.index))
+                .map(String::as_str)
+                .unwrap_or("(observation)");
+            let mut line = format!("- {}: observation missed: \"{}\"", feedback.date, text);
+            if let Some(comment) = &o.comment {
+                line.push_str(&format!(" (\"{}\")", comment));
+            }
+            lines.push(line);
+        }
+    }
+    Ok(lines)
+}
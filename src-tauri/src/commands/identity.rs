@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use tauri::AppHandle;
 
-use crate::db;
+use crate::{db, language};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Identity {
@@ -9,9 +9,26 @@ pub struct Identity {
     pub name: String,
     pub description: String,
     pub user_id: i64,
+    pub prompt_template_id: Option<i64>,
+    // Overrides the global language setting for this identity's reflections.
+    pub language: Option<String>,
     pub created_at: String,
 }
 
+const IDENTITY_COLUMNS: &str = "id, name, description, user_id, prompt_template_id, language, created_at";
+
+fn identity_from_row(row: &rusqlite::Row) -> rusqlite::Result<Identity> {
+    Ok(Identity {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        user_id: row.get(3)?,
+        prompt_template_id: row.get(4)?,
+        language: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CreateIdentityInput {
     pub name: String,
@@ -31,19 +48,11 @@ pub fn create_identity(app: AppHandle, user_id: i64, input: CreateIdentityInput)
     get_identity_by_id(&conn, id)
 }
 
-fn get_identity_by_id(conn: &rusqlite::Connection, id: i64) -> Result<Identity, String> {
+pub(crate) fn get_identity_by_id(conn: &rusqlite::Connection, id: i64) -> Result<Identity, String> {
     conn.query_row(
-        "SELECT id, name, description, user_id, created_at FROM identity WHERE id = ?1",
+        &format!("SELECT {} FROM identity WHERE id = ?1", IDENTITY_COLUMNS),
         [id],
-        |row| {
-            Ok(Identity {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                description: row.get(2)?,
-                user_id: row.get(3)?,
-                created_at: row.get(4)?,
-            })
-        },
+        identity_from_row,
     )
     .map_err(|e| e.to_string())
 }
@@ -52,18 +61,13 @@ fn get_identity_by_id(conn: &rusqlite::Connection, id: i64) -> Result<Identity,
 pub fn list_identities(app: AppHandle, user_id: i64) -> Result<Vec<Identity>, String> {
     let conn = db::get_conn(&app)?;
     let mut stmt = conn
-        .prepare("SELECT id, name, description, user_id, created_at FROM identity WHERE user_id = ?1 ORDER BY created_at DESC")
+        .prepare(&format!(
+            "SELECT {} FROM identity WHERE user_id = ?1 ORDER BY created_at DESC",
+            IDENTITY_COLUMNS
+        ))
         .map_err(|e| e.to_string())?;
     let rows = stmt
-        .query_map([user_id], |row| {
-            Ok(Identity {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                description: row.get(2)?,
-                user_id: row.get(3)?,
-                created_at: row.get(4)?,
-            })
-        })
+        .query_map([user_id], identity_from_row)
         .map_err(|e| e.to_string())?;
     let mut out = Vec::new();
     for r in rows {
@@ -76,17 +80,11 @@ pub fn list_identities(app: AppHandle, user_id: i64) -> Result<Vec<Identity>, St
 pub fn get_identity(app: AppHandle, id: i64) -> Result<Option<Identity>, String> {
     let conn = db::get_conn(&app)?;
     let mut stmt = conn
-        .prepare("SELECT id, name, description, user_id, created_at FROM identity WHERE id = ?1")
+        .prepare(&format!("SELECT {} FROM identity WHERE id = ?1", IDENTITY_COLUMNS))
         .map_err(|e| e.to_string())?;
     let mut rows = stmt.query([id]).map_err(|e| e.to_string())?;
     match rows.next().map_err(|e| e.to_string())? {
-        Some(row) => Ok(Some(Identity {
-            id: row.get(0).map_err(|e| e.to_string())?,
-            name: row.get(1).map_err(|e| e.to_string())?,
-            description: row.get(2).map_err(|e| e.to_string())?,
-            user_id: row.get(3).map_err(|e| e.to_string())?,
-            created_at: row.get(4).map_err(|e| e.to_string())?,
-        })),
+        Some(row) => identity_from_row(row).map(Some).map_err(|e| e.to_string()),
         None => Ok(None),
     }
 }
@@ -109,3 +107,16 @@ pub fn update_identity(
     }
     get_identity_by_id(&conn, id)
 }
+
+// Sets the language this identity's reflections are written in; None follows
+// the global setting.
+#[tauri::command]
+pub fn set_identity_language(app: AppHandle, identity_id: i64, language: Option<String>) -> Result<Identity, String> {
+    if let Some(code) = &language {
+        language::check(code)?;
+    }
+    let conn = db::get_conn(&app)?;
+    conn.execute("UPDATE identity SET language = ?1 WHERE id = ?2", (language, identity_id))
+        .map_err(|e| e.to_string())?;
+    get_identity_by_id(&conn, identity_id)
+}
@@ -0,0 +1,510 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::commands::behavior::{insert_behavior, LogBehaviorInput};
+use crate::commands::trait_::traits_for_identity;
+use crate::{dates, db};
+
+#[derive(Debug, Deserialize)]
+pub struct ImportInput {
+    pub path: String,
+    // "csv" or "json"; inferred from the file extension when omitted.
+    pub format: Option<String>,
+    #[serde(default)]
+    pub mapping: ColumnMapping,
+    // Used for rows without an identity column or value.
+    pub default_identity_id: Option<i64>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ColumnMapping {
+    pub date: String,
+    pub description: String,
+    pub score: String,
+    pub identity: Option<String>,
+    pub tags: Option<String>,
+    pub value: Option<String>,
+    pub unit: Option<String>,
+}
+
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        ColumnMapping {
+            date: "date".to_string(),
+            description: "description".to_string(),
+            score: "alignment_score".to_string(),
+            identity: Some("identity".to_string()),
+            tags: Some("tags".to_string()),
+            value: None,
+            unit: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportStatus {
+    Created,
+    Skipped,
+    Rejected,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportRowResult {
+    pub line: u64,
+    pub status: ImportStatus,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub created: usize,
+    pub skipped: usize,
+    pub rejected: usize,
+    pub rows: Vec<ImportRowResult>,
+}
+
+// A row's line and its fields, or why the row could not be read.
+type Record = (u64, Result<HashMap<String, String>, String>);
+
+#[tauri::command]
+pub fn import_behaviors(app: AppHandle, input: ImportInput) -> Result<ImportReport, String> {
+    let path = Path::new(&input.path);
+    let format = match input.format.as_deref() {
+        Some(f) => f.to_ascii_lowercase(),
+        None => path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase())
+            .unwrap_or_default(),
+    };
+    let records = match format.as_str() {
+        "csv" => read_csv(path)?,
+        "json" => read_json(path)?,
+        other => return Err(format!("Unsupported import format '{}': use csv or json", other)),
+    };
+
+    let mut conn = db::get_conn(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let report = import_records(&tx, records, &input)?;
+    // A dry run performs every insert, so its report matches a real import,
+    // and then rolls them back by dropping the transaction.
+    if !input.dry_run {
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+    Ok(report)
+}
+
+fn import_records(tx: &rusqlite::Connection, records: Vec<Record>, input: &ImportInput) -> Result<ImportReport, String> {
+    // Names are matched case-insensitively; a name shared by several
+    // identities maps to None, so rows naming it are rejected.
+    let mut identities: HashMap<String, Option<i64>> = HashMap::new();
+    let mut identity_ids: HashSet<i64> = HashSet::new();
+    {
+        let mut stmt = tx
+            .prepare("SELECT id, name FROM identity")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+            .map_err(|e| e.to_string())?;
+        for r in rows {
+            let (id, name) = r.map_err(|e| e.to_string())?;
+            identity_ids.insert(id);
+            identities
+                .entry(name.trim().to_lowercase())
+                .and_modify(|e| *e = None)
+                .or_insert(Some(id));
+        }
+    }
+    if let Some(id) = input.default_identity_id {
+        if !identity_ids.contains(&id) {
+            return Err(format!("Identity {} not found", id));
+        }
+    }
+
+    let mut traits_by_identity: HashMap<i64, HashMap<String, i64>> = HashMap::new();
+    let mut seen: HashSet<(String, String, i64)> = HashSet::new();
+    let mut report = ImportReport {
+        dry_run: input.dry_run,
+        created: 0,
+        skipped: 0,
+        rejected: 0,
+        rows: Vec::with_capacity(records.len()),
+    };
+
+    for (line, record) in records {
+        let parsed = record.and_then(|record| to_behavior_input(&record, input, &identities));
+        let parsed = parsed.and_then(|(mut behavior, tags)| {
+            let known = match traits_by_identity.entry(behavior.identity_id) {
+                Entry::Occupied(e) => e.into_mut(),
+                Entry::Vacant(e) => e.insert(
+                    traits_for_identity(tx, behavior.identity_id)?
+                        .into_iter()
+                        .map(|t| (t.name.trim().to_lowercase(), t.id))
+                        .collect(),
+                ),
+            };
+            let mut unknown = Vec::new();
+            for tag in tags {
+                match known.get(&tag.to_lowercase()) {
+                    Some(id) => behavior.trait_ids.push(*id),
+                    None => unknown.push(tag),
+                }
+            }
+            let note = (!unknown.is_empty()).then(|| format!("Unknown tags ignored: {}", unknown.join(", ")));
+            Ok((behavior, note))
+        });
+
+        let (behavior, note) = match parsed {
+            Ok(p) => p,
+            Err(message) => {
+                report.rejected += 1;
+                report.rows.push(ImportRowResult {
+                    line,
+                    status: ImportStatus::Rejected,
+                    message: Some(message),
+                });
+                continue;
+            }
+        };
+
+        let key = (behavior.date.clone(), behavior.description.clone(), behavior.identity_id);
+        let exists: bool = tx
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM behavior_log WHERE date = ?1 AND description = ?2 AND identity_id = ?3)",
+                (&key.0, &key.1, key.2),
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if exists || !seen.insert(key) {
+            report.skipped += 1;
+            report.rows.push(ImportRowResult {
+                line,
+                status: ImportStatus::Skipped,
+                message: Some("Duplicate of an existing behavior".to_string()),
+            });
+            continue;
+        }
+
+        // A row failing partway, e.g. on a trait link, must not leave its
+        // behavior behind.
+        tx.execute_batch("SAVEPOINT import_row").map_err(|e| e.to_string())?;
+        let inserted = insert_behavior(tx, &behavior);
+        let end = match inserted {
+            Ok(_) => "RELEASE import_row",
+            Err(_) => "ROLLBACK TO import_row; RELEASE import_row",
+        };
+        tx.execute_batch(end).map_err(|e| e.to_string())?;
+        if let Err(message) = inserted {
+            report.rejected += 1;
+            report.rows.push(ImportRowResult {
+                line,
+                status: ImportStatus::Rejected,
+                message: Some(message),
+            });
+            continue;
+        }
+        report.created += 1;
+        report.rows.push(ImportRowResult {
+            line,
+            status: ImportStatus::Created,
+            message: note,
+        });
+    }
+    Ok(report)
+}
+
+fn to_behavior_input(
+    record: &HashMap<String, String>,
+    input: &ImportInput,
+    identities: &HashMap<String, Option<i64>>,
+) -> Result<(LogBehaviorInput, Vec<String>), String> {
+    let mapping = &input.mapping;
+    let field = |column: &str| {
+        record
+            .get(column)
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+    };
+
+    let date = field(&mapping.date).ok_or_else(|| format!("Missing '{}'", mapping.date))?;
+    dates::parse_date(date)?;
+
+    let description = field(&mapping.description)
+        .ok_or_else(|| format!("Missing '{}'", mapping.description))?;
+
+    let score_raw = field(&mapping.score).ok_or_else(|| format!("Missing '{}'", mapping.score))?;
+    let score: i32 = score_raw
+        .parse()
+        .map_err(|_| format!("Score '{}' is not a whole number", score_raw))?;
+    if !(1..=10).contains(&score) {
+        return Err(format!("Score {} is outside 1–10", score));
+    }
+
+    let identity_id = match mapping.identity.as_deref().and_then(field) {
+        Some(name) => match identities.get(&name.to_lowercase()) {
+            Some(Some(id)) => *id,
+            Some(None) => return Err(format!("Identity name '{}' matches more than one identity", name)),
+            None => return Err(format!("Unknown identity '{}'", name)),
+        },
+        None => input
+            .default_identity_id
+            .ok_or("No identity given and no default identity selected")?,
+    };
+
+    let value = match mapping.value.as_deref().and_then(field) {
+        Some(v) => Some(
+            v.parse::<f64>()
+                .map_err(|_| format!("Value '{}' is not a number", v))?,
+        ),
+        None => None,
+    };
+    let unit = mapping.unit.as_deref().and_then(field).map(str::to_string);
+    if value.is_some() != unit.is_some() {
+        return Err("value and unit must be given together".to_string());
+    }
+
+    let tags = mapping
+        .tags
+        .as_deref()
+        .and_then(field)
+        .map(|t| {
+            t.split([',', ';'])
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok((
+        LogBehaviorInput {
+            date: date.to_string(),
+            description: description.to_string(),
+            identity_id,
+            alignment_score: score,
+            suggested_score: None,
+            value,
+            unit,
+            trait_ids: Vec::new(),
+        },
+        tags,
+    ))
+}
+
+fn read_csv(path: &Path) -> Result<Vec<Record>, String> {
+    parse_csv(std::fs::File::open(path).map_err(|e| e.to_string())?)
+}
+
+// A malformed record (e.g. invalid UTF-8) rejects only its own line; an
+// unreadable header still fails the whole import.
+fn parse_csv(input: impl Read) -> Result<Vec<Record>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(input);
+    let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+    let mut out = Vec::new();
+    let mut last_line = 1;
+    for result in reader.records() {
+        match result {
+            Ok(record) => {
+                let line = record.position().map(|p| p.line()).unwrap_or(last_line + 1);
+                let fields = headers
+                    .iter()
+                    .zip(record.iter())
+                    .map(|(h, v)| (h.to_string(), v.to_string()))
+                    .collect();
+                last_line = line;
+                out.push((line, Ok(fields)));
+            }
+            Err(e) => {
+                let line = e.position().map(|p| p.line()).unwrap_or(last_line + 1);
+                // An I/O error means nothing further can be read.
+                if matches!(e.kind(), csv::ErrorKind::Io(_)) {
+                    return Err(e.to_string());
+                }
+                last_line = line;
+                out.push((line, Err(format!("Malformed CSV record: {}", e))));
+            }
+        }
+    }
+    Ok(out)
+}
+
+// JSON files hold an array of objects; the "line" reported for each is its
+// 1-based position in the array.
+fn read_json(path: &Path) -> Result<Vec<Record>, String> {
+    parse_json(&std::fs::read_to_string(path).map_err(|e| e.to_string())?)
+}
+
+fn parse_json(text: &str) -> Result<Vec<Record>, String> {
+    let items: Vec<serde_json::Map<String, serde_json::Value>> =
+        serde_json::from_str(text).map_err(|e| format!("Invalid JSON import file: {}", e))?;
+    Ok(items
+        .into_iter()
+        .enumerate()
+        .map(|(i, obj)| {
+            let fields = obj
+                .into_iter()
+                .filter_map(|(k, v)| {
+                    let v = match v {
+                        serde_json::Value::String(s) => s,
+                        serde_json::Value::Array(items) => items
+                            .iter()
+                            .map(|i| i.as_str().map(str::to_string).unwrap_or_else(|| i.to_string()))
+                            .collect::<Vec<_>>()
+                            .join(","),
+                        serde_json::Value::Null => return None,
+                        other => other.to_string(),
+                    };
+                    Some((k, v))
+                })
+                .collect();
+            (i as u64 + 1, Ok(fields))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(dry_run: bool) -> ImportInput {
+        ImportInput {
+            path: String::new(),
+            format: None,
+            mapping: ColumnMapping {
+                value: Some("value".to_string()),
+                unit: Some("unit".to_string()),
+                ..ColumnMapping::default()
+            },
+            default_identity_id: Some(1),
+            dry_run,
+        }
+    }
+
+    fn record(fields: &[(&str, &str)]) -> HashMap<String, String> {
+        fields.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn maps_a_record_to_a_behavior() {
+        let identities = HashMap::from([("a writer".to_string(), Some(1)), ("a runner".to_string(), None)]);
+        let (behavior, tags) = to_behavior_input(
+            &record(&[
+                ("date", "2024-03-01"),
+                ("description", " Wrote 500 words "),
+                ("alignment_score", "8"),
+                ("identity", "A Writer"),
+                ("tags", "focused; patient,"),
+                ("value", "500"),
+                ("unit", "words"),
+            ]),
+            &input(false),
+            &identities,
+        )
+        .unwrap();
+        assert_eq!(behavior.description, "Wrote 500 words");
+        assert_eq!(behavior.identity_id, 1);
+        assert_eq!(behavior.value, Some(500.0));
+        assert_eq!(tags, vec!["focused", "patient"]);
+
+        let reject =
+            |fields: &[(&str, &str)]| to_behavior_input(&record(fields), &input(false), &identities).unwrap_err();
+        assert!(reject(&[("date", "2024-03-01"), ("description", "x"), ("alignment_score", "11")]).contains("outside"));
+        assert!(reject(&[("date", "03/01/2024"), ("description", "x"), ("alignment_score", "5")]).contains("Invalid date"));
+        assert!(reject(&[("date", "2024-03-01"), ("description", "x"), ("alignment_score", "5"), ("identity", "nobody")])
+            .contains("Unknown identity"));
+        assert!(reject(&[("date", "2024-03-01"), ("description", "x"), ("alignment_score", "5"), ("identity", "A Runner")])
+            .contains("more than one identity"));
+        assert!(reject(&[("date", "2024-03-01"), ("description", "x"), ("alignment_score", "5"), ("value", "3")])
+            .contains("together"));
+    }
+
+    #[test]
+    fn malformed_csv_records_reject_only_their_line() {
+        let mut csv = b"date,description,alignment_score\n2024-03-01,Ran,7\n".to_vec();
+        csv.extend_from_slice(b"2024-03-02,\xff\xfe,7\n2024-03-03,Read,6\n");
+        let records = parse_csv(csv.as_slice()).unwrap();
+        let lines: Vec<(u64, bool)> = records.iter().map(|(line, r)| (*line, r.is_ok())).collect();
+        assert_eq!(lines, vec![(2, true), (3, false), (4, true)]);
+    }
+
+    #[test]
+    fn json_lines_are_array_positions() {
+        let records = parse_json(r#"[{"date": "2024-03-01", "tags": ["a", "b"], "unit": null}, {}]"#).unwrap();
+        assert_eq!(records.len(), 2);
+        let (line, fields) = &records[0];
+        assert_eq!(*line, 1);
+        let fields = fields.as_ref().unwrap();
+        assert_eq!(fields["tags"], "a,b");
+        assert!(!fields.contains_key("unit"));
+        assert!(parse_json("{}").is_err());
+    }
+
+    #[test]
+    fn dry_run_reports_what_a_real_import_would() {
+        let csv = "date,description,alignment_score,value,unit\n\
+                   2024-03-01,Ran,7,5,km\n\
+                   2024-03-01,Ran,7,5,km\n\
+                   2024-03-02,Swam,6,NaN,m\n";
+        let summary = |report: &ImportReport| (report.created, report.skipped, report.rejected);
+
+        let mut conn = db::test_conn();
+        let tx = conn.transaction().unwrap();
+        let dry = import_records(&tx, parse_csv(csv.as_bytes()).unwrap(), &input(true)).unwrap();
+        drop(tx);
+        // The NaN value passes parsing but is refused on insert, in both modes.
+        assert_eq!(summary(&dry), (1, 1, 1));
+        let count = |conn: &rusqlite::Connection| -> i64 {
+            conn.query_row("SELECT COUNT(*) FROM behavior_log", [], |row| row.get(0)).unwrap()
+        };
+        assert_eq!(count(&conn), 0);
+
+        let tx = conn.transaction().unwrap();
+        let real = import_records(&tx, parse_csv(csv.as_bytes()).unwrap(), &input(false)).unwrap();
+        tx.commit().unwrap();
+        assert_eq!(summary(&real), summary(&dry));
+        assert_eq!(count(&conn), 1);
+    }
+
+    #[test]
+    fn rows_failing_partway_leave_nothing_behind() {
+        let mut conn = db::test_conn();
+        conn.execute_batch(
+            "INSERT INTO trait (name, identity_id) VALUES ('focused', 1);
+             INSERT INTO identity (name, description, user_id) VALUES ('A Writer', '', 1);
+             CREATE TRIGGER no_links BEFORE INSERT ON behavior_trait BEGIN SELECT RAISE(ABORT, 'link refused'); END;",
+        )
+        .unwrap();
+        let csv = "date,description,alignment_score,tags,identity\n\
+                   2024-03-01,Wrote,7,focused,\n\
+                   2024-03-01,Ran,6,,\n\
+                   2024-03-01,Read,6,,a writer\n";
+        let tx = conn.transaction().unwrap();
+        let report = import_records(&tx, parse_csv(csv.as_bytes()).unwrap(), &input(false)).unwrap();
+        tx.commit().unwrap();
+
+        assert_eq!((report.created, report.rejected), (1, 2), "{:?}", report.rows);
+        assert!(report.rows[0].message.as_deref().unwrap().contains("link refused"));
+        assert!(report.rows[2].message.as_deref().unwrap().contains("more than one identity"));
+        let descriptions: Vec<String> = conn
+            .prepare("SELECT description FROM behavior_log")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(descriptions, vec!["Ran"]);
+    }
+}
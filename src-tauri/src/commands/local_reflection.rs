@@ -0,0 +1,247 @@
+use crate::commands::behavior::BehaviorLog;
+use crate::commands::reflection::{Reflection, ReflectionContext};
+
+// Difference from the baseline, in points, below which a day counts as in
+// line with it.
+const BASELINE_TOLERANCE: f64 = 0.5;
+
+// A reflection built from fixed rules, for when no provider is configured or
+// the call fails. The same context always gives the same text. The rules are
+// written in English only, whatever the identity's output language; callers
+// note that on the stored version.
+pub(crate) fn local_reflection(context: &ReflectionContext) -> Reflection {
+    let name = &context.identity.name;
+    let quiet_traits: Vec<&str> = context
+        .traits
+        .iter()
+        .filter(|t| !context.behaviors.iter().any(|b| b.trait_ids.contains(&t.id)))
+        .map(|t| t.name.as_str())
+        .collect();
+
+    let Some((best, worst)) = extremes(&context.behaviors) else {
+        return Reflection {
+            title: "No behaviors logged".to_string(),
+            alignment_summary: format!(
+                "Nothing was logged for this day, so there is no evidence either way about being {}.",
+                name
+            ),
+            observations: vec![
+                "The logging streak ends here; a day without entries is a day the identity went unrecorded."
+                    .to_string(),
+                baseline_note(context.baseline),
+                traits_note(&quiet_traits, context.traits.len()),
+            ],
+            identity_correction: format!("Log at least one behavior tomorrow that shows being {}.", name),
+            closing_statement: format!("Being {} starts again with the next thing you do.", name),
+            follow_up: None,
+        };
+    };
+
+    let count = context.behaviors.len();
+    let average = context.behaviors.iter().map(|b| b.alignment_score as f64).sum::<f64>() / count as f64;
+    let spread = best.alignment_score - worst.alignment_score;
+
+    let title = if average >= 8.0 {
+        format!("Living as {}", name)
+    } else if average >= 6.0 {
+        "Mostly aligned".to_string()
+    } else if average >= 4.0 {
+        "A mixed day".to_string()
+    } else {
+        "Off course".to_string()
+    };
+
+    let mut alignment_summary = format!(
+        "Average alignment was {:.1}/10 across {} behavior{}",
+        average,
+        count,
+        if count == 1 { "" } else { "s" }
+    );
+    if count > 1 {
+        alignment_summary.push_str(&format!(
+            ", ranging from {} to {}",
+            worst.alignment_score, best.alignment_score
+        ));
+    }
+    alignment_summary.push_str(". ");
+    alignment_summary.push_str(&match context.baseline {
+        Some(baseline) if (average - baseline).abs() < BASELINE_TOLERANCE => {
+            format!("That is in line with the 7-day baseline of {:.1}.", baseline)
+        }
+        Some(baseline) => format!(
+            "That is {:.1} points {} the 7-day baseline of {:.1}.",
+            (average - baseline).abs(),
+            if average > baseline { "above" } else { "below" },
+            baseline
+        ),
+        None => "There is no earlier week to compare with yet.".to_string(),
+    });
+
+    let second = if count == 1 {
+        "Only one behavior was logged, so there is nothing to contrast it with.".to_string()
+    } else if spread >= 4 {
+        format!(
+            "Weakest point: \"{}\" ({}/10). A spread of {} points means consistency, not ability, is the gap.",
+            worst.description, worst.alignment_score, spread
+        )
+    } else {
+        format!(
+            "Weakest point: \"{}\" ({}/10), close to the rest of the day.",
+            worst.description, worst.alignment_score
+        )
+    };
+    let observations = vec![
+        format!(
+            "Strongest evidence: \"{}\" ({}/10).",
+            best.description, best.alignment_score
+        ),
+        second,
+        traits_note(&quiet_traits, context.traits.len()),
+    ];
+
+    let identity_correction = if let Some(first) = quiet_traits.first() {
+        format!("Tomorrow, do one deliberate thing that shows {}.", first)
+    } else if count > 1 && worst.alignment_score < 7 {
+        format!(
+            "Next time, handle \"{}\" the way someone who is {} would.",
+            worst.description, name
+        )
+    } else {
+        format!("Repeat \"{}\" tomorrow so it becomes a pattern.", best.description)
+    };
+
+    let closing_statement = if average >= 6.0 {
+        format!("Each of these actions was a vote for being {}. Keep voting.", name)
+    } else {
+        format!("One day does not decide who you are. The next action can still be {}.", name)
+    };
+
+    Reflection {
+        title,
+        alignment_summary,
+        observations,
+        identity_correction,
+        closing_statement,
+        // The rules cannot tell whether a free-text correction was acted on,
+        // so local reflections leave the follow-up unassessed.
+        follow_up: None,
+    }
+}
+
+// The highest and lowest scored behaviors; the earliest logged wins ties.
+fn extremes(behaviors: &[BehaviorLog]) -> Option<(&BehaviorLog, &BehaviorLog)> {
+    let first = behaviors.first()?;
+    let mut best = first;
+    let mut worst = first;
+    for b in &behaviors[1..] {
+        if b.alignment_score > best.alignment_score {
+            best = b;
+        }
+        if b.alignment_score < worst.alignment_score {
+            worst = b;
+        }
+    }
+    Some((best, worst))
+}
+
+fn baseline_note(baseline: Option<f64>) -> String {
+    match baseline {
+        Some(b) => format!("The 7-day baseline stands at {:.1}/10.", b),
+        None => "There is no 7-day baseline yet.".to_string(),
+    }
+}
+
+fn traits_note(quiet: &[&str], total: usize) -> String {
+    if total == 0 {
+        "No traits are defined for this identity yet, so behaviors cannot be tied to them.".to_string()
+    } else if quiet.is_empty() {
+        "Every trait had at least one behavior backing it.".to_string()
+    } else if quiet.len() == total {
+        format!("No behavior was tagged with any trait ({}).", quiet.join(", "))
+    } else {
+        format!("No behavior showed {}.", quiet.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::identity::Identity;
+    use crate::commands::trait_::Trait;
+
+    fn behavior(id: i64, description: &str, score: i32, trait_ids: Vec<i64>) -> BehaviorLog {
+        BehaviorLog {
+            id,
+            date: "2024-03-04".to_string(),
+            description: description.to_string(),
+            identity_id: 1,
+            alignment_score: score,
+            suggested_score: None,
+            value: None,
+            unit: None,
+            trait_ids,
+            created_at: String::new(),
+        }
+    }
+
+    fn context(behaviors: Vec<BehaviorLog>, baseline: Option<f64>) -> ReflectionContext {
+        let traits = ["focused", "patient"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| Trait {
+                id: i as i64 + 1,
+                name: name.to_string(),
+                identity_id: 1,
+                definition: None,
+                created_at: String::new(),
+            })
+            .collect();
+        ReflectionContext {
+            identity: Identity {
+                id: 1,
+                name: "a writer".to_string(),
+                description: String::new(),
+                user_id: 1,
+                prompt_template_id: None,
+                language: None,
+                created_at: String::new(),
+            },
+            traits,
+            behaviors,
+            week_average: baseline,
+            baseline,
+            streak: 1,
+            previous: Vec::new(),
+            language: "en".to_string(),
+            feedback: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn builds_from_scores_baseline_and_traits() {
+        let r = local_reflection(&context(
+            vec![
+                behavior(1, "Wrote 500 words", 9, vec![1]),
+                behavior(2, "Doomscrolled an hour", 3, vec![]),
+            ],
+            Some(4.0),
+        ));
+        assert_eq!(r.title, "Mostly aligned");
+        assert!(r.alignment_summary.contains("6.0/10 across 2 behaviors, ranging from 3 to 9"));
+        assert!(r.alignment_summary.contains("2.0 points above the 7-day baseline of 4.0"));
+        assert_eq!(r.observations.len(), 3);
+        assert!(r.observations[0].contains("Wrote 500 words"));
+        assert!(r.observations[1].contains("spread of 6 points"));
+        assert_eq!(r.observations[2], "No behavior showed patient.");
+        assert_eq!(r.identity_correction, "Tomorrow, do one deliberate thing that shows patient.");
+        assert!(r.follow_up.is_none());
+    }
+
+    #[test]
+    fn handles_an_empty_day() {
+        let r = local_reflection(&context(Vec::new(), None));
+        assert_eq!(r.title, "No behaviors logged");
+        assert_eq!(r.observations.len(), 3);
+        assert_eq!(r.observations[1], "There is no 7-day baseline yet.");
+    }
+}
@@ -0,0 +1,153 @@
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::db;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Metric {
+    pub id: i64,
+    pub name: String,
+    pub unit: String,
+    pub target: f64,
+    pub period: String,
+    pub identity_id: i64,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateMetricInput {
+    pub name: String,
+    pub unit: String,
+    pub target: f64,
+    pub period: String,
+}
+
+fn metric_from_row(row: &rusqlite::Row) -> rusqlite::Result<Metric> {
+    Ok(Metric {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        unit: row.get(2)?,
+        target: row.get(3)?,
+        period: row.get(4)?,
+        identity_id: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+#[tauri::command]
+pub fn create_metric(
+    app: AppHandle,
+    identity_id: i64,
+    input: CreateMetricInput,
+) -> Result<Metric, String> {
+    let conn = db::get_conn(&app)?;
+    insert_metric(&conn, identity_id, &input)
+}
+
+// Behaviors are counted toward a metric by their unit, so each unit may back
+// only one metric per identity.
+pub(crate) fn insert_metric(
+    conn: &rusqlite::Connection,
+    identity_id: i64,
+    input: &CreateMetricInput,
+) -> Result<Metric, String> {
+    let unit = input.unit.trim();
+    if input.name.trim().is_empty() || unit.is_empty() {
+        return Err("Metric name and unit are required".to_string());
+    }
+    if !(input.target.is_finite() && input.target > 0.0) {
+        return Err("target must be greater than 0".to_string());
+    }
+    if input.period != "daily" && input.period != "weekly" {
+        return Err("period must be 'daily' or 'weekly'".to_string());
+    }
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT name FROM metric WHERE identity_id = ?1 AND unit = ?2 COLLATE NOCASE",
+            (identity_id, unit),
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if let Some(name) = existing {
+        return Err(format!(
+            "The metric '{}' already counts behaviors logged in '{}'; use a different unit",
+            name, unit
+        ));
+    }
+    conn.execute(
+        "INSERT INTO metric (name, unit, target, period, identity_id) VALUES (?1, ?2, ?3, ?4, ?5)",
+        (input.name.trim(), unit, input.target, &input.period, identity_id),
+    )
+    .map_err(|e| e.to_string())?;
+    let id = conn.last_insert_rowid();
+    conn.query_row(
+        "SELECT id, name, unit, target, period, identity_id, created_at FROM metric WHERE id = ?1",
+        [id],
+        metric_from_row,
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_metrics(app: AppHandle, identity_id: i64) -> Result<Vec<Metric>, String> {
+    let conn = db::get_conn(&app)?;
+    metrics_for_identity(&conn, identity_id)
+}
+
+pub(crate) fn metrics_for_identity(
+    conn: &rusqlite::Connection,
+    identity_id: i64,
+) -> Result<Vec<Metric>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, name, unit, target, period, identity_id, created_at FROM metric WHERE identity_id = ?1 ORDER BY created_at")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([identity_id], metric_from_row)
+        .map_err(|e| e.to_string())?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(out)
+}
+
+#[tauri::command]
+pub fn delete_metric(app: AppHandle, id: i64) -> Result<(), String> {
+    let conn = db::get_conn(&app)?;
+    conn.execute("DELETE FROM metric WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(name: &str, unit: &str) -> CreateMetricInput {
+        CreateMetricInput {
+            name: name.to_string(),
+            unit: unit.to_string(),
+            target: 30.0,
+            period: "daily".to_string(),
+        }
+    }
+
+    #[test]
+    fn one_metric_per_unit_and_identity() {
+        let conn = db::test_conn();
+        insert_metric(&conn, 1, &input("minutes meditated", "min")).unwrap();
+        let err = insert_metric(&conn, 1, &input("minutes exercised", "MIN")).unwrap_err();
+        assert!(err.contains("minutes meditated"), "{}", err);
+        // Another identity may reuse the unit.
+        insert_metric(&conn, 2, &input("minutes exercised", "min")).unwrap();
+        // The index backs the check for writes that bypass it.
+        assert!(conn
+            .execute(
+                "INSERT INTO metric (name, unit, target, period, identity_id) VALUES ('x', 'Min', 1, 'daily', 1)",
+                [],
+            )
+            .is_err());
+    }
+}
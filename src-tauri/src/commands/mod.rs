@@ -1,6 +1,22 @@
+pub mod action;
 pub mod analytics;
+pub mod backfill;
 pub mod behavior;
+pub mod commitment;
+pub mod credentials;
+pub mod feedback;
 pub mod identity;
+pub mod import;
+pub mod local_reflection;
+pub mod metric;
+pub mod period_reflection;
+pub mod preset;
+pub mod prompt_template;
+pub mod redaction;
 pub mod reflection;
+pub mod reflection_chat;
+pub mod reflection_queue;
+pub mod settings;
 pub mod trait_;
+pub mod usage;
 pub mod user;
@@ -0,0 +1,621 @@
+use chrono::{Datelike, Duration, Months, NaiveDate};
+use rusqlite::{Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::commands::behavior::{behaviors_for_date, BehaviorLog};
+use crate::commands::identity::get_identity_by_id;
+use crate::commands::redaction::redactor;
+use crate::commands::reflection::CURRENT_VERSION;
+use crate::commands::trait_::traits_for_identity;
+use crate::llm::redact::Redactor;
+use crate::llm::ReflectionProvider;
+use crate::{dates, db, language, llm};
+
+// Day-by-day detail above this many characters is summarized in chunks
+// before the final report call, so a busy month still fits in context.
+const CHUNK_CHARS: usize = 12_000;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    Week,
+    Month,
+}
+
+impl Period {
+    fn as_str(self) -> &'static str {
+        match self {
+            Period::Week => "week",
+            Period::Month => "month",
+        }
+    }
+
+    // Weeks run Monday to Sunday and months from the 1st, whatever day
+    // inside the period `start` names.
+    fn bounds(self, start: NaiveDate) -> (NaiveDate, NaiveDate) {
+        match self {
+            Period::Week => {
+                let first = dates::week_start(start);
+                (first, first + Duration::days(6))
+            }
+            Period::Month => {
+                let first = start.with_day(1).unwrap_or(start);
+                let next = first.checked_add_months(Months::new(1)).unwrap_or(first);
+                (first, next - Duration::days(1))
+            }
+        }
+    }
+
+    fn previous(self, first: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Week => first - Duration::days(7),
+            Period::Month => first.checked_sub_months(Months::new(1)).unwrap_or(first),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeriodReport {
+    pub title: String,
+    pub wins: Vec<String>,
+    pub misalignments: Vec<String>,
+    // "improving", "stable" or "declining".
+    pub trend: String,
+    pub trend_summary: String,
+    pub focus: String,
+}
+
+impl PeriodReport {
+    fn restore(self, redactor: &Redactor) -> Self {
+        PeriodReport {
+            title: redactor.restore(&self.title),
+            wins: self.wins.iter().map(|w| redactor.restore(w)).collect(),
+            misalignments: self.misalignments.iter().map(|m| redactor.restore(m)).collect(),
+            trend_summary: redactor.restore(&self.trend_summary),
+            focus: redactor.restore(&self.focus),
+            ..self
+        }
+    }
+
+    // Checks the structure and that the text is in `language`.
+    fn validate(&self, language: &str) -> Result<(), String> {
+        for (name, value) in [
+            ("title", &self.title),
+            ("trendSummary", &self.trend_summary),
+            ("focus", &self.focus),
+        ] {
+            if value.trim().is_empty() {
+                return Err(format!("{} is empty", name));
+            }
+        }
+        if !["improving", "stable", "declining"].contains(&self.trend.as_str()) {
+            return Err(format!(
+                "trend must be improving, stable or declining, got '{}'",
+                self.trend
+            ));
+        }
+        for (name, items) in [("wins", &self.wins), ("misalignments", &self.misalignments)] {
+            if items.is_empty() || items.len() > 5 {
+                return Err(format!("{} must have 1 to 5 entries", name));
+            }
+            if items.iter().any(|i| i.trim().is_empty()) {
+                return Err(format!("{} contains an empty entry", name));
+            }
+        }
+        let mut texts = vec![self.title.as_str(), self.trend_summary.as_str(), self.focus.as_str()];
+        texts.extend(self.wins.iter().chain(&self.misalignments).map(|i| i.as_str()));
+        language::validate(language, &texts)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PeriodReflection {
+    pub id: i64,
+    pub identity_id: i64,
+    pub period: Period,
+    pub start_date: String,
+    pub end_date: String,
+    pub report: PeriodReport,
+    pub average_alignment: Option<f64>,
+    pub model: Option<String>,
+    pub created_at: String,
+}
+
+const PERIOD_COLUMNS: &str = "id, identity_id, period, start_date, end_date, title, wins, misalignments, \
+     trend, trend_summary, focus, average_alignment, model, created_at";
+
+fn period_from_row(row: &Row) -> rusqlite::Result<PeriodReflection> {
+    let period: String = row.get(2)?;
+    let wins: String = row.get(6)?;
+    let misalignments: String = row.get(7)?;
+    Ok(PeriodReflection {
+        id: row.get(0)?,
+        identity_id: row.get(1)?,
+        period: if period == "month" { Period::Month } else { Period::Week },
+        start_date: row.get(3)?,
+        end_date: row.get(4)?,
+        report: PeriodReport {
+            title: row.get(5)?,
+            wins: serde_json::from_str(&wins).unwrap_or_default(),
+            misalignments: serde_json::from_str(&misalignments).unwrap_or_default(),
+            trend: row.get(8)?,
+            trend_summary: row.get(9)?,
+            focus: row.get(10)?,
+        },
+        average_alignment: row.get(11)?,
+        model: row.get(12)?,
+        created_at: row.get(13)?,
+    })
+}
+
+const SYSTEM_INSTRUCTION: &str = r#"You are a psychologically intelligent identity performance coach.
+Review a whole period of behavior against the stated identity.
+Be specific, cite concrete days or behaviors, and avoid generic motivation.
+Name the wins that reinforced the identity, the misalignments that kept recurring,
+the direction of the trend compared with the previous period, and exactly one
+focus for the next period.
+
+Respond with valid JSON only, in this exact structure:
+{
+  "title": "string",
+  "wins": ["string"],
+  "misalignments": ["string"],
+  "trend": "improving" | "stable" | "declining",
+  "trendSummary": "string",
+  "focus": "string"
+}"#;
+
+const CHUNK_INSTRUCTION: &str = r#"You are helping prepare a period review for an identity coach.
+Condense the days below into compact notes: notable aligned behaviors, recurring
+misalignments, and how alignment moved. Keep dates. Plain text, at most 15 lines."#;
+
+fn report_schema() -> serde_json::Value {
+    let text = serde_json::json!({ "type": "string" });
+    let list = serde_json::json!({ "type": "array", "items": text, "minItems": 1, "maxItems": 5 });
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "title": text,
+            "wins": list,
+            "misalignments": list,
+            "trend": { "type": "string", "enum": ["improving", "stable", "declining"] },
+            "trendSummary": text,
+            "focus": text
+        },
+        "required": ["title", "wins", "misalignments", "trend", "trendSummary", "focus"],
+        "additionalProperties": false
+    })
+}
+
+struct DayDigest {
+    date: String,
+    behaviors: Vec<BehaviorLog>,
+    summary: Option<String>,
+    correction: Option<String>,
+}
+
+impl DayDigest {
+    fn average(&self) -> Option<f64> {
+        if self.behaviors.is_empty() {
+            return None;
+        }
+        let total: i32 = self.behaviors.iter().map(|b| b.alignment_score).sum();
+        Some(total as f64 / self.behaviors.len() as f64)
+    }
+
+    fn text(&self) -> String {
+        let mut out = format!(
+            "{} (average {}):\n",
+            self.date,
+            self.average()
+                .map(|a| format!("{:.1}/10", a))
+                .unwrap_or_else(|| "nothing logged".to_string())
+        );
+        for b in &self.behaviors {
+            out.push_str(&format!("  - {} ({}/10)\n", b.description, b.alignment_score));
+        }
+        if let Some(summary) = &self.summary {
+            out.push_str(&format!("  Reflection: {}\n", summary));
+        }
+        if let Some(correction) = &self.correction {
+            out.push_str(&format!("  Correction given: {}\n", correction));
+        }
+        out
+    }
+}
+
+// Packs whole texts, in order, into chunks of at most `limit` characters. A
+// text longer than `limit` gets a chunk of its own rather than being cut.
+fn chunk_texts(texts: impl IntoIterator<Item = String>, limit: usize) -> Vec<String> {
+    let mut chunks: Vec<String> = vec![String::new()];
+    for text in texts {
+        let current = chunks.last_mut().expect("chunks is never empty");
+        if !current.is_empty() && current.len() + text.len() > limit {
+            chunks.push(text);
+        } else {
+            current.push_str(&text);
+        }
+    }
+    chunks
+}
+
+fn load_days(
+    conn: &Connection,
+    identity_id: i64,
+    first: NaiveDate,
+    last: NaiveDate,
+) -> Result<Vec<DayDigest>, String> {
+    let mut days = Vec::new();
+    let mut date = first;
+    while date <= last {
+        let day = dates::format_date(date);
+        let (summary, correction) = conn
+            .query_row(
+                &format!(
+                    "SELECT alignment_summary, identity_correction FROM daily_reflection \
+                     WHERE identity_id = ?1 AND date = ?2 AND {}",
+                    CURRENT_VERSION
+                ),
+                (identity_id, &day),
+                |row| Ok((row.get::<_, Option<String>>(0)?, row.get::<_, Option<String>>(1)?)),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?
+            .unwrap_or((None, None));
+        days.push(DayDigest {
+            behaviors: behaviors_for_date(conn, identity_id, &day)?,
+            date: day,
+            summary,
+            correction,
+        });
+        date += Duration::days(1);
+    }
+    Ok(days)
+}
+
+fn average_between(
+    conn: &Connection,
+    identity_id: i64,
+    first: NaiveDate,
+    last: NaiveDate,
+) -> Result<Option<f64>, String> {
+    conn.query_row(
+        "SELECT AVG(alignment_score) FROM behavior_log WHERE identity_id = ?1 AND date BETWEEN ?2 AND ?3",
+        (identity_id, dates::format_date(first), dates::format_date(last)),
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn format_average(average: Option<f64>) -> String {
+    average
+        .map(|a| format!("{:.1}/10", a))
+        .unwrap_or_else(|| "no data".to_string())
+}
+
+// Asks for the report, condensing the detail first when it is too long. Every
+// prompt, the chunk summaries included, is redacted; the report comes back
+// restored, with the raw reply rebuilt from it and the final request for its
+// prompt hash.
+async fn write_report(
+    provider: &dyn ReflectionProvider,
+    mut redactor: Redactor,
+    header: &str,
+    days: &[DayDigest],
+    language: &str,
+) -> Result<(PeriodReport, String, llm::ChatRequest), String> {
+    // The per-day averages are short and always sent; the detail is sent as-is
+    // when it fits and otherwise condensed chunk by chunk.
+    let averages = days
+        .iter()
+        .map(|d| format!("- {}: {}", d.date, format_average(d.average())))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut chunks = chunk_texts(
+        days.iter()
+            .filter(|d| !d.behaviors.is_empty() || d.summary.is_some())
+            .map(|d| redactor.redact(&d.text()))
+            .collect::<Vec<_>>(),
+        CHUNK_CHARS,
+    );
+    // The notes keep the placeholders, so they go into the final prompt as
+    // they are.
+    let detail = if chunks.len() == 1 {
+        chunks.remove(0)
+    } else {
+        let system = format!("{}\n\n{}", CHUNK_INSTRUCTION, language::instruction(language));
+        let mut notes = Vec::new();
+        for (i, chunk) in chunks.iter().enumerate() {
+            let request = llm::ChatRequest::new(system.as_str(), chunk.as_str()).temperature(0.3);
+            let note = provider.complete(&request).await?.text;
+            notes.push(format!("Notes, part {} of {}:\n{}", i + 1, chunks.len(), note.trim()));
+        }
+        notes.join("\n\n")
+    };
+
+    let user_content = format!(
+        "{}\nDaily averages:\n{}\n\nDetail:\n{}",
+        redactor.redact(header),
+        averages,
+        detail
+    );
+    let system = format!(
+        "{}\n\n{} {}",
+        SYSTEM_INSTRUCTION,
+        language::instruction(language),
+        language::KEEP_KEYS
+    );
+    let request =
+        llm::ChatRequest::new(system, user_content).json_schema("period_reflection", report_schema());
+    let (report, content) = llm::complete_json::<PeriodReport, _>(
+        provider,
+        &request,
+        |r: &PeriodReport| r.validate(language),
+        &mut |_, _| {},
+    )
+    .await?;
+    if redactor.replacements().is_empty() {
+        return Ok((report, content, request));
+    }
+    let report = report.restore(&redactor);
+    let content = serde_json::to_string(&report).map_err(|e| e.to_string())?;
+    Ok((report, content, request))
+}
+
+#[tauri::command]
+pub async fn generate_period_reflection(
+    app: AppHandle,
+    identity_id: i64,
+    period: Period,
+    start: String,
+) -> Result<PeriodReflection, String> {
+    let (first, last) = period.bounds(dates::parse_date(&start)?);
+    let (header, days, average, language, redactor, config, provider) = {
+        let conn = db::get_conn(&app)?;
+        let identity = get_identity_by_id(&conn, identity_id)?;
+        let language = language::for_identity(&conn, &identity)?;
+        let traits = traits_for_identity(&conn, identity_id)?
+            .into_iter()
+            .map(|t| t.name)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let days = load_days(&conn, identity_id, first, last)?;
+        let average = average_between(&conn, identity_id, first, last)?;
+        let previous_first = period.previous(first);
+        let previous = average_between(&conn, identity_id, previous_first, first - Duration::days(1))?;
+        let header = format!(
+            "Identity: {}\nDescription: {}\nTraits: {}\n\nPeriod: {} from {} to {}\nAverage alignment: {}\nPrevious {} average: {}\n",
+            identity.name,
+            identity.description,
+            traits,
+            period.as_str(),
+            dates::format_date(first),
+            dates::format_date(last),
+            format_average(average),
+            period.as_str(),
+            format_average(previous),
+        );
+        let config = llm::load_config(&conn, Some(identity_id))?;
+        let provider = llm::metered_provider(&app, &config)?;
+        (header, days, average, language, redactor(&conn)?, config, provider)
+    };
+
+    if days.iter().all(|d| d.behaviors.is_empty()) {
+        return Err(format!("No behaviors logged for this {}", period.as_str()));
+    }
+
+    let (report, content, request) =
+        write_report(provider.as_ref(), redactor, &header, &days, &language).await?;
+
+    let conn = db::get_conn(&app)?;
+    conn.execute(
+        "INSERT INTO period_reflection (identity_id, period, start_date, end_date, content, title, wins, \
+         misalignments, trend, trend_summary, focus, average_alignment, model, prompt_hash) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        (
+            identity_id,
+            period.as_str(),
+            dates::format_date(first),
+            dates::format_date(last),
+            &content,
+            &report.title,
+            serde_json::to_string(&report.wins).map_err(|e| e.to_string())?,
+            serde_json::to_string(&report.misalignments).map_err(|e| e.to_string())?,
+            &report.trend,
+            &report.trend_summary,
+            &report.focus,
+            average,
+            &config.model,
+            llm::prompt_hash(&request),
+        ),
+    )
+    .map_err(|e| e.to_string())?;
+    conn.query_row(
+        &format!("SELECT {} FROM period_reflection WHERE id = ?1", PERIOD_COLUMNS),
+        [conn.last_insert_rowid()],
+        period_from_row,
+    )
+    .map_err(|e| e.to_string())
+}
+
+// The most recent report for the period containing `start`.
+#[tauri::command]
+pub fn get_period_reflection(
+    app: AppHandle,
+    identity_id: i64,
+    period: Period,
+    start: String,
+) -> Result<Option<PeriodReflection>, String> {
+    let (first, _) = period.bounds(dates::parse_date(&start)?);
+    let conn = db::get_conn(&app)?;
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM period_reflection WHERE identity_id = ?1 AND period = ?2 AND start_date = ?3 \
+             ORDER BY id DESC LIMIT 1",
+            PERIOD_COLUMNS
+        ),
+        (identity_id, period.as_str(), dates::format_date(first)),
+        period_from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_period_reflections(
+    app: AppHandle,
+    identity_id: i64,
+    period: Option<Period>,
+    limit: Option<i32>,
+) -> Result<Vec<PeriodReflection>, String> {
+    let conn = db::get_conn(&app)?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM period_reflection WHERE identity_id = ?1 AND (?2 IS NULL OR period = ?2) \
+             ORDER BY start_date DESC, id DESC LIMIT ?3",
+            PERIOD_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(
+            (identity_id, period.map(Period::as_str), limit.unwrap_or(20) as i64),
+            period_from_row,
+        )
+        .map_err(|e| e.to_string())?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        dates::parse_date(s).unwrap()
+    }
+
+    fn bounds(period: Period, start: &str) -> (String, String) {
+        let (first, last) = period.bounds(date(start));
+        (dates::format_date(first), dates::format_date(last))
+    }
+
+    #[test]
+    fn weeks_run_monday_to_sunday() {
+        let week = ("2024-05-13".to_string(), "2024-05-19".to_string());
+        assert_eq!(bounds(Period::Week, "2024-05-13"), week);
+        assert_eq!(bounds(Period::Week, "2024-05-15"), week);
+        assert_eq!(bounds(Period::Week, "2024-05-19"), week);
+        // Across a month and a year boundary.
+        assert_eq!(
+            bounds(Period::Week, "2025-01-01"),
+            ("2024-12-30".to_string(), "2025-01-05".to_string())
+        );
+        assert_eq!(Period::Week.previous(date("2024-05-13")), date("2024-05-06"));
+    }
+
+    #[test]
+    fn months_end_on_their_last_day() {
+        assert_eq!(
+            bounds(Period::Month, "2024-01-31"),
+            ("2024-01-01".to_string(), "2024-01-31".to_string())
+        );
+        assert_eq!(
+            bounds(Period::Month, "2024-02-10"),
+            ("2024-02-01".to_string(), "2024-02-29".to_string())
+        );
+        assert_eq!(
+            bounds(Period::Month, "2023-02-28"),
+            ("2023-02-01".to_string(), "2023-02-28".to_string())
+        );
+        assert_eq!(
+            bounds(Period::Month, "2024-04-30"),
+            ("2024-04-01".to_string(), "2024-04-30".to_string())
+        );
+        assert_eq!(
+            bounds(Period::Month, "2024-12-01"),
+            ("2024-12-01".to_string(), "2024-12-31".to_string())
+        );
+        assert_eq!(Period::Month.previous(date("2024-03-01")), date("2024-02-01"));
+        assert_eq!(Period::Month.previous(date("2024-01-01")), date("2023-12-01"));
+    }
+
+    fn day(date: &str, description: &str) -> DayDigest {
+        DayDigest {
+            date: date.to_string(),
+            behaviors: vec![BehaviorLog {
+                id: 0,
+                date: date.to_string(),
+                description: description.to_string(),
+                identity_id: 1,
+                alignment_score: 7,
+                suggested_score: None,
+                value: None,
+                unit: None,
+                trait_ids: Vec::new(),
+                created_at: String::new(),
+            }],
+            summary: Some("A good day with Alice.".to_string()),
+            correction: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn every_prompt_is_redacted_and_the_report_restored() {
+        use crate::llm::redact::Rule;
+        use crate::llm::tests::scripted;
+
+        let provider = scripted(vec![
+            "Notes on [PERSON_1], part one.",
+            "Notes on [PERSON_1], part two.",
+            r#"{"title": "A month with [PERSON_1]", "wins": ["Wrote with [PERSON_1]"],
+                "misalignments": ["Skipped drafts"], "trend": "stable",
+                "trendSummary": "About the same as last month.", "focus": "Ask [PERSON_1] for notes."}"#,
+        ]);
+        let rules = Rule::terms("PERSON", &["Alice".to_string()]).unwrap();
+        let redactor = Redactor::new(rules.into_iter().collect());
+        // Two long days, so the detail is condensed in two chunk calls.
+        let long = format!("Co-wrote with Alice. {}", "Drafted scenes. ".repeat(CHUNK_CHARS / 20));
+        let days = [day("2024-05-01", &long), day("2024-05-02", &long)];
+        let header = "Identity: a writer\nDescription: Writes with Alice every week.\n";
+
+        let (report, content, request) =
+            write_report(&provider, redactor, header, &days, "en").await.unwrap();
+
+        let seen = provider.seen.lock().unwrap();
+        assert_eq!(seen.len(), 3);
+        // The chunk summaries are asked for in the output language too.
+        assert!(seen.iter().all(|r| r.system.contains(&language::instruction("en"))));
+        drop(seen);
+        assert!(!provider.sent().contains("Alice"));
+        assert!(request.messages[0].content.contains("Writes with [PERSON_1] every week"));
+        assert_eq!(report.title, "A month with Alice");
+        assert_eq!(report.focus, "Ask Alice for notes.");
+        assert!(content.contains("Wrote with Alice") && !content.contains("[PERSON_1]"));
+    }
+
+    #[test]
+    fn chunks_keep_days_whole_and_in_order() {
+        let day = |c: char, n: usize| c.to_string().repeat(n);
+
+        // Everything fits: one chunk, even when empty.
+        assert_eq!(chunk_texts(Vec::new(), 10), vec![String::new()]);
+        assert_eq!(chunk_texts(vec![day('a', 4), day('b', 6)], 10), vec!["aaaabbbbbb"]);
+
+        // The day that would overflow starts the next chunk.
+        assert_eq!(
+            chunk_texts(vec![day('a', 4), day('b', 4), day('c', 4), day('d', 4)], 10),
+            vec!["aaaabbbb", "ccccdddd"]
+        );
+
+        // An oversized day is never split and never leaves an empty chunk.
+        assert_eq!(
+            chunk_texts(vec![day('a', 12), day('b', 3), day('c', 12)], 10),
+            vec![day('a', 12), day('b', 3), day('c', 12)]
+        );
+    }
+}
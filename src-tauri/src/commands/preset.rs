@@ -0,0 +1,139 @@
+use rusqlite::{Connection, Row};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::commands::behavior::{get_behavior_by_id, id_list, insert_behavior, BehaviorLog, LogBehaviorInput};
+use crate::commands::identity::get_identity_by_id;
+use crate::db;
+
+// A behavior logged often enough to keep one tap away.
+#[derive(Debug, Serialize)]
+pub struct QuickLogPreset {
+    pub id: i64,
+    pub identity_id: i64,
+    pub description: String,
+    pub alignment_score: i32,
+    pub trait_ids: Vec<i64>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QuickLogPresetInput {
+    pub identity_id: i64,
+    pub description: String,
+    pub alignment_score: i32,
+    #[serde(default)]
+    pub trait_ids: Vec<i64>,
+}
+
+const PRESET_COLUMNS: &str = "id, identity_id, description, alignment_score, \
+    (SELECT GROUP_CONCAT(trait_id) FROM quick_log_preset_trait WHERE preset_id = quick_log_preset.id), created_at";
+
+fn preset_from_row(row: &Row) -> rusqlite::Result<QuickLogPreset> {
+    Ok(QuickLogPreset {
+        id: row.get(0)?,
+        identity_id: row.get(1)?,
+        description: row.get(2)?,
+        alignment_score: row.get(3)?,
+        trait_ids: id_list(row.get(4)?),
+        created_at: row.get(5)?,
+    })
+}
+
+fn get_preset_by_id(conn: &Connection, id: i64) -> Result<QuickLogPreset, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM quick_log_preset WHERE id = ?1", PRESET_COLUMNS),
+        [id],
+        preset_from_row,
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => format!("Preset {} not found", id),
+        e => e.to_string(),
+    })
+}
+
+#[tauri::command]
+pub fn create_quick_log_preset(app: AppHandle, input: QuickLogPresetInput) -> Result<QuickLogPreset, String> {
+    let description = input.description.trim();
+    if description.is_empty() {
+        return Err("description is required".to_string());
+    }
+    if !(1..=10).contains(&input.alignment_score) {
+        return Err("alignment_score must be between 1 and 10".to_string());
+    }
+    let mut conn = db::get_conn(&app)?;
+    get_identity_by_id(&conn, input.identity_id)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let id = insert_preset(&tx, description, &input)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    get_preset_by_id(&conn, id)
+}
+
+// Trait ids that are not the identity's own are dropped.
+fn insert_preset(conn: &Connection, description: &str, input: &QuickLogPresetInput) -> Result<i64, String> {
+    conn.execute(
+        "INSERT INTO quick_log_preset (identity_id, description, alignment_score) VALUES (?1, ?2, ?3)",
+        (input.identity_id, description, input.alignment_score),
+    )
+    .map_err(|e| e.to_string())?;
+    let id = conn.last_insert_rowid();
+    for trait_id in &input.trait_ids {
+        conn.execute(
+            "INSERT OR IGNORE INTO quick_log_preset_trait (preset_id, trait_id) \
+             SELECT ?1, id FROM trait WHERE id = ?2 AND identity_id = ?3",
+            (id, trait_id, input.identity_id),
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn list_quick_log_presets(app: AppHandle, identity_id: i64) -> Result<Vec<QuickLogPreset>, String> {
+    let conn = db::get_conn(&app)?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM quick_log_preset WHERE identity_id = ?1 ORDER BY description",
+            PRESET_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([identity_id], preset_from_row)
+        .map_err(|e| e.to_string())?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(out)
+}
+
+#[tauri::command]
+pub fn delete_quick_log_preset(app: AppHandle, id: i64) -> Result<(), String> {
+    let conn = db::get_conn(&app)?;
+    conn.execute("DELETE FROM quick_log_preset WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Logs the preset's behavior on `date`.
+#[tauri::command]
+pub fn log_quick_log_preset(app: AppHandle, id: i64, date: String) -> Result<BehaviorLog, String> {
+    let mut conn = db::get_conn(&app)?;
+    let preset = get_preset_by_id(&conn, id)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let behavior_id = insert_behavior(
+        &tx,
+        &LogBehaviorInput {
+            date,
+            description: preset.description,
+            identity_id: preset.identity_id,
+            alignment_score: preset.alignment_score,
+            suggested_score: None,
+            value: None,
+            unit: None,
+            trait_ids: preset.trait_ids,
+        },
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+    get_behavior_by_id(&conn, behavior_id)
+}
@@ -0,0 +1,432 @@
+use rusqlite::{Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::commands::identity::{get_identity_by_id, Identity};
+use crate::commands::reflection;
+use crate::{dates, db};
+
+// Placeholders a template may use, written as {{name}}.
+pub(crate) const PLACEHOLDERS: [&str; 9] = [
+    "date",
+    "identity_name",
+    "identity_description",
+    "traits",
+    "behaviors",
+    "week_average",
+    "streak",
+    "previous_reflections",
+    "language",
+];
+
+pub(crate) const DEFAULT_SYSTEM_TEMPLATE: &str = "You are a psychologically intelligent identity performance coach.
+Analyze behavioral alignment with the stated identity.
+Be specific, insightful, and constructive.
+Avoid generic motivation.
+Focus on identity reinforcement and misalignment patterns.";
+
+pub(crate) const DEFAULT_USER_TEMPLATE: &str = "Identity: {{identity_name}}
+Description: {{identity_description}}
+Traits: {{traits}}
+
+Today's behaviors and alignment:
+{{behaviors}}
+
+Recent context:
+- 7-day average alignment: {{week_average}}
+- Logging streak: {{streak}} day(s)
+
+Earlier reflections (most recent first):
+{{previous_reflections}}
+";
+
+// Coaching personas created on first run. They only change the system
+// template; users can edit or delete them like any other template.
+const PERSONAS: [(&str, &str); 4] = [
+    (
+        "Blunt",
+        "You are a blunt, no-nonsense identity coach.
+Say plainly where behavior contradicted the stated identity and what it cost.
+No cushioning, no praise that was not earned, no generic motivation.
+Be specific and brief.",
+    ),
+    (
+        "Stoic",
+        "You are a coach in the Stoic tradition of Epictetus, Seneca and Marcus Aurelius.
+Separate what was within the person's control from what was not.
+Judge the day by choices and character, not outcomes.
+Be calm, exact and sparing with words; avoid generic motivation.",
+    ),
+    (
+        "Sports coach",
+        "You are a high-energy sports coach reviewing game tape of the day.
+Call out the plays that fit the identity and the ones that did not, like a coach at halftime.
+Be direct and specific, and frame the correction as the next rep to run.",
+    ),
+    (
+        "Gentle",
+        "You are a warm, gentle identity coach.
+Notice effort and small wins first, then name misalignments kindly and without judgment.
+Stay specific and honest; encouragement must be grounded in what actually happened.",
+    ),
+];
+
+#[derive(Debug, Serialize)]
+pub struct PromptTemplate {
+    pub id: i64,
+    pub name: String,
+    pub system_template: String,
+    pub user_template: String,
+    // Bumped on every edit and recorded on each reflection the template
+    // produced.
+    pub version: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+// A template as it was at one version.
+#[derive(Debug, Serialize)]
+pub struct PromptTemplateVersion {
+    pub template_id: i64,
+    pub version: i64,
+    pub name: String,
+    pub system_template: String,
+    pub user_template: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PromptTemplateInput {
+    pub name: String,
+    pub system_template: String,
+    pub user_template: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RenderedPrompt {
+    pub system: String,
+    pub user: String,
+}
+
+const TEMPLATE_COLUMNS: &str = "id, name, system_template, user_template, version, created_at, updated_at";
+
+fn template_from_row(row: &Row) -> rusqlite::Result<PromptTemplate> {
+    Ok(PromptTemplate {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        system_template: row.get(2)?,
+        user_template: row.get(3)?,
+        version: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+const VERSION_COLUMNS: &str = "template_id, version, name, system_template, user_template, created_at";
+
+fn version_from_row(row: &Row) -> rusqlite::Result<PromptTemplateVersion> {
+    Ok(PromptTemplateVersion {
+        template_id: row.get(0)?,
+        version: row.get(1)?,
+        name: row.get(2)?,
+        system_template: row.get(3)?,
+        user_template: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+// Copies the template's current text into its history.
+fn record_version(conn: &Connection, id: i64) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR IGNORE INTO prompt_template_version (template_id, version, name, system_template, \
+         user_template) SELECT id, version, name, system_template, user_template FROM prompt_template \
+         WHERE id = ?1",
+        [id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub(crate) fn seed_personas(conn: &Connection) -> Result<(), String> {
+    // Seed once, so personas the user deleted stay deleted.
+    let seeded: i64 = conn
+        .query_row("SELECT COUNT(*) FROM prompt_template", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if seeded > 0 {
+        return Ok(());
+    }
+    for (name, system) in PERSONAS {
+        conn.execute(
+            "INSERT INTO prompt_template (name, system_template, user_template) VALUES (?1, ?2, ?3)",
+            (name, system, DEFAULT_USER_TEMPLATE),
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+pub(crate) fn get_template_by_id(conn: &Connection, id: i64) -> Result<PromptTemplate, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM prompt_template WHERE id = ?1", TEMPLATE_COLUMNS),
+        [id],
+        template_from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Prompt template {} not found", id))
+}
+
+// The template chosen for an identity, or None for the built-in default.
+pub(crate) fn template_for_identity(conn: &Connection, identity: &Identity) -> Result<Option<PromptTemplate>, String> {
+    match identity.prompt_template_id {
+        Some(id) => get_template_by_id(conn, id).map(Some),
+        None => Ok(None),
+    }
+}
+
+// Replaces each {{name}} with its value. Unknown placeholders are an error so
+// typos surface when the template is saved rather than in the prompt.
+pub(crate) fn render(template: &str, values: &[(&str, String)]) -> Result<String, String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| "Unclosed {{ in template".to_string())?;
+        let name = after[..end].trim();
+        let value = values
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, v)| v.as_str())
+            .ok_or_else(|| {
+                format!(
+                    "Unknown placeholder {{{{{}}}}}; available: {}",
+                    name,
+                    PLACEHOLDERS.join(", ")
+                )
+            })?;
+        out.push_str(value);
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn validate(input: &PromptTemplateInput) -> Result<(), String> {
+    if input.name.trim().is_empty() {
+        return Err("Template name is empty".to_string());
+    }
+    if input.system_template.trim().is_empty() {
+        return Err("System template is empty".to_string());
+    }
+    if input.user_template.trim().is_empty() {
+        return Err("User template is empty".to_string());
+    }
+    let values: Vec<(&str, String)> = PLACEHOLDERS.iter().map(|p| (*p, String::new())).collect();
+    render(&input.system_template, &values)?;
+    render(&input.user_template, &values)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_prompt_templates(app: AppHandle) -> Result<Vec<PromptTemplate>, String> {
+    let conn = db::get_conn(&app)?;
+    let mut stmt = conn
+        .prepare(&format!("SELECT {} FROM prompt_template ORDER BY name", TEMPLATE_COLUMNS))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], template_from_row).map_err(|e| e.to_string())?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(out)
+}
+
+// The built-in default, for starting a new template from it.
+#[tauri::command]
+pub fn get_default_prompt_template() -> PromptTemplateInput {
+    PromptTemplateInput {
+        name: "Default".to_string(),
+        system_template: DEFAULT_SYSTEM_TEMPLATE.to_string(),
+        user_template: DEFAULT_USER_TEMPLATE.to_string(),
+    }
+}
+
+#[tauri::command]
+pub fn create_prompt_template(app: AppHandle, input: PromptTemplateInput) -> Result<PromptTemplate, String> {
+    validate(&input)?;
+    let mut conn = db::get_conn(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let template = insert_template(&tx, &input)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(template)
+}
+
+fn insert_template(conn: &Connection, input: &PromptTemplateInput) -> Result<PromptTemplate, String> {
+    conn.execute(
+        "INSERT INTO prompt_template (name, system_template, user_template) VALUES (?1, ?2, ?3)",
+        (input.name.trim(), &input.system_template, &input.user_template),
+    )
+    .map_err(|e| e.to_string())?;
+    let id = conn.last_insert_rowid();
+    record_version(conn, id)?;
+    get_template_by_id(conn, id)
+}
+
+#[tauri::command]
+pub fn update_prompt_template(
+    app: AppHandle,
+    id: i64,
+    input: PromptTemplateInput,
+) -> Result<PromptTemplate, String> {
+    validate(&input)?;
+    let mut conn = db::get_conn(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let template = update_template(&tx, id, &input)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(template)
+}
+
+// Saves the new text as the next version; earlier versions stay in
+// prompt_template_version.
+fn update_template(conn: &Connection, id: i64, input: &PromptTemplateInput) -> Result<PromptTemplate, String> {
+    let updated = conn
+        .execute(
+            "UPDATE prompt_template SET name = ?1, system_template = ?2, user_template = ?3, \
+             version = version + 1, updated_at = datetime('now') WHERE id = ?4",
+            (input.name.trim(), &input.system_template, &input.user_template, id),
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Prompt template {} not found", id));
+    }
+    record_version(conn, id)?;
+    get_template_by_id(conn, id)
+}
+
+fn template_version(conn: &Connection, template_id: i64, version: i64) -> Result<PromptTemplateVersion, String> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM prompt_template_version WHERE template_id = ?1 AND version = ?2",
+            VERSION_COLUMNS
+        ),
+        (template_id, version),
+        version_from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Version {} of prompt template {} not found", version, template_id))
+}
+
+// The template text a reflection was generated from, by the
+// prompt_template_id and prompt_template_version it records. Works after the
+// template was edited or deleted.
+#[tauri::command]
+pub fn get_prompt_template_version(
+    app: AppHandle,
+    template_id: i64,
+    version: i64,
+) -> Result<PromptTemplateVersion, String> {
+    let conn = db::get_conn(&app)?;
+    template_version(&conn, template_id, version)
+}
+
+#[tauri::command]
+pub fn delete_prompt_template(app: AppHandle, id: i64) -> Result<(), String> {
+    let conn = db::get_conn(&app)?;
+    conn.execute("UPDATE identity SET prompt_template_id = NULL WHERE prompt_template_id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM prompt_template WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Renders a template, saved or not, against an identity's real data for a
+// day, exactly as it would be sent.
+#[tauri::command]
+pub fn preview_prompt_template(
+    app: AppHandle,
+    identity_id: i64,
+    date: String,
+    input: PromptTemplateInput,
+) -> Result<RenderedPrompt, String> {
+    validate(&input)?;
+    let day = dates::parse_date(&date)?;
+    let conn = db::get_conn(&app)?;
+    let (system, user) =
+        reflection::render_prompt(&conn, identity_id, day, &input.system_template, &input.user_template)?;
+    Ok(RenderedPrompt { system, user })
+}
+
+// Chooses the template an identity's reflections use; None restores the
+// built-in default.
+#[tauri::command]
+pub fn set_identity_prompt_template(
+    app: AppHandle,
+    identity_id: i64,
+    template_id: Option<i64>,
+) -> Result<Identity, String> {
+    let conn = db::get_conn(&app)?;
+    if let Some(id) = template_id {
+        get_template_by_id(&conn, id)?;
+    }
+    conn.execute(
+        "UPDATE identity SET prompt_template_id = ?1 WHERE id = ?2",
+        (template_id, identity_id),
+    )
+    .map_err(|e| e.to_string())?;
+    get_identity_by_id(&conn, identity_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values() -> Vec<(&'static str, String)> {
+        vec![("identity_name", "a writer".to_string()), ("streak", "3".to_string())]
+    }
+
+    #[test]
+    fn render_fills_placeholders() {
+        assert_eq!(
+            render("{{identity_name}} on a {{ streak }}-day streak, {{streak}}", &values()).unwrap(),
+            "a writer on a 3-day streak, 3"
+        );
+        assert_eq!(render("no placeholders", &values()).unwrap(), "no placeholders");
+        // A single brace is plain text.
+        assert_eq!(render("{streak}", &values()).unwrap(), "{streak}");
+    }
+
+    #[test]
+    fn render_rejects_unknown_and_unclosed_placeholders() {
+        let err = render("{{identity}}", &values()).unwrap_err();
+        assert!(err.contains("Unknown placeholder {{identity}}"), "{}", err);
+        assert!(err.contains("previous_reflections"), "{}", err);
+        assert!(render("{{streak", &values()).unwrap_err().contains("Unclosed"));
+    }
+
+    #[test]
+    fn edits_keep_earlier_versions() {
+        let conn = db::test_conn();
+        let input = |system: &str| PromptTemplateInput {
+            name: "Mine".to_string(),
+            system_template: system.to_string(),
+            user_template: DEFAULT_USER_TEMPLATE.to_string(),
+        };
+        let created = insert_template(&conn, &input("Be brief.")).unwrap();
+        let updated = update_template(&conn, created.id, &input("Be thorough.")).unwrap();
+        assert_eq!(updated.version, 2);
+
+        assert_eq!(template_version(&conn, created.id, 1).unwrap().system_template, "Be brief.");
+        assert_eq!(template_version(&conn, created.id, 2).unwrap().system_template, "Be thorough.");
+        assert!(template_version(&conn, created.id, 3).is_err());
+
+        // Seeded personas have their first version recorded too.
+        let blunt: i64 = conn
+            .query_row("SELECT id FROM prompt_template WHERE name = 'Blunt'", [], |row| row.get(0))
+            .unwrap();
+        assert!(template_version(&conn, blunt, 1).is_ok());
+    }
+}
@@ -0,0 +1,125 @@
+use rusqlite::{Connection, Row};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::commands::settings::load_settings;
+use crate::db;
+use crate::llm::redact::{builtin_rules, Redactor, Rule};
+
+const KINDS: [&str; 2] = ["term", "pattern"];
+
+#[derive(Debug, Serialize)]
+pub struct RedactionRule {
+    pub id: i64,
+    // "term" for a word or name from a term list, "pattern" for a regex.
+    pub kind: String,
+    // Groups terms into a list and names the placeholder, e.g. PERSON for
+    // [PERSON_1].
+    pub label: String,
+    pub value: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RedactionRuleInput {
+    pub kind: String,
+    pub label: String,
+    pub value: String,
+}
+
+const RULE_COLUMNS: &str = "id, kind, label, value, created_at";
+
+fn rule_from_row(row: &Row) -> rusqlite::Result<RedactionRule> {
+    Ok(RedactionRule {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        label: row.get(2)?,
+        value: row.get(3)?,
+        created_at: row.get(4)?,
+    })
+}
+
+fn rules(conn: &Connection) -> Result<Vec<RedactionRule>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM redaction_rule ORDER BY kind DESC, label, value",
+            RULE_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], rule_from_row).map_err(|e| e.to_string())?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(out)
+}
+
+// The redactor for prompts: term lists first, then the user's patterns,
+// then the built-in detectors unless they are switched off.
+pub(crate) fn redactor(conn: &Connection) -> Result<Redactor, String> {
+    let stored = rules(conn)?;
+    let mut out = Vec::new();
+    let mut labels: Vec<&str> = stored.iter().filter(|r| r.kind == "term").map(|r| r.label.as_str()).collect();
+    labels.dedup();
+    for label in labels {
+        let terms: Vec<String> = stored
+            .iter()
+            .filter(|r| r.kind == "term" && r.label == label)
+            .map(|r| r.value.clone())
+            .collect();
+        out.extend(Rule::terms(label, &terms)?);
+    }
+    for r in stored.iter().filter(|r| r.kind == "pattern") {
+        out.push(Rule::pattern(&r.label, &r.value)?);
+    }
+    if load_settings(conn)?.redact_builtin {
+        out.extend(builtin_rules());
+    }
+    Ok(Redactor::new(out))
+}
+
+#[tauri::command]
+pub fn list_redaction_rules(app: AppHandle) -> Result<Vec<RedactionRule>, String> {
+    let conn = db::get_conn(&app)?;
+    rules(&conn)
+}
+
+#[tauri::command]
+pub fn add_redaction_rule(app: AppHandle, input: RedactionRuleInput) -> Result<RedactionRule, String> {
+    if !KINDS.contains(&input.kind.as_str()) {
+        return Err(format!("kind must be one of {}, got '{}'", KINDS.join(", "), input.kind));
+    }
+    let label = input.label.trim();
+    let value = input.value.trim();
+    if label.is_empty() || value.is_empty() {
+        return Err("label and value are required".to_string());
+    }
+    // Check the pattern now rather than failing every later reflection.
+    if input.kind == "pattern" {
+        Rule::pattern(label, value)?;
+    }
+    let conn = db::get_conn(&app)?;
+    // Adding a rule that already exists returns the existing one.
+    conn.execute(
+        "INSERT INTO redaction_rule (kind, label, value) VALUES (?1, ?2, ?3) ON CONFLICT DO NOTHING",
+        (&input.kind, label, value),
+    )
+    .map_err(|e| e.to_string())?;
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM redaction_rule WHERE kind = ?1 AND label = ?2 AND value = ?3",
+            RULE_COLUMNS
+        ),
+        (&input.kind, label, value),
+        rule_from_row,
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn delete_redaction_rule(app: AppHandle, id: i64) -> Result<(), String> {
+    let conn = db::get_conn(&app)?;
+    conn.execute("DELETE FROM redaction_rule WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
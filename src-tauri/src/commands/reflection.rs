@@ -1,7 +1,114 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::{Duration, NaiveDate};
+use rusqlite::{Connection, OptionalExtension, Row};
 use serde::{Deserialize, Serialize};
-use tauri::AppHandle;
+use similar::{ChangeTag, TextDiff};
+use tauri::{AppHandle, Emitter, State};
+use tokio::sync::oneshot;
+
+use crate::commands::behavior::{behaviors_for_date, BehaviorLog};
+use crate::commands::feedback::negative_feedback_lines;
+use crate::commands::identity::{get_identity_by_id, Identity};
+use crate::commands::local_reflection::local_reflection;
+use crate::commands::prompt_template::{
+    render, template_for_identity, PromptTemplate, DEFAULT_SYSTEM_TEMPLATE, DEFAULT_USER_TEMPLATE,
+};
+use crate::commands::redaction::redactor;
+use crate::commands::reflection_queue;
+use crate::commands::settings::load_settings;
+use crate::commands::trait_::{traits_for_identity, Trait};
+use crate::llm::redact::{Redactor, Replacement};
+use crate::{dates, db, language, llm};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reflection {
+    pub title: String,
+    pub alignment_summary: String,
+    pub observations: Vec<String>,
+    pub identity_correction: String,
+    pub closing_statement: String,
+    // Missing on reflections generated before follow-ups existed.
+    #[serde(default)]
+    pub follow_up: Option<FollowUp>,
+}
+
+// Whether the most recent earlier correction was acted on, judged from the
+// day's behaviors.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FollowUp {
+    // "acted", "partial", "not_acted", or "no_previous" when there was no
+    // earlier correction to follow up on.
+    pub status: String,
+    pub note: String,
+}
+
+const FOLLOW_UP_STATUSES: [&str; 4] = ["acted", "partial", "not_acted", "no_previous"];
+
+// How many earlier reflections are quoted back to the model.
+const MEMORY_REFLECTIONS: i64 = 3;
+
+impl Reflection {
+    fn restore(self, redactor: &Redactor) -> Self {
+        Reflection {
+            title: redactor.restore(&self.title),
+            alignment_summary: redactor.restore(&self.alignment_summary),
+            observations: self.observations.iter().map(|o| redactor.restore(o)).collect(),
+            identity_correction: redactor.restore(&self.identity_correction),
+            closing_statement: redactor.restore(&self.closing_statement),
+            follow_up: self.follow_up.map(|f| FollowUp {
+                note: redactor.restore(&f.note),
+                ..f
+            }),
+        }
+    }
 
-use crate::db;
+    // Checks the structure and that the text is in `language`.
+    fn validate(&self, language: &str) -> Result<(), String> {
+        let fields = [
+            ("title", &self.title),
+            ("alignmentSummary", &self.alignment_summary),
+            ("identityCorrection", &self.identity_correction),
+            ("closingStatement", &self.closing_statement),
+        ];
+        for (name, value) in fields {
+            if value.trim().is_empty() {
+                return Err(format!("{} is empty", name));
+            }
+        }
+        if self.observations.len() != 3 {
+            return Err(format!(
+                "observations must have exactly 3 entries, got {}",
+                self.observations.len()
+            ));
+        }
+        if self.observations.iter().any(|o| o.trim().is_empty()) {
+            return Err("observations contains an empty entry".to_string());
+        }
+        let follow_up = self.follow_up.as_ref().ok_or("followUp is missing")?;
+        if !FOLLOW_UP_STATUSES.contains(&follow_up.status.as_str()) {
+            return Err(format!(
+                "followUp.status must be one of {}, got '{}'",
+                FOLLOW_UP_STATUSES.join(", "),
+                follow_up.status
+            ));
+        }
+        if follow_up.note.trim().is_empty() {
+            return Err("followUp.note is empty".to_string());
+        }
+        let mut texts = vec![
+            self.title.as_str(),
+            self.alignment_summary.as_str(),
+            self.identity_correction.as_str(),
+            self.closing_statement.as_str(),
+            follow_up.note.as_str(),
+        ];
+        texts.extend(self.observations.iter().map(|o| o.as_str()));
+        language::validate(language, &texts)
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DailyReflection {
@@ -9,30 +116,90 @@ pub struct DailyReflection {
     pub date: String,
     pub content: String,
     pub identity_id: i64,
+    // None only for rows written before reflections were validated whose
+    // content never parsed.
+    pub reflection: Option<Reflection>,
+    pub version: i64,
+    pub model: Option<String>,
+    pub prompt_hash: Option<String>,
+    pub pinned: bool,
+    // The template and its version the prompt was rendered from; None for the
+    // built-in default.
+    pub prompt_template_id: Option<i64>,
+    pub prompt_template_version: Option<i64>,
+    // Built by the local rules because no provider was configured or the
+    // call failed; `fallback_reason` says why.
+    pub local: bool,
+    pub fallback_reason: Option<String>,
     pub created_at: String,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct ReflectionInput {
-    pub identity_id: i64,
-    pub date: String,
-    pub identity_name: String,
-    pub identity_description: String,
-    pub traits: Vec<String>,
-    pub behaviors: Vec<BehaviorForReflection>,
+const REFLECTION_COLUMNS: &str = "id, date, content, identity_id, title, alignment_summary, \
+     observations, identity_correction, closing_statement, version, model, prompt_hash, pinned, created_at, \
+     follow_up_status, follow_up_note, prompt_template_id, prompt_template_version, local, fallback_reason";
+
+// Picks the version shown for a day: the pinned one, otherwise the newest
+// provider version, otherwise the newest local fallback. A fallback stored
+// after a failed regeneration never hides an earlier AI version.
+pub(crate) const CURRENT_VERSION: &str = "id = (SELECT c.id FROM daily_reflection c \
+     WHERE c.identity_id = daily_reflection.identity_id AND c.date = daily_reflection.date \
+     ORDER BY c.pinned DESC, c.local ASC, c.id DESC LIMIT 1)";
+
+fn reflection_from_row(row: &Row) -> rusqlite::Result<DailyReflection> {
+    let content: String = row.get(2)?;
+    let title: Option<String> = row.get(4)?;
+    let observations: Option<String> = row.get(6)?;
+    let reflection = match (title, observations) {
+        (Some(title), Some(observations)) => Some(Reflection {
+            title,
+            alignment_summary: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+            observations: serde_json::from_str(&observations).unwrap_or_default(),
+            identity_correction: row.get::<_, Option<String>>(7)?.unwrap_or_default(),
+            closing_statement: row.get::<_, Option<String>>(8)?.unwrap_or_default(),
+            follow_up: match (row.get::<_, Option<String>>(14)?, row.get::<_, Option<String>>(15)?) {
+                (Some(status), Some(note)) => Some(FollowUp { status, note }),
+                _ => None,
+            },
+        }),
+        // Legacy rows only have the raw text.
+        _ => llm::parse_json::<Reflection>(&content).ok(),
+    };
+    Ok(DailyReflection {
+        id: row.get(0)?,
+        date: row.get(1)?,
+        content,
+        identity_id: row.get(3)?,
+        reflection,
+        version: row.get(9)?,
+        model: row.get(10)?,
+        prompt_hash: row.get(11)?,
+        pinned: row.get(12)?,
+        prompt_template_id: row.get(16)?,
+        prompt_template_version: row.get(17)?,
+        local: row.get(18)?,
+        fallback_reason: row.get(19)?,
+        created_at: row.get(13)?,
+    })
 }
 
-#[derive(Debug, Deserialize)]
-pub struct BehaviorForReflection {
-    pub description: String,
-    pub alignment_score: i32,
+pub(crate) fn get_reflection_by_id(conn: &Connection, id: i64) -> Result<DailyReflection, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM daily_reflection WHERE id = ?1", REFLECTION_COLUMNS),
+        [id],
+        reflection_from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Reflection {} not found", id))
 }
 
-const SYSTEM_INSTRUCTION: &str = r#"You are a psychologically intelligent identity performance coach.
-Analyze behavioral alignment with the stated identity.
-Be specific, insightful, and constructive.
-Avoid generic motivation.
-Focus on identity reinforcement and misalignment patterns.
+// Appended to every system template, so personas change the voice but never
+// the structure the output is validated against.
+const RESPONSE_FORMAT: &str = r#"Give exactly three observations.
+Build on earlier reflections instead of repeating them. In followUp, judge from
+today's behaviors whether the most recent earlier identity correction was acted
+on: "acted", "partial" or "not_acted", with a one-sentence note. Use
+"no_previous" when there is no earlier correction.
 
 Respond with valid JSON only, in this exact structure:
 {
@@ -40,104 +207,552 @@ Respond with valid JSON only, in this exact structure:
   "alignmentSummary": "string",
   "observations": ["string", "string", "string"],
   "identityCorrection": "string",
-  "closingStatement": "string"
+  "closingStatement": "string",
+  "followUp": { "status": "acted" | "partial" | "not_acted" | "no_previous", "note": "string" }
 }"#;
 
+fn reflection_schema() -> serde_json::Value {
+    let text = serde_json::json!({ "type": "string" });
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "title": text,
+            "alignmentSummary": text,
+            "observations": { "type": "array", "items": text, "minItems": 3, "maxItems": 3 },
+            "identityCorrection": text,
+            "closingStatement": text,
+            "followUp": {
+                "type": "object",
+                "properties": {
+                    "status": { "type": "string", "enum": FOLLOW_UP_STATUSES },
+                    "note": text
+                },
+                "required": ["status", "note"],
+                "additionalProperties": false
+            }
+        },
+        "required": ["title", "alignmentSummary", "observations", "identityCorrection", "closingStatement", "followUp"],
+        "additionalProperties": false
+    })
+}
+
+// In-flight generations by request id. Sending on the channel makes the
+// generation drop its future, which aborts the provider request.
+#[derive(Default)]
+pub struct ReflectionJobs {
+    running: Mutex<HashMap<String, oneshot::Sender<()>>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct DeltaEvent<'a> {
+    request_id: &'a str,
+    // 2 when the first reply was rejected and is being repaired; listeners
+    // should drop the text received so far.
+    attempt: u32,
+    text: &'a str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct DoneEvent<'a> {
+    request_id: &'a str,
+    reflection: &'a DailyReflection,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ErrorEvent<'a> {
+    request_id: &'a str,
+    error: &'a str,
+    cancelled: bool,
+}
+
+const CANCELLED: &str = "Reflection cancelled";
+
+// Streams progress as reflection://delta events and finishes with either
+// reflection://done or reflection://error, all carrying `request_id`.
 #[tauri::command]
 pub async fn generate_reflection(
     app: AppHandle,
-    api_key: String,
-    input: ReflectionInput,
+    jobs: State<'_, ReflectionJobs>,
+    request_id: String,
+    identity_id: i64,
+    date: String,
 ) -> Result<DailyReflection, String> {
-    if api_key.is_empty() {
-        return Err("OpenAI API key is required".to_string());
-    }
-
-    let behaviors_text = input
-        .behaviors
-        .iter()
-        .map(|b| format!("- {} (alignment: {}/10)", b.description, b.alignment_score))
-        .collect::<Vec<_>>()
-        .join("\n");
-
-    let traits_text = input.traits.join(", ");
-    let user_content = format!(
-        r#"Identity: {}
-Description: {}
-Traits: {}
-
-Today's behaviors and alignment:
-{}
-"#,
-        input.identity_name,
-        input.identity_description,
-        traits_text,
-        if behaviors_text.is_empty() {
-            "(No behaviors logged today)".to_string()
+    let (cancel_tx, cancel_rx) = oneshot::channel();
+    {
+        let mut jobs = jobs.running.lock().map_err(|e| e.to_string())?;
+        if jobs.contains_key(&request_id) {
+            return Err(format!("Request {} is already running", request_id));
+        }
+        jobs.insert(request_id.clone(), cancel_tx);
+    }
+
+    // The row is written in one statement after the stream has finished, so
+    // a cancelled generation never leaves anything behind.
+    let result = tokio::select! {
+        r = run_reflection(&app, &request_id, identity_id, &date) => r,
+        _ = cancel_rx => Err(CANCELLED.to_string()),
+    };
+    if let Ok(mut jobs) = jobs.running.lock() {
+        jobs.remove(&request_id);
+    }
+
+    match &result {
+        Ok(reflection) => {
+            let _ = app.emit(
+                "reflection://done",
+                DoneEvent {
+                    request_id: &request_id,
+                    reflection,
+                },
+            );
+        }
+        Err(error) => {
+            let _ = app.emit(
+                "reflection://error",
+                ErrorEvent {
+                    request_id: &request_id,
+                    error,
+                    cancelled: error == CANCELLED,
+                },
+            );
+        }
+    }
+    result
+}
+
+#[tauri::command]
+pub fn cancel_reflection(jobs: State<'_, ReflectionJobs>, request_id: String) -> Result<bool, String> {
+    let sender = jobs.running.lock().map_err(|e| e.to_string())?.remove(&request_id);
+    Ok(match sender {
+        Some(sender) => sender.send(()).is_ok(),
+        None => false,
+    })
+}
+
+// Everything the prompt says about the day, loaded from the database rather
+// than taken from the webview.
+pub(crate) struct ReflectionContext {
+    pub(crate) identity: Identity,
+    pub(crate) traits: Vec<Trait>,
+    pub(crate) behaviors: Vec<BehaviorLog>,
+    // Average alignment over the 7 days ending on the reflected day.
+    pub(crate) week_average: Option<f64>,
+    // Average alignment over the 7 days before the reflected day.
+    pub(crate) baseline: Option<f64>,
+    // Consecutive days with at least one logged behavior, ending on the
+    // reflected day.
+    pub(crate) streak: u32,
+    // The last few reflections before this day, newest first.
+    pub(crate) previous: Vec<DailyReflection>,
+    // Recent negative feedback on earlier reflections, when the
+    // feedback_in_prompt setting is on.
+    pub(crate) feedback: Vec<String>,
+    // Language code the reflection is written in.
+    pub(crate) language: String,
+}
+
+impl ReflectionContext {
+    pub(crate) fn load(conn: &Connection, identity_id: i64, date: NaiveDate) -> Result<Self, String> {
+        let identity = get_identity_by_id(conn, identity_id)?;
+        let traits = traits_for_identity(conn, identity_id)?;
+        let behaviors = behaviors_for_date(conn, identity_id, &dates::format_date(date))?;
+
+        let average = |from: NaiveDate, to: NaiveDate| -> Result<Option<f64>, String> {
+            conn.query_row(
+                "SELECT AVG(alignment_score) FROM behavior_log WHERE identity_id = ?1 AND date BETWEEN ?2 AND ?3",
+                (identity_id, dates::format_date(from), dates::format_date(to)),
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())
+        };
+        let week_average = average(date - Duration::days(6), date)?;
+        let baseline = average(date - Duration::days(7), date - Duration::days(1))?;
+
+        let mut stmt = conn
+            .prepare("SELECT DISTINCT date FROM behavior_log WHERE identity_id = ?1 AND date <= ?2 ORDER BY date DESC")
+            .map_err(|e| e.to_string())?;
+        let logged = stmt
+            .query_map((identity_id, dates::format_date(date)), |row| row.get::<_, String>(0))
+            .map_err(|e| e.to_string())?;
+        let mut streak = 0;
+        let mut expected = date;
+        for d in logged {
+            if d.map_err(|e| e.to_string())? != dates::format_date(expected) {
+                break;
+            }
+            streak += 1;
+            expected -= Duration::days(1);
+        }
+
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM daily_reflection WHERE identity_id = ?1 AND date < ?2 AND {} \
+                 ORDER BY date DESC LIMIT ?3",
+                REFLECTION_COLUMNS, CURRENT_VERSION
+            ))
+            .map_err(|e| e.to_string())?;
+        let previous = stmt
+            .query_map(
+                (identity_id, dates::format_date(date), MEMORY_REFLECTIONS),
+                reflection_from_row,
+            )
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+
+        let feedback = if load_settings(conn)?.feedback_in_prompt {
+            negative_feedback_lines(conn, identity_id, date)?
         } else {
-            behaviors_text
+            Vec::new()
+        };
+
+        let language = language::for_identity(conn, &identity)?;
+        Ok(ReflectionContext {
+            identity,
+            traits,
+            behaviors,
+            week_average,
+            baseline,
+            streak,
+            previous,
+            feedback,
+            language,
+        })
+    }
+
+    // Renders the system and user prompts from the given templates.
+    fn render_prompts(
+        &self,
+        date: NaiveDate,
+        system_template: &str,
+        user_template: &str,
+    ) -> Result<(String, String), String> {
+        let values = self.values(date);
+        let system = format!(
+            "{}\n\n{}\n\n{} {}",
+            render(system_template, &values)?.trim_end(),
+            RESPONSE_FORMAT,
+            language::instruction(&self.language),
+            language::KEEP_KEYS
+        );
+        let mut user = render(user_template, &values)?;
+        // Added after the template so it applies whichever template is used.
+        if !self.feedback.is_empty() {
+            user = format!(
+                "{}\n\nThe user's feedback on earlier reflections; adjust the coaching to it:\n{}\n",
+                user.trim_end(),
+                self.feedback.join("\n")
+            );
         }
-    );
-
-    let client = reqwest::Client::new();
-    let body = serde_json::json!({
-        "model": "gpt-4o-mini",
-        "messages": [
-            { "role": "system", "content": SYSTEM_INSTRUCTION },
-            { "role": "user", "content": user_content }
-        ],
-        "temperature": 0.7
-    });
-
-    let res = client
-        .post("https://api.openai.com/v1/chat/completions")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
+        Ok((system, user))
+    }
 
-    if !res.status().is_success() {
-        let err_text = res.text().await.unwrap_or_default();
-        return Err(format!("OpenAI API error: {}", err_text));
+    // One line per behavior with its score, quantity and tagged traits.
+    pub(crate) fn behaviors_text(&self) -> String {
+        self.behaviors
+            .iter()
+            .map(|b| {
+                let mut line = format!("- {} (alignment: {}/10", b.description, b.alignment_score);
+                if let (Some(value), Some(unit)) = (b.value, &b.unit) {
+                    line.push_str(&format!(", {} {}", value, unit));
+                }
+                let tags: Vec<&str> = self
+                    .traits
+                    .iter()
+                    .filter(|t| b.trait_ids.contains(&t.id))
+                    .map(|t| t.name.as_str())
+                    .collect();
+                if !tags.is_empty() {
+                    line.push_str(&format!(", traits: {}", tags.join(", ")));
+                }
+                line.push(')');
+                line
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
     }
 
-    let json: serde_json::Value = res.json().await.map_err(|e| e.to_string())?;
-    let content_raw = json
-        .get("choices")
-        .and_then(|c| c.get(0))
-        .and_then(|c| c.get("message"))
-        .and_then(|m| m.get("content"))
-        .and_then(|c| c.as_str())
-        .ok_or("Invalid OpenAI response")?;
+    fn values(&self, date: NaiveDate) -> Vec<(&'static str, String)> {
+        let behaviors_text = self.behaviors_text();
+        let traits_text = self
+            .traits
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
 
-    let content = content_raw.trim();
-    let content = content.strip_prefix("```json").unwrap_or(content);
-    let content = content.strip_prefix("```").unwrap_or(content).trim();
-    let content = content.strip_suffix("```").unwrap_or(content).trim();
+        vec![
+            ("date", dates::format_date(date)),
+            ("identity_name", self.identity.name.clone()),
+            ("identity_description", self.identity.description.clone()),
+            ("traits", traits_text),
+            (
+                "behaviors",
+                if behaviors_text.is_empty() {
+                    "(No behaviors logged today)".to_string()
+                } else {
+                    behaviors_text
+                },
+            ),
+            (
+                "week_average",
+                self.week_average
+                    .map(|a| format!("{:.1}/10", a))
+                    .unwrap_or_else(|| "no data".to_string()),
+            ),
+            ("streak", self.streak.to_string()),
+            ("previous_reflections", self.previous_text()),
+            ("language", language::name(&self.language).unwrap_or("English").to_string()),
+        ]
+    }
 
-    let conn = db::get_conn(&app)?;
+    fn previous_text(&self) -> String {
+        let parsed: Vec<(&str, &Reflection)> = self
+            .previous
+            .iter()
+            .filter_map(|d| d.reflection.as_ref().map(|r| (d.date.as_str(), r)))
+            .collect();
+        if parsed.is_empty() {
+            return "(none)".to_string();
+        }
+        parsed
+            .iter()
+            .map(|(date, r)| {
+                format!(
+                    "{}:\n  Observations: {}\n  Identity correction: {}",
+                    date,
+                    r.observations.join(" | "),
+                    r.identity_correction
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+// Renders the system and user prompts for a day from the given templates.
+pub(crate) fn render_prompt(
+    conn: &Connection,
+    identity_id: i64,
+    date: NaiveDate,
+    system_template: &str,
+    user_template: &str,
+) -> Result<(String, String), String> {
+    ReflectionContext::load(conn, identity_id, date)?.render_prompts(date, system_template, user_template)
+}
+
+// What gets stored for one generation.
+struct NewReflection {
+    reflection: Reflection,
+    content: String,
+    model: Option<String>,
+    prompt_hash: Option<String>,
+    template: Option<(i64, i64)>,
+    local: bool,
+    fallback_reason: Option<String>,
+}
+
+async fn run_reflection(
+    app: &AppHandle,
+    request_id: &str,
+    identity_id: i64,
+    date: &str,
+) -> Result<DailyReflection, String> {
+    let prepared = prepare(app, identity_id, date)?;
+
+    // Without a usable provider, or when the call fails, the day still gets
+    // a reflection from the local rules. A spent budget is reported instead,
+    // since a silent fallback would hide why the AI stopped answering.
+    let new = match ask_provider(app, request_id, identity_id, &prepared).await {
+        Ok((reflection, content, model)) => NewReflection {
+            reflection,
+            content,
+            model: Some(model),
+            prompt_hash: Some(llm::prompt_hash(&prepared.request)),
+            template: prepared.template.map(|t| (t.id, t.version)),
+            local: false,
+            fallback_reason: None,
+        },
+        Err(error) if llm::usage::is_budget_error(&error) => return Err(error),
+        Err(error) => {
+            // Offline: generate it properly once the network is back.
+            if llm::http::is_offline(&error) {
+                reflection_queue::enqueue(app, identity_id, date)?;
+            }
+            let reflection = local_reflection(&prepared.context);
+            let mut reason = error;
+            if prepared.context.language != language::DEFAULT {
+                reason.push_str(" (written in English: the local rules are not translated)");
+            }
+            NewReflection {
+                content: serde_json::to_string(&reflection).map_err(|e| e.to_string())?,
+                reflection,
+                model: None,
+                prompt_hash: None,
+                template: None,
+                local: true,
+                fallback_reason: Some(reason),
+            }
+        }
+    };
+    store(app, identity_id, date, &new)
+}
+
+// Generates a reflection with the provider only, for the queue and backfill;
+// errors are left for the caller to retry or report.
+pub(crate) async fn generate_with_provider(
+    app: &AppHandle,
+    request_id: &str,
+    identity_id: i64,
+    date: &str,
+) -> Result<DailyReflection, String> {
+    let prepared = prepare(app, identity_id, date)?;
+    let (reflection, content, model) = ask_provider(app, request_id, identity_id, &prepared).await?;
+    let new = NewReflection {
+        reflection,
+        content,
+        model: Some(model),
+        prompt_hash: Some(llm::prompt_hash(&prepared.request)),
+        template: prepared.template.map(|t| (t.id, t.version)),
+        local: false,
+        fallback_reason: None,
+    };
+    store(app, identity_id, date, &new)
+}
+
+// A day's request, ready to send.
+struct Prepared {
+    context: ReflectionContext,
+    // Already redacted; this is exactly what the provider receives.
+    request: llm::ChatRequest,
+    template: Option<PromptTemplate>,
+    // Maps the placeholders in the request back to the original text.
+    redactor: Redactor,
+}
+
+fn prepare(app: &AppHandle, identity_id: i64, date: &str) -> Result<Prepared, String> {
+    let day = dates::parse_date(date)?;
+    let conn = db::get_conn(app)?;
+    let context = ReflectionContext::load(&conn, identity_id, day)?;
+    let template = template_for_identity(&conn, &context.identity)?;
+    let (system, user) = match &template {
+        Some(t) => context.render_prompts(day, &t.system_template, &t.user_template)?,
+        None => context.render_prompts(day, DEFAULT_SYSTEM_TEMPLATE, DEFAULT_USER_TEMPLATE)?,
+    };
+    let mut redactor = redactor(&conn)?;
+    let request = llm::ChatRequest::new(redactor.redact(&system), redactor.redact(&user))
+        .json_schema("daily_reflection", reflection_schema());
+    Ok(Prepared {
+        context,
+        request,
+        template,
+        redactor,
+    })
+}
+
+// Every generation is kept as a new version of the day's reflection.
+fn store(app: &AppHandle, identity_id: i64, date: &str, new: &NewReflection) -> Result<DailyReflection, String> {
+    let conn = db::get_conn(app)?;
+    let reflection = &new.reflection;
+    let observations = serde_json::to_string(&reflection.observations).map_err(|e| e.to_string())?;
     conn.execute(
-        "INSERT OR REPLACE INTO daily_reflection (date, content, identity_id) VALUES (?1, ?2, ?3)",
-        [&input.date, content, &input.identity_id.to_string()],
+        "INSERT INTO daily_reflection (date, content, identity_id, title, alignment_summary, observations, \
+         identity_correction, closing_statement, follow_up_status, follow_up_note, version, model, prompt_hash, \
+         prompt_template_id, prompt_template_version, local, fallback_reason) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, \
+         (SELECT COALESCE(MAX(version), 0) + 1 FROM daily_reflection WHERE date = ?1 AND identity_id = ?3), \
+         ?11, ?12, ?13, ?14, ?15, ?16)",
+        rusqlite::params![
+            date,
+            &new.content,
+            identity_id,
+            &reflection.title,
+            &reflection.alignment_summary,
+            &observations,
+            &reflection.identity_correction,
+            &reflection.closing_statement,
+            reflection.follow_up.as_ref().map(|f| &f.status),
+            reflection.follow_up.as_ref().map(|f| &f.note),
+            &new.model,
+            &new.prompt_hash,
+            new.template.map(|t| t.0),
+            new.template.map(|t| t.1),
+            new.local,
+            &new.fallback_reason,
+        ],
     )
     .map_err(|e| e.to_string())?;
-    let id = conn.last_insert_rowid();
-    conn.query_row(
-        "SELECT id, date, content, identity_id, created_at FROM daily_reflection WHERE id = ?1",
-        [id],
-        |row| {
-            Ok(DailyReflection {
-                id: row.get(0)?,
-                date: row.get(1)?,
-                content: row.get(2)?,
-                identity_id: row.get(3)?,
-                created_at: row.get(4)?,
-            })
+    get_reflection_by_id(&conn, conn.last_insert_rowid())
+}
+
+// Sends the request to the configured provider, streaming deltas as events.
+// Returns the validated reflection with redacted text restored, its raw text
+// and the model used. Deltas are passed on as received, placeholders and all.
+async fn ask_provider(
+    app: &AppHandle,
+    request_id: &str,
+    identity_id: i64,
+    prepared: &Prepared,
+) -> Result<(Reflection, String, String), String> {
+    let (provider, config) = {
+        let conn = db::get_conn(app)?;
+        let config = llm::load_config(&conn, Some(identity_id))?;
+        let provider = llm::metered_provider(app, &config)?;
+        (provider, config)
+    };
+    let (reflection, content) = llm::complete_json::<Reflection, _>(
+        provider.as_ref(),
+        &prepared.request,
+        |r: &Reflection| r.validate(&prepared.context.language),
+        &mut |attempt, text| {
+            let _ = app.emit(
+                "reflection://delta",
+                DeltaEvent {
+                    request_id,
+                    attempt,
+                    text,
+                },
+            );
         },
     )
-    .map_err(|e| e.to_string())
+    .await?;
+    if prepared.redactor.replacements().is_empty() {
+        return Ok((reflection, content, config.model));
+    }
+    // Restored text may not be valid inside the raw JSON, so the stored
+    // content is rebuilt from the restored reflection.
+    let reflection = reflection.restore(&prepared.redactor);
+    let content = serde_json::to_string(&reflection).map_err(|e| e.to_string())?;
+    Ok((reflection, content, config.model))
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReflectionPayload {
+    pub provider: String,
+    pub model: String,
+    pub system: String,
+    pub user: String,
+    pub prompt_hash: String,
+    // Placeholders in the text and what they stand for; only the
+    // placeholders leave the device.
+    pub replacements: Vec<Replacement>,
+}
+
+// Shows exactly what generating the day's reflection would send, after
+// redaction, without calling the provider.
+#[tauri::command]
+pub fn preview_reflection_payload(app: AppHandle, identity_id: i64, date: String) -> Result<ReflectionPayload, String> {
+    let prepared = prepare(&app, identity_id, &date)?;
+    let config = llm::load_config(&db::get_conn(&app)?, Some(identity_id))?;
+    let request = &prepared.request;
+    Ok(ReflectionPayload {
+        provider: config.provider.as_str().to_string(),
+        model: config.model,
+        system: request.system.clone(),
+        user: request.messages.iter().map(|m| m.content.as_str()).collect::<Vec<_>>().join("\n\n"),
+        prompt_hash: llm::prompt_hash(request),
+        replacements: prepared.redactor.replacements().to_vec(),
+    })
 }
 
 #[tauri::command]
@@ -146,19 +761,17 @@ pub fn get_reflection_for_date(
     identity_id: i64,
     date: String,
 ) -> Result<Option<DailyReflection>, String> {
+    dates::parse_date(&date)?;
     let conn = db::get_conn(&app)?;
     let mut stmt = conn
-        .prepare("SELECT id, date, content, identity_id, created_at FROM daily_reflection WHERE identity_id = ?1 AND date = ?2")
+        .prepare(&format!(
+            "SELECT {} FROM daily_reflection WHERE identity_id = ?1 AND date = ?2 AND {}",
+            REFLECTION_COLUMNS, CURRENT_VERSION
+        ))
         .map_err(|e| e.to_string())?;
     let mut rows = stmt.query((identity_id, date.as_str())).map_err(|e| e.to_string())?;
     match rows.next().map_err(|e| e.to_string())? {
-        Some(row) => Ok(Some(DailyReflection {
-            id: row.get(0).map_err(|e| e.to_string())?,
-            date: row.get(1).map_err(|e| e.to_string())?,
-            content: row.get(2).map_err(|e| e.to_string())?,
-            identity_id: row.get(3).map_err(|e| e.to_string())?,
-            created_at: row.get(4).map_err(|e| e.to_string())?,
-        })),
+        Some(row) => reflection_from_row(row).map(Some).map_err(|e| e.to_string()),
         None => Ok(None),
     }
 }
@@ -172,18 +785,37 @@ pub fn list_reflections(
     let limit = limit.unwrap_or(30);
     let conn = db::get_conn(&app)?;
     let mut stmt = conn
-        .prepare("SELECT id, date, content, identity_id, created_at FROM daily_reflection WHERE identity_id = ?1 ORDER BY date DESC LIMIT ?2")
+        .prepare(&format!(
+            "SELECT {} FROM daily_reflection WHERE identity_id = ?1 AND {} ORDER BY date DESC LIMIT ?2",
+            REFLECTION_COLUMNS, CURRENT_VERSION
+        ))
         .map_err(|e| e.to_string())?;
     let rows = stmt
-        .query_map((identity_id, limit as i64), |row| {
-            Ok(DailyReflection {
-                id: row.get(0)?,
-                date: row.get(1)?,
-                content: row.get(2)?,
-                identity_id: row.get(3)?,
-                created_at: row.get(4)?,
-            })
-        })
+        .query_map((identity_id, limit as i64), reflection_from_row)
+        .map_err(|e| e.to_string())?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(out)
+}
+
+#[tauri::command]
+pub fn list_reflection_versions(
+    app: AppHandle,
+    identity_id: i64,
+    date: String,
+) -> Result<Vec<DailyReflection>, String> {
+    dates::parse_date(&date)?;
+    let conn = db::get_conn(&app)?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM daily_reflection WHERE identity_id = ?1 AND date = ?2 ORDER BY version DESC, id DESC",
+            REFLECTION_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map((identity_id, date.as_str()), reflection_from_row)
         .map_err(|e| e.to_string())?;
     let mut out = Vec::new();
     for r in rows {
@@ -191,3 +823,145 @@ pub fn list_reflections(
     }
     Ok(out)
 }
+
+#[tauri::command]
+pub fn pin_reflection(app: AppHandle, id: i64) -> Result<DailyReflection, String> {
+    let mut conn = db::get_conn(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let target = get_reflection_by_id(&tx, id)?;
+    tx.execute(
+        "UPDATE daily_reflection SET pinned = 0 WHERE identity_id = ?1 AND date = ?2 AND pinned = 1",
+        (target.identity_id, &target.date),
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute("UPDATE daily_reflection SET pinned = 1 WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    get_reflection_by_id(&conn, id)
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiffSegment {
+    pub tag: &'static str,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldDiff {
+    pub field: String,
+    pub changed: bool,
+    pub segments: Vec<DiffSegment>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReflectionDiff {
+    pub from: DailyReflection,
+    pub to: DailyReflection,
+    pub same_prompt: bool,
+    pub fields: Vec<FieldDiff>,
+}
+
+fn diff_text(field: &str, from: &str, to: &str) -> FieldDiff {
+    let segments = TextDiff::from_words(from, to)
+        .iter_all_changes()
+        .map(|change| DiffSegment {
+            tag: match change.tag() {
+                ChangeTag::Equal => "equal",
+                ChangeTag::Delete => "delete",
+                ChangeTag::Insert => "insert",
+            },
+            text: change.value().to_string(),
+        })
+        .collect();
+    FieldDiff {
+        field: field.to_string(),
+        changed: from != to,
+        segments,
+    }
+}
+
+// Word-level diff of two versions, field by field. Versions without parsed
+// fields are compared on their raw content.
+#[tauri::command]
+pub fn diff_reflections(app: AppHandle, from_id: i64, to_id: i64) -> Result<ReflectionDiff, String> {
+    let conn = db::get_conn(&app)?;
+    let from = get_reflection_by_id(&conn, from_id)?;
+    let to = get_reflection_by_id(&conn, to_id)?;
+    if from.identity_id != to.identity_id || from.date != to.date {
+        return Err("Only versions of the same day's reflection can be compared".to_string());
+    }
+    let fields = match (&from.reflection, &to.reflection) {
+        (Some(a), Some(b)) => {
+            let mut fields = vec![
+                diff_text("title", &a.title, &b.title),
+                diff_text("alignmentSummary", &a.alignment_summary, &b.alignment_summary),
+            ];
+            for i in 0..a.observations.len().max(b.observations.len()) {
+                let empty = String::new();
+                fields.push(diff_text(
+                    &format!("observations[{}]", i),
+                    a.observations.get(i).unwrap_or(&empty),
+                    b.observations.get(i).unwrap_or(&empty),
+                ));
+            }
+            fields.push(diff_text("identityCorrection", &a.identity_correction, &b.identity_correction));
+            fields.push(diff_text("closingStatement", &a.closing_statement, &b.closing_statement));
+            if let (Some(x), Some(y)) = (&a.follow_up, &b.follow_up) {
+                fields.push(diff_text(
+                    "followUp",
+                    &format!("{}: {}", x.status, x.note),
+                    &format!("{}: {}", y.status, y.note),
+                ));
+            }
+            fields
+        }
+        _ => vec![diff_text("content", &from.content, &to.content)],
+    };
+    Ok(ReflectionDiff {
+        same_prompt: from.prompt_hash.is_some() && from.prompt_hash == to.prompt_hash,
+        from,
+        to,
+        fields,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn current(conn: &Connection) -> i64 {
+        conn.query_row(
+            &format!("SELECT id FROM daily_reflection WHERE date = '2024-05-01' AND {}", CURRENT_VERSION),
+            [],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn local_fallback_does_not_replace_an_ai_version() {
+        let conn = db::test_conn();
+        let add = |local: bool| {
+            conn.execute(
+                "INSERT INTO daily_reflection (date, content, identity_id, local) VALUES ('2024-05-01', '{}', 1, ?1)",
+                [local],
+            )
+            .unwrap();
+            conn.last_insert_rowid()
+        };
+
+        // With only fallbacks, the newest one is shown.
+        add(true);
+        let fallback = add(true);
+        assert_eq!(current(&conn), fallback);
+
+        let ai = add(false);
+        let later_fallback = add(true);
+        assert_eq!(current(&conn), ai);
+
+        // Pinning still picks any version.
+        conn.execute("UPDATE daily_reflection SET pinned = 1 WHERE id = ?1", [later_fallback])
+            .unwrap();
+        assert_eq!(current(&conn), later_fallback);
+    }
+}
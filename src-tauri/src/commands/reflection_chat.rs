@@ -0,0 +1,242 @@
+use rusqlite::{Connection, Row};
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::commands::redaction::redactor;
+use crate::commands::reflection::{get_reflection_by_id, DailyReflection, ReflectionContext};
+use crate::{dates, db, language, llm};
+
+// Roughly 4 characters per token, so the history sent stays near 4k tokens
+// on top of the seed.
+const HISTORY_CHARS: usize = 16_000;
+
+const MESSAGE_CHARS: usize = 4_000;
+
+const CHAT_INSTRUCTION: &str = r#"You are an identity-based habit coach continuing a conversation about a daily reflection you wrote.
+Answer the user's questions about it: explain your reasoning from the behaviors logged that day, and when asked for a plan, give small, concrete steps that fit the identity.
+Be direct and brief. Reply in plain text, not JSON."#;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReflectionMessage {
+    pub id: i64,
+    pub reflection_id: i64,
+    // "user" or "assistant".
+    pub role: String,
+    pub content: String,
+    // The model that wrote an assistant message.
+    pub model: Option<String>,
+    pub created_at: String,
+}
+
+const MESSAGE_COLUMNS: &str = "id, reflection_id, role, content, model, created_at";
+
+fn message_from_row(row: &Row) -> rusqlite::Result<ReflectionMessage> {
+    Ok(ReflectionMessage {
+        id: row.get(0)?,
+        reflection_id: row.get(1)?,
+        role: row.get(2)?,
+        content: row.get(3)?,
+        model: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+fn messages(conn: &Connection, reflection_id: i64) -> Result<Vec<ReflectionMessage>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM reflection_message WHERE reflection_id = ?1 ORDER BY id",
+            MESSAGE_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([reflection_id], message_from_row)
+        .map_err(|e| e.to_string())?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(out)
+}
+
+// The system prompt: the identity, the day's behaviors and the reflection
+// the conversation is about.
+fn seed(context: &ReflectionContext, reflection: &DailyReflection) -> String {
+    let traits = context
+        .traits
+        .iter()
+        .map(|t| t.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let behaviors = match context.behaviors_text() {
+        text if text.is_empty() => "(No behaviors logged)".to_string(),
+        text => text,
+    };
+    let reflection_text = match &reflection.reflection {
+        Some(r) => format!(
+            "Title: {}\nAlignment summary: {}\nObservations:\n{}\nIdentity correction: {}\nClosing statement: {}",
+            r.title,
+            r.alignment_summary,
+            r.observations
+                .iter()
+                .map(|o| format!("- {}", o))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            r.identity_correction,
+            r.closing_statement
+        ),
+        None => reflection.content.clone(),
+    };
+    format!(
+        "{}\n{}\n\nIdentity: {}\nDescription: {}\nTraits: {}\n\nBehaviors on {}:\n{}\n\nYour reflection for that day:\n{}",
+        CHAT_INSTRUCTION,
+        language::instruction(&context.language),
+        context.identity.name,
+        context.identity.description,
+        traits,
+        reflection.date,
+        behaviors,
+        reflection_text
+    )
+}
+
+// Index of the oldest message that still fits in `budget` characters,
+// counting back from the newest. The newest message is always kept, and the
+// kept history starts with a user turn as providers require.
+fn window_start(history: &[llm::ChatMessage], budget: usize) -> usize {
+    let mut start = history.len();
+    let mut used = 0;
+    while start > 0 {
+        let len = history[start - 1].content.len();
+        if used + len > budget && start < history.len() {
+            break;
+        }
+        used += len;
+        start -= 1;
+    }
+    while start < history.len() - 1 && history[start].role != "user" {
+        start += 1;
+    }
+    start
+}
+
+// Asks a follow-up question about a reflection. The conversation is seeded
+// with the reflection, that day's behaviors and the identity, followed by as
+// many of the latest earlier turns as fit in HISTORY_CHARS. Both turns are
+// saved only once the reply has arrived.
+#[tauri::command]
+pub async fn chat_with_reflection(
+    app: AppHandle,
+    reflection_id: i64,
+    message: String,
+) -> Result<ReflectionMessage, String> {
+    let message = message.trim().to_string();
+    if message.is_empty() {
+        return Err("message is required".to_string());
+    }
+    if message.chars().count() > MESSAGE_CHARS {
+        return Err(format!("message is longer than {} characters", MESSAGE_CHARS));
+    }
+
+    let (request, redactor, config, provider) = {
+        let conn = db::get_conn(&app)?;
+        let reflection = get_reflection_by_id(&conn, reflection_id)?;
+        let day = dates::parse_date(&reflection.date)?;
+        let context = ReflectionContext::load(&conn, reflection.identity_id, day)?;
+
+        let mut history: Vec<llm::ChatMessage> = messages(&conn, reflection_id)?
+            .into_iter()
+            .map(|m| llm::ChatMessage {
+                role: m.role,
+                content: m.content,
+            })
+            .collect();
+        history.push(llm::ChatMessage::user(message.clone()));
+        let start = window_start(&history, HISTORY_CHARS);
+        let mut system = seed(&context, &reflection);
+        if start > 0 {
+            system.push_str("\n\n(Earlier messages in this conversation were left out.)");
+        }
+
+        let mut redactor = redactor(&conn)?;
+        let request = llm::ChatRequest {
+            system: redactor.redact(&system),
+            messages: history[start..]
+                .iter()
+                .map(|m| llm::ChatMessage {
+                    role: m.role.clone(),
+                    content: redactor.redact(&m.content),
+                })
+                .collect(),
+            temperature: None,
+            schema: None,
+        };
+        let config = llm::load_config(&conn, Some(reflection.identity_id))?;
+        let provider = llm::metered_provider(&app, &config)?;
+        (request, redactor, config, provider)
+    };
+
+    let reply = provider.complete(&request).await?.text;
+    let reply = redactor.restore(reply.trim());
+
+    let mut conn = db::get_conn(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO reflection_message (reflection_id, role, content) VALUES (?1, 'user', ?2)",
+        (reflection_id, &message),
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO reflection_message (reflection_id, role, content, model) VALUES (?1, 'assistant', ?2, ?3)",
+        (reflection_id, &reply, &config.model),
+    )
+    .map_err(|e| e.to_string())?;
+    let id = tx.last_insert_rowid();
+    tx.commit().map_err(|e| e.to_string())?;
+    conn.query_row(
+        &format!("SELECT {} FROM reflection_message WHERE id = ?1", MESSAGE_COLUMNS),
+        [id],
+        message_from_row,
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_reflection_messages(app: AppHandle, reflection_id: i64) -> Result<Vec<ReflectionMessage>, String> {
+    let conn = db::get_conn(&app)?;
+    messages(&conn, reflection_id)
+}
+
+// Starts the conversation over.
+#[tauri::command]
+pub fn clear_reflection_messages(app: AppHandle, reflection_id: i64) -> Result<(), String> {
+    let conn = db::get_conn(&app)?;
+    conn.execute("DELETE FROM reflection_message WHERE reflection_id = ?1", [reflection_id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(turns: &[(&str, usize)]) -> Vec<llm::ChatMessage> {
+        turns
+            .iter()
+            .map(|(role, len)| llm::ChatMessage {
+                role: role.to_string(),
+                content: "x".repeat(*len),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn window_keeps_newest_turns_starting_with_user() {
+        let h = history(&[("user", 10), ("assistant", 10), ("user", 10), ("assistant", 10), ("user", 10)]);
+        assert_eq!(window_start(&h, 100), 0);
+        // Four messages fit, but the window may not open on an assistant turn.
+        assert_eq!(window_start(&h, 40), 2);
+        // An oversized newest message is still sent on its own.
+        let h = history(&[("user", 10), ("assistant", 10), ("user", 500)]);
+        assert_eq!(window_start(&h, 100), 2);
+    }
+}
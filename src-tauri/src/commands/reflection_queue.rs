@@ -0,0 +1,215 @@
+use std::time::Duration;
+
+use rusqlite::{Connection, OptionalExtension, Row};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::commands::reflection::generate_with_provider;
+use crate::{db, llm};
+
+// How often pending reflections are retried while the network is down.
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+// How many finished entries the status keeps showing.
+const RECENT_DONE: i64 = 20;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueuedReflection {
+    pub id: i64,
+    pub identity_id: i64,
+    pub date: String,
+    // "pending", "processing", "done" or "failed".
+    pub status: String,
+    pub attempts: i64,
+    pub last_error: Option<String>,
+    // The reflection generated once the entry is done.
+    pub reflection_id: Option<i64>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueStatus {
+    pub pending: i64,
+    pub failed: i64,
+    pub items: Vec<QueuedReflection>,
+}
+
+const QUEUE_COLUMNS: &str =
+    "id, identity_id, date, status, attempts, last_error, reflection_id, created_at, updated_at";
+
+fn queued_from_row(row: &Row) -> rusqlite::Result<QueuedReflection> {
+    Ok(QueuedReflection {
+        id: row.get(0)?,
+        identity_id: row.get(1)?,
+        date: row.get(2)?,
+        status: row.get(3)?,
+        attempts: row.get(4)?,
+        last_error: row.get(5)?,
+        reflection_id: row.get(6)?,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
+
+fn queue_status(conn: &Connection) -> Result<QueueStatus, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM reflection_queue WHERE status != 'done' \
+             OR id IN (SELECT id FROM reflection_queue WHERE status = 'done' ORDER BY id DESC LIMIT ?1) \
+             ORDER BY id DESC",
+            QUEUE_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let items = stmt
+        .query_map([RECENT_DONE], queued_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(QueueStatus {
+        pending: items.iter().filter(|i| i.status == "pending" || i.status == "processing").count() as i64,
+        failed: items.iter().filter(|i| i.status == "failed").count() as i64,
+        items,
+    })
+}
+
+// Tells the frontend the queue changed, with the new status.
+fn notify(app: &AppHandle, conn: &Connection) -> Result<(), String> {
+    let _ = app.emit("reflection-queue://changed", queue_status(conn)?);
+    Ok(())
+}
+
+// Queues a day for generation once the network is back. A day already
+// waiting is not queued twice.
+pub(crate) fn enqueue(app: &AppHandle, identity_id: i64, date: &str) -> Result<(), String> {
+    let conn = db::get_conn(app)?;
+    conn.execute(
+        "INSERT INTO reflection_queue (identity_id, date) SELECT ?1, ?2 WHERE NOT EXISTS \
+         (SELECT 1 FROM reflection_queue WHERE identity_id = ?1 AND date = ?2 AND status IN ('pending', 'processing'))",
+        (identity_id, date),
+    )
+    .map_err(|e| e.to_string())?;
+    notify(app, &conn)
+}
+
+// Takes the oldest pending entry. The conditional update keeps two runs from
+// picking the same one.
+fn claim_next(conn: &Connection) -> Result<Option<QueuedReflection>, String> {
+    loop {
+        let next = conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM reflection_queue WHERE status = 'pending' ORDER BY id LIMIT 1",
+                    QUEUE_COLUMNS
+                ),
+                [],
+                queued_from_row,
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let Some(next) = next else {
+            return Ok(None);
+        };
+        let claimed = conn
+            .execute(
+                "UPDATE reflection_queue SET status = 'processing', updated_at = datetime('now') \
+                 WHERE id = ?1 AND status = 'pending'",
+                [next.id],
+            )
+            .map_err(|e| e.to_string())?;
+        if claimed == 1 {
+            return Ok(Some(next));
+        }
+    }
+}
+
+fn finish(conn: &Connection, id: i64, result: &Result<i64, String>, status: &str) -> Result<(), String> {
+    conn.execute(
+        "UPDATE reflection_queue SET status = ?2, attempts = attempts + 1, last_error = ?3, \
+         reflection_id = ?4, updated_at = datetime('now') WHERE id = ?1",
+        (id, status, result.as_ref().err(), result.as_ref().ok()),
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Works through pending entries until the queue is empty or the network is
+// still down.
+async fn process(app: &AppHandle) -> Result<(), String> {
+    loop {
+        let Some(item) = claim_next(&db::get_conn(app)?)? else {
+            return Ok(());
+        };
+        let request_id = format!("queue-{}", item.id);
+        let result = generate_with_provider(app, &request_id, item.identity_id, &item.date)
+            .await
+            .map(|r| r.id);
+        let status = match &result {
+            Ok(_) => "done",
+            Err(e) if llm::http::is_offline(e) => "pending",
+            Err(_) => "failed",
+        };
+        let conn = db::get_conn(app)?;
+        finish(&conn, item.id, &result, status)?;
+        notify(app, &conn)?;
+        if status == "pending" {
+            return Ok(());
+        }
+    }
+}
+
+// Runs for the life of the app, retrying the queue periodically.
+pub(crate) async fn run(app: AppHandle) {
+    // Entries left processing by a previous run never finished.
+    if let Ok(conn) = db::get_conn(&app) {
+        let _ = conn.execute(
+            "UPDATE reflection_queue SET status = 'pending' WHERE status = 'processing'",
+            [],
+        );
+    }
+    loop {
+        if let Err(e) = process(&app).await {
+            eprintln!("Reflection queue error: {}", e);
+        }
+        tokio::time::sleep(RETRY_INTERVAL).await;
+    }
+}
+
+#[tauri::command]
+pub fn get_reflection_queue(app: AppHandle) -> Result<QueueStatus, String> {
+    let conn = db::get_conn(&app)?;
+    queue_status(&conn)
+}
+
+// Processes the queue now, e.g. when the webview reports it is back online.
+#[tauri::command]
+pub async fn process_reflection_queue(app: AppHandle) -> Result<QueueStatus, String> {
+    process(&app).await?;
+    let conn = db::get_conn(&app)?;
+    queue_status(&conn)
+}
+
+// Puts a failed entry back in line.
+#[tauri::command]
+pub fn retry_queued_reflection(app: AppHandle, id: i64) -> Result<QueueStatus, String> {
+    let conn = db::get_conn(&app)?;
+    conn.execute(
+        "UPDATE reflection_queue SET status = 'pending', updated_at = datetime('now') WHERE id = ?1 AND status = 'failed'",
+        [id],
+    )
+    .map_err(|e| e.to_string())?;
+    notify(&app, &conn)?;
+    queue_status(&conn)
+}
+
+#[tauri::command]
+pub fn remove_queued_reflection(app: AppHandle, id: i64) -> Result<QueueStatus, String> {
+    let conn = db::get_conn(&app)?;
+    conn.execute(
+        "DELETE FROM reflection_queue WHERE id = ?1 AND status != 'processing'",
+        [id],
+    )
+    .map_err(|e| e.to_string())?;
+    notify(&app, &conn)?;
+    queue_status(&conn)
+}
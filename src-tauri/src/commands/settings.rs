@@ -0,0 +1,167 @@
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{db, language};
+use crate::llm::{self, LlmConfig};
+
+const TIMEZONE: &str = "timezone";
+const DAY_END_HOUR: &str = "day_end_hour";
+const FEEDBACK_IN_PROMPT: &str = "feedback_in_prompt";
+const REDACT_BUILTIN: &str = "redact_builtin";
+const LANGUAGE: &str = "language";
+const ALLOW_FILE_CREDENTIALS: &str = "allow_file_credentials";
+
+#[derive(Debug, Serialize)]
+pub struct Settings {
+    // IANA name such as "America/Los_Angeles"; None follows the system clock.
+    pub timezone: Option<String>,
+    // Hour (0–23) at which the logging day rolls over, e.g. 3 for 3am.
+    pub day_end_hour: u32,
+    // Quote recent negative reflection feedback in the next prompt.
+    pub feedback_in_prompt: bool,
+    // Replace emails, phone numbers and URLs in reflection prompts. On
+    // unless switched off.
+    pub redact_builtin: bool,
+    // Language code reflections are written in unless an identity sets its
+    // own, e.g. "de".
+    pub language: String,
+    // Keep API keys in a permissions-protected file when no OS keyring is
+    // available. Off unless the user opts in.
+    pub allow_file_credentials: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateSettingsInput {
+    pub timezone: Option<String>,
+    pub day_end_hour: Option<u32>,
+    pub feedback_in_prompt: Option<bool>,
+    pub redact_builtin: Option<bool>,
+    pub language: Option<String>,
+    pub allow_file_credentials: Option<bool>,
+}
+
+pub(crate) fn get_setting(conn: &Connection, key: &str) -> Result<Option<String>, String> {
+    conn.query_row("SELECT value FROM app_setting WHERE key = ?1", [key], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())
+}
+
+pub(crate) fn set_setting(conn: &Connection, key: &str, value: Option<&str>) -> Result<(), String> {
+    match value {
+        Some(v) => conn.execute(
+            "INSERT INTO app_setting (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            [key, v],
+        ),
+        None => conn.execute("DELETE FROM app_setting WHERE key = ?1", [key]),
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub(crate) fn load_settings(conn: &Connection) -> Result<Settings, String> {
+    let day_end_hour = match get_setting(conn, DAY_END_HOUR)? {
+        Some(v) => v.parse().map_err(|_| format!("Invalid stored day_end_hour: {}", v))?,
+        None => 0,
+    };
+    Ok(Settings {
+        timezone: get_setting(conn, TIMEZONE)?,
+        day_end_hour,
+        feedback_in_prompt: get_setting(conn, FEEDBACK_IN_PROMPT)?.as_deref() == Some("1"),
+        redact_builtin: get_setting(conn, REDACT_BUILTIN)?.as_deref() != Some("0"),
+        language: get_setting(conn, LANGUAGE)?.unwrap_or_else(|| language::DEFAULT.to_string()),
+        allow_file_credentials: get_setting(conn, ALLOW_FILE_CREDENTIALS)?.as_deref() == Some("1"),
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct Language {
+    pub code: &'static str,
+    pub name: &'static str,
+}
+
+// The languages reflections can be written in.
+#[tauri::command]
+pub fn list_languages() -> Vec<Language> {
+    language::LANGUAGES
+        .iter()
+        .map(|(code, name)| Language { code, name })
+        .collect()
+}
+
+#[tauri::command]
+pub fn get_settings(app: AppHandle) -> Result<Settings, String> {
+    let conn = db::get_conn(&app)?;
+    load_settings(&conn)
+}
+
+// Every field is checked before anything is written, so a bad value leaves
+// all settings as they were.
+#[tauri::command]
+pub fn update_settings(app: AppHandle, input: UpdateSettingsInput) -> Result<Settings, String> {
+    let mut changes: Vec<(&str, Option<String>)> = Vec::new();
+    if let Some(tz) = input.timezone {
+        let tz = tz.trim();
+        if !tz.is_empty() {
+            tz.parse::<chrono_tz::Tz>()
+                .map_err(|_| format!("Unknown timezone: {}", tz))?;
+        }
+        changes.push((TIMEZONE, (!tz.is_empty()).then(|| tz.to_string())));
+    }
+    if let Some(hour) = input.day_end_hour {
+        if hour > 23 {
+            return Err("day_end_hour must be between 0 and 23".to_string());
+        }
+        changes.push((DAY_END_HOUR, Some(hour.to_string())));
+    }
+    if let Some(enabled) = input.feedback_in_prompt {
+        changes.push((FEEDBACK_IN_PROMPT, enabled.then(|| "1".to_string())));
+    }
+    if let Some(enabled) = input.redact_builtin {
+        changes.push((REDACT_BUILTIN, (!enabled).then(|| "0".to_string())));
+    }
+    if let Some(code) = input.language {
+        language::check(&code)?;
+        changes.push((LANGUAGE, (code != language::DEFAULT).then_some(code)));
+    }
+    if let Some(enabled) = input.allow_file_credentials {
+        changes.push((ALLOW_FILE_CREDENTIALS, enabled.then(|| "1".to_string())));
+    }
+
+    let mut conn = db::get_conn(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    for (key, value) in &changes {
+        set_setting(&tx, key, value.as_deref())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    load_settings(&conn)
+}
+
+#[tauri::command]
+pub fn get_llm_config(app: AppHandle, identity_id: Option<i64>) -> Result<Option<LlmConfig>, String> {
+    let conn = db::get_conn(&app)?;
+    llm::stored_config(&conn, identity_id)
+}
+
+#[tauri::command]
+pub fn get_effective_llm_config(app: AppHandle, identity_id: Option<i64>) -> Result<LlmConfig, String> {
+    let conn = db::get_conn(&app)?;
+    llm::load_config(&conn, identity_id)
+}
+
+#[tauri::command]
+pub fn set_llm_config(
+    app: AppHandle,
+    identity_id: Option<i64>,
+    config: LlmConfig,
+) -> Result<LlmConfig, String> {
+    let conn = db::get_conn(&app)?;
+    llm::save_config(&conn, identity_id, &config)?;
+    llm::load_config(&conn, identity_id)
+}
+
+#[tauri::command]
+pub fn clear_llm_config(app: AppHandle, identity_id: Option<i64>) -> Result<(), String> {
+    let conn = db::get_conn(&app)?;
+    llm::delete_config(&conn, identity_id)
+}
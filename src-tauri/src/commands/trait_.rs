@@ -1,16 +1,42 @@
+use rusqlite::{Connection, Row};
 use serde::{Deserialize, Serialize};
 use tauri::AppHandle;
 
-use crate::db;
+use crate::commands::identity::get_identity_by_id;
+use crate::commands::redaction::redactor;
+use crate::{db, llm};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Trait {
     pub id: i64,
     pub name: String,
     pub identity_id: i64,
+    // A short definition, set when the trait came from a suggestion.
+    pub definition: Option<String>,
     pub created_at: String,
 }
 
+const TRAIT_COLUMNS: &str = "id, name, identity_id, definition, created_at";
+
+fn trait_from_row(row: &Row) -> rusqlite::Result<Trait> {
+    Ok(Trait {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        identity_id: row.get(2)?,
+        definition: row.get(3)?,
+        created_at: row.get(4)?,
+    })
+}
+
+fn get_trait_by_id(conn: &Connection, id: i64) -> Result<Trait, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM trait WHERE id = ?1", TRAIT_COLUMNS),
+        [id],
+        trait_from_row,
+    )
+    .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn create_trait(app: AppHandle, identity_id: i64, name: String) -> Result<Trait, String> {
     let conn = db::get_conn(&app)?;
@@ -19,37 +45,27 @@ pub fn create_trait(app: AppHandle, identity_id: i64, name: String) -> Result<Tr
         [&name, &identity_id.to_string()],
     )
     .map_err(|e| e.to_string())?;
-    let id = conn.last_insert_rowid();
-    conn.query_row(
-        "SELECT id, name, identity_id, created_at FROM trait WHERE id = ?1",
-        [id],
-        |row| {
-            Ok(Trait {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                identity_id: row.get(2)?,
-                created_at: row.get(3)?,
-            })
-        },
-    )
-    .map_err(|e| e.to_string())
+    get_trait_by_id(&conn, conn.last_insert_rowid())
 }
 
 #[tauri::command]
 pub fn list_traits(app: AppHandle, identity_id: i64) -> Result<Vec<Trait>, String> {
     let conn = db::get_conn(&app)?;
+    traits_for_identity(&conn, identity_id)
+}
+
+pub(crate) fn traits_for_identity(
+    conn: &Connection,
+    identity_id: i64,
+) -> Result<Vec<Trait>, String> {
     let mut stmt = conn
-        .prepare("SELECT id, name, identity_id, created_at FROM trait WHERE identity_id = ?1 ORDER BY created_at")
+        .prepare(&format!(
+            "SELECT {} FROM trait WHERE identity_id = ?1 ORDER BY created_at",
+            TRAIT_COLUMNS
+        ))
         .map_err(|e| e.to_string())?;
     let rows = stmt
-        .query_map([identity_id], |row| {
-            Ok(Trait {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                identity_id: row.get(2)?,
-                created_at: row.get(3)?,
-            })
-        })
+        .query_map([identity_id], trait_from_row)
         .map_err(|e| e.to_string())?;
     let mut out = Vec::new();
     for r in rows {
@@ -65,3 +81,169 @@ pub fn delete_trait(app: AppHandle, id: i64) -> Result<(), String> {
         .map_err(|e| e.to_string())?;
     Ok(())
 }
+
+const SUGGEST_TRAITS_INSTRUCTION: &str = r#"You are a psychologically intelligent identity performance coach.
+Suggest 5 to 8 traits that signal the user's identity: qualities a person with this identity shows in their daily behavior.
+Use one or two words per trait name, and do not repeat or rephrase the existing traits.
+For each trait give a one-sentence definition and 2 or 3 small, concrete example behaviors.
+
+Respond with valid JSON only, in this exact structure:
+{
+  "traits": [
+    { "name": "decisive", "definition": "one sentence", "examples": ["example behavior"] }
+  ]
+}"#;
+
+fn traits_schema() -> serde_json::Value {
+    let text = serde_json::json!({ "type": "string" });
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "traits": {
+                "type": "array",
+                "minItems": 5,
+                "maxItems": 8,
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "name": text,
+                        "definition": text,
+                        "examples": { "type": "array", "minItems": 1, "maxItems": 3, "items": text }
+                    },
+                    "required": ["name", "definition", "examples"],
+                    "additionalProperties": false
+                }
+            }
+        },
+        "required": ["traits"],
+        "additionalProperties": false
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct RawTraitSuggestions {
+    traits: Vec<TraitSuggestion>,
+}
+
+impl RawTraitSuggestions {
+    fn validate(&self) -> Result<(), String> {
+        if self.traits.is_empty() {
+            return Err("expected at least one trait".to_string());
+        }
+        if self.traits.iter().any(|t| t.name.trim().is_empty()) {
+            return Err("a trait has an empty name".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TraitSuggestion {
+    pub name: String,
+    pub definition: String,
+    #[serde(default)]
+    pub examples: Vec<String>,
+}
+
+// Asks for candidate traits from the identity's name and description. Traits
+// the identity already has are given to the model and dropped from the reply.
+#[tauri::command]
+pub async fn suggest_traits(app: AppHandle, identity_id: i64) -> Result<Vec<TraitSuggestion>, String> {
+    let (existing, request, redactor, provider) = {
+        let conn = db::get_conn(&app)?;
+        let identity = get_identity_by_id(&conn, identity_id)?;
+        let existing = traits_for_identity(&conn, identity_id)?;
+        let prompt = format!(
+            "Identity: {}\nDescription: {}\nExisting traits: {}\n",
+            identity.name,
+            if identity.description.trim().is_empty() {
+                "(none)"
+            } else {
+                identity.description.trim()
+            },
+            if existing.is_empty() {
+                "(none)".to_string()
+            } else {
+                existing.iter().map(|t| t.name.as_str()).collect::<Vec<_>>().join(", ")
+            },
+        );
+        let mut redactor = redactor(&conn)?;
+        let request = llm::ChatRequest::new(SUGGEST_TRAITS_INSTRUCTION, redactor.redact(&prompt))
+            .json_schema("trait_suggestions", traits_schema());
+        let provider = llm::provider_for_identity(&app, &conn, identity_id)?;
+        (existing, request, redactor, provider)
+    };
+
+    let (raw, _) = llm::complete_json::<RawTraitSuggestions, _>(
+        provider.as_ref(),
+        &request,
+        RawTraitSuggestions::validate,
+        &mut |_, _| {},
+    )
+    .await?;
+    let mut seen: Vec<String> = existing.iter().map(|t| t.name.trim().to_lowercase()).collect();
+    let mut out = Vec::new();
+    for t in raw.traits {
+        let name = redactor.restore(t.name.trim());
+        if seen.contains(&name.to_lowercase()) {
+            continue;
+        }
+        seen.push(name.to_lowercase());
+        out.push(TraitSuggestion {
+            name,
+            definition: redactor.restore(t.definition.trim()),
+            examples: t
+                .examples
+                .iter()
+                .map(|e| redactor.restore(e.trim()))
+                .filter(|e| !e.is_empty())
+                .collect(),
+        });
+    }
+    Ok(out)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AcceptTraitInput {
+    pub name: String,
+    #[serde(default)]
+    pub definition: Option<String>,
+}
+
+// Adds the accepted suggestions to the identity in one transaction. Blank
+// names and names the identity already has (ignoring case) are skipped, so
+// only the traits actually created are returned.
+#[tauri::command]
+pub fn accept_trait_suggestions(
+    app: AppHandle,
+    identity_id: i64,
+    traits: Vec<AcceptTraitInput>,
+) -> Result<Vec<Trait>, String> {
+    let mut conn = db::get_conn(&app)?;
+    get_identity_by_id(&conn, identity_id)?;
+    let mut seen: Vec<String> = traits_for_identity(&conn, identity_id)?
+        .iter()
+        .map(|t| t.name.trim().to_lowercase())
+        .collect();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut ids = Vec::new();
+    for t in &traits {
+        let name = t.name.trim();
+        if name.is_empty() || seen.contains(&name.to_lowercase()) {
+            continue;
+        }
+        seen.push(name.to_lowercase());
+        tx.execute(
+            "INSERT INTO trait (name, identity_id, definition) VALUES (?1, ?2, ?3)",
+            (
+                name,
+                identity_id,
+                t.definition.as_deref().map(str::trim).filter(|d| !d.is_empty()),
+            ),
+        )
+        .map_err(|e| e.to_string())?;
+        ids.push(tx.last_insert_rowid());
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    ids.into_iter().map(|id| get_trait_by_id(&conn, id)).collect()
+}
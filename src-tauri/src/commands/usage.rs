@@ -0,0 +1,182 @@
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::commands::settings::set_setting;
+use crate::llm::usage::{month_spent, month_start, monthly_budget, Price, MONTHLY_BUDGET};
+use crate::{dates, db};
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageRange {
+    Today,
+    // The last 7 days, including today.
+    Week,
+    // The current calendar month, the same window the budget applies to.
+    Month,
+    All,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModelUsage {
+    pub provider: String,
+    pub model: String,
+    pub calls: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub cost: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AiUsage {
+    // None for the "all" range.
+    pub from_date: Option<String>,
+    pub to_date: String,
+    pub calls: i64,
+    pub failed_calls: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    // Estimated USD; calls to models without a price count as free. While a
+    // budget is set such calls are refused.
+    pub cost: f64,
+    // Successful calls whose tokens could not be priced.
+    pub unpriced_calls: i64,
+    pub average_latency_ms: Option<f64>,
+    pub by_model: Vec<ModelUsage>,
+    pub monthly_budget: Option<f64>,
+    pub month_spent: f64,
+}
+
+#[tauri::command]
+pub fn get_ai_usage(app: AppHandle, range: UsageRange) -> Result<AiUsage, String> {
+    let conn = db::get_conn(&app)?;
+    let today = dates::local_today(&conn)?;
+    let from_date = match range {
+        UsageRange::Today => Some(today),
+        UsageRange::Week => Some(today - Duration::days(6)),
+        UsageRange::Month => Some(month_start(&conn)?),
+        UsageRange::All => None,
+    }
+    .map(dates::format_date);
+
+    let mut usage = conn
+        .query_row(
+            "SELECT COUNT(*), COUNT(error), COALESCE(SUM(prompt_tokens), 0), COALESCE(SUM(completion_tokens), 0), \
+             COALESCE(SUM(cost), 0), COUNT(CASE WHEN error IS NULL AND cost IS NULL THEN 1 END), AVG(latency_ms) \
+             FROM ai_usage WHERE ?1 IS NULL OR date >= ?1",
+            [&from_date],
+            |row| {
+                Ok(AiUsage {
+                    from_date: from_date.clone(),
+                    to_date: dates::format_date(today),
+                    calls: row.get(0)?,
+                    failed_calls: row.get(1)?,
+                    prompt_tokens: row.get(2)?,
+                    completion_tokens: row.get(3)?,
+                    cost: row.get(4)?,
+                    unpriced_calls: row.get(5)?,
+                    average_latency_ms: row.get(6)?,
+                    by_model: Vec::new(),
+                    monthly_budget: None,
+                    month_spent: 0.0,
+                })
+            },
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT provider, model, COUNT(*), COALESCE(SUM(prompt_tokens), 0), COALESCE(SUM(completion_tokens), 0), \
+             COALESCE(SUM(cost), 0) FROM ai_usage WHERE ?1 IS NULL OR date >= ?1 \
+             GROUP BY provider, model ORDER BY SUM(cost) DESC, COUNT(*) DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([&from_date], |row| {
+            Ok(ModelUsage {
+                provider: row.get(0)?,
+                model: row.get(1)?,
+                calls: row.get(2)?,
+                prompt_tokens: row.get(3)?,
+                completion_tokens: row.get(4)?,
+                cost: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    for r in rows {
+        usage.by_model.push(r.map_err(|e| e.to_string())?);
+    }
+    usage.monthly_budget = monthly_budget(&conn)?;
+    usage.month_spent = month_spent(&conn)?;
+    Ok(usage)
+}
+
+#[tauri::command]
+pub fn list_ai_prices(app: AppHandle) -> Result<Vec<Price>, String> {
+    let conn = db::get_conn(&app)?;
+    let mut stmt = conn
+        .prepare("SELECT model, prompt_per_million, completion_per_million FROM ai_price ORDER BY model")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(Price {
+                model: row.get(0)?,
+                prompt_per_million: row.get(1)?,
+                completion_per_million: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(out)
+}
+
+// Prices are USD per million tokens. `model` may be a prefix such as
+// "gpt-4o" to cover every snapshot of that model.
+#[tauri::command]
+pub fn set_ai_price(
+    app: AppHandle,
+    model: String,
+    prompt_per_million: f64,
+    completion_per_million: f64,
+) -> Result<(), String> {
+    let model = model.trim();
+    if model.is_empty() {
+        return Err("model is required".to_string());
+    }
+    if [prompt_per_million, completion_per_million]
+        .iter()
+        .any(|p| *p < 0.0 || !p.is_finite())
+    {
+        return Err("prices must be finite and not negative".to_string());
+    }
+    let conn = db::get_conn(&app)?;
+    conn.execute(
+        "INSERT INTO ai_price (model, prompt_per_million, completion_per_million) VALUES (?1, ?2, ?3) \
+         ON CONFLICT(model) DO UPDATE SET prompt_per_million = excluded.prompt_per_million, \
+         completion_per_million = excluded.completion_per_million",
+        (model, prompt_per_million, completion_per_million),
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_ai_price(app: AppHandle, model: String) -> Result<(), String> {
+    let conn = db::get_conn(&app)?;
+    conn.execute("DELETE FROM ai_price WHERE model = ?1", [model.trim()])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// None removes the cap.
+#[tauri::command]
+pub fn set_ai_budget(app: AppHandle, monthly_usd: Option<f64>) -> Result<(), String> {
+    if monthly_usd.is_some_and(|b| b < 0.0 || !b.is_finite()) {
+        return Err("The budget cannot be negative".to_string());
+    }
+    let conn = db::get_conn(&app)?;
+    set_setting(&conn, MONTHLY_BUDGET, monthly_usd.map(|b| b.to_string()).as_deref())
+}
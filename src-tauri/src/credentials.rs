@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+const SERVICE: &str = "identity-habit-ai";
+const NONCE_LEN: usize = 12;
+const NO_KEYRING: &str = "No OS keyring is available to store the API key. Allow the plain-file \
+fallback to keep it in the app data folder, protected only by file permissions";
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyStore {
+    Keyring,
+    File,
+}
+
+// Stores an API key in the OS keyring (Keychain, Credential Manager, Secret
+// Service). Without a keyring the key only goes to a file in the app data dir
+// when `allow_file` is set: that file is obfuscated, not encrypted, since its
+// key sits next to it, so it is only as safe as the 0600 permissions on the
+// two files.
+pub fn store_key(
+    app: &AppHandle,
+    account: &str,
+    secret: &str,
+    allow_file: bool,
+) -> Result<KeyStore, String> {
+    let (a, s) = (account.to_string(), secret.to_string());
+    if on_keyring_thread(move || keyring::Entry::new(SERVICE, &a)?.set_password(&s)).is_ok() {
+        // Drop any stale copy from an earlier fallback.
+        remove_from_file(app, account)?;
+        return Ok(KeyStore::Keyring);
+    }
+    if !allow_file {
+        return Err(NO_KEYRING.to_string());
+    }
+    let dir = store_dir(app)?;
+    let mut keys = read_file(&dir)?;
+    keys.insert(account.to_string(), secret.to_string());
+    write_file(&dir, &keys)?;
+    Ok(KeyStore::File)
+}
+
+pub fn load_key(app: &AppHandle, account: &str) -> Result<Option<(String, KeyStore)>, String> {
+    let a = account.to_string();
+    if let Ok(secret) = on_keyring_thread(move || keyring::Entry::new(SERVICE, &a)?.get_password()) {
+        return Ok(Some((secret, KeyStore::Keyring)));
+    }
+    Ok(read_file(&store_dir(app)?)?.remove(account).map(|s| (s, KeyStore::File)))
+}
+
+pub fn delete_key(app: &AppHandle, account: &str) -> Result<(), String> {
+    let a = account.to_string();
+    match on_keyring_thread(move || keyring::Entry::new(SERVICE, &a)?.delete_credential()) {
+        Ok(())
+        | Err(keyring::Error::NoEntry)
+        | Err(keyring::Error::PlatformFailure(_))
+        | Err(keyring::Error::NoStorageAccess(_)) => {}
+        Err(e) => return Err(e.to_string()),
+    }
+    remove_from_file(app, account)
+}
+
+// The async Secret Service backend drives its own runtime and deadlocks when
+// called from a thread that already runs one, so keyring calls get a thread
+// of their own.
+fn on_keyring_thread<T: Send + 'static>(
+    f: impl FnOnce() -> keyring::Result<T> + Send + 'static,
+) -> keyring::Result<T> {
+    std::thread::spawn(f)
+        .join()
+        .unwrap_or_else(|_| Err(keyring::Error::Invalid("keyring".into(), "thread panicked".into())))
+}
+
+fn store_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| format!("{}", e))?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+fn remove_from_file(app: &AppHandle, account: &str) -> Result<(), String> {
+    let dir = store_dir(app)?;
+    let mut keys = read_file(&dir)?;
+    if keys.remove(account).is_some() {
+        write_file(&dir, &keys)?;
+    }
+    Ok(())
+}
+
+// The cipher only keeps the keys out of casual view (backups, grep, a quick
+// look at the file). credentials.key lives in the same directory, so anyone
+// who can read credentials.enc can read the key too.
+fn file_cipher(dir: &Path) -> Result<ChaCha20Poly1305, String> {
+    let path = dir.join("credentials.key");
+    let key = match std::fs::read(&path) {
+        Ok(bytes) if bytes.len() == 32 => bytes,
+        Ok(_) => return Err("Credential key file is corrupt".to_string()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let key = ChaCha20Poly1305::generate_key(&mut OsRng).to_vec();
+            write_private(&path, &key)?;
+            key
+        }
+        Err(e) => return Err(e.to_string()),
+    };
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+fn read_file(dir: &Path) -> Result<HashMap<String, String>, String> {
+    let path = dir.join("credentials.enc");
+    let data = match std::fs::read(&path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(e) => return Err(e.to_string()),
+    };
+    if data.len() < NONCE_LEN {
+        return Err("Credential store is corrupt".to_string());
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    let plain = file_cipher(dir)?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Credential store could not be decrypted".to_string())?;
+    serde_json::from_slice(&plain).map_err(|e| e.to_string())
+}
+
+fn write_file(dir: &Path, keys: &HashMap<String, String>) -> Result<(), String> {
+    let path = dir.join("credentials.enc");
+    if keys.is_empty() {
+        return match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+            _ => Ok(()),
+        };
+    }
+    let plain = serde_json::to_vec(keys).map_err(|e| e.to_string())?;
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = file_cipher(dir)?
+        .encrypt(&nonce, plain.as_slice())
+        .map_err(|_| "Could not encrypt credential store".to_string())?;
+    let mut data = nonce.to_vec();
+    data.extend_from_slice(&ciphertext);
+    write_private(&path, &data)
+}
+
+// Creates the file owner-only so the data is never readable by others, even
+// briefly. The mode only applies to new files, so an existing one is
+// tightened before it is truncated and rewritten.
+fn write_private(path: &Path, data: &[u8]) -> Result<(), String> {
+    use std::io::Write;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path).map_err(|e| e.to_string())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))
+            .map_err(|e| e.to_string())?;
+    }
+    file.set_len(0).map_err(|e| e.to_string())?;
+    file.write_all(data).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("credentials-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn file_store_round_trips() {
+        let dir = temp_dir("round-trip");
+        assert!(read_file(&dir).unwrap().is_empty());
+        let keys = HashMap::from([("openai".to_string(), "sk-secret".to_string())]);
+        write_file(&dir, &keys).unwrap();
+        assert_eq!(read_file(&dir).unwrap(), keys);
+        let raw = std::fs::read(dir.join("credentials.enc")).unwrap();
+        assert!(!raw.windows(9).any(|w| w == b"sk-secret"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            for file in ["credentials.enc", "credentials.key"] {
+                let mode = std::fs::metadata(dir.join(file)).unwrap().permissions().mode();
+                assert_eq!(mode & 0o777, 0o600, "{}", file);
+            }
+        }
+        // Rewriting a file that was left world-readable tightens it first.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let path = dir.join("credentials.enc");
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+            write_file(&dir, &keys).unwrap();
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+            assert_eq!(read_file(&dir).unwrap(), keys);
+        }
+        // Writing an empty store removes the file.
+        write_file(&dir, &HashMap::new()).unwrap();
+        assert!(!dir.join("credentials.enc").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn tampered_or_rekeyed_store_is_rejected() {
+        let dir = temp_dir("tampered");
+        write_file(&dir, &HashMap::from([("a".to_string(), "b".to_string())])).unwrap();
+        let path = dir.join("credentials.enc");
+        let mut data = std::fs::read(&path).unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        std::fs::write(&path, &data).unwrap();
+        assert!(read_file(&dir).unwrap_err().contains("could not be decrypted"));
+
+        std::fs::write(dir.join("credentials.key"), b"short").unwrap();
+        assert!(read_file(&dir).unwrap_err().contains("corrupt"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,113 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Utc};
+use rusqlite::Connection;
+
+use crate::commands::settings::{load_settings, Settings};
+
+pub fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .ok()
+        .filter(|d| d.format("%Y-%m-%d").to_string() == date)
+        .ok_or_else(|| format!("Invalid date '{}': expected YYYY-MM-DD", date))
+}
+
+pub fn format_date(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+// The user's "today": wall-clock time in their timezone, shifted back by the
+// day boundary so that 1am still counts as yesterday for a 3am cutoff.
+pub fn local_today(conn: &Connection) -> Result<NaiveDate, String> {
+    Ok(today_for(&load_settings(conn)?, Utc::now()))
+}
+
+fn today_for(settings: &Settings, now: DateTime<Utc>) -> NaiveDate {
+    let wall = match settings
+        .timezone
+        .as_deref()
+        .and_then(|tz| tz.parse::<chrono_tz::Tz>().ok())
+    {
+        Some(tz) => now.with_timezone(&tz).naive_local(),
+        None => now.with_timezone(&Local).naive_local(),
+    };
+    (wall - Duration::hours(settings.day_end_hour as i64)).date()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn settings(timezone: Option<&str>, day_end_hour: u32) -> Settings {
+        Settings {
+            timezone: timezone.map(str::to_string),
+            day_end_hour,
+            feedback_in_prompt: false,
+            redact_builtin: true,
+            language: "en".to_string(),
+            allow_file_credentials: false,
+        }
+    }
+
+    fn utc(y: i32, m: u32, d: u32, h: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn parse_date_is_strict() {
+        assert_eq!(parse_date("2024-02-29").unwrap(), NaiveDate::from_ymd_opt(2024, 2, 29).unwrap());
+        assert!(parse_date("2023-02-29").is_err());
+        assert!(parse_date("2024-2-9").is_err());
+        assert!(parse_date("2024-02-09T00:00").is_err());
+    }
+
+    #[test]
+    fn week_starts_on_monday() {
+        let sunday = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        assert_eq!(week_start(sunday), NaiveDate::from_ymd_opt(2024, 3, 4).unwrap());
+        assert_eq!(week_start(week_start(sunday)), week_start(sunday));
+    }
+
+    #[test]
+    fn today_follows_the_timezone() {
+        // 23:30 UTC is already the next day in Berlin and still the same day
+        // in Los Angeles.
+        let now = Utc.with_ymd_and_hms(2024, 3, 10, 23, 30, 0).unwrap();
+        assert_eq!(
+            today_for(&settings(Some("Europe/Berlin"), 0), now),
+            NaiveDate::from_ymd_opt(2024, 3, 11).unwrap()
+        );
+        assert_eq!(
+            today_for(&settings(Some("America/Los_Angeles"), 0), now),
+            NaiveDate::from_ymd_opt(2024, 3, 10).unwrap()
+        );
+    }
+
+    #[test]
+    fn today_rolls_over_at_the_day_end_hour() {
+        let tz = Some("UTC");
+        // 02:00 with a 3am cutoff still counts as the previous day.
+        assert_eq!(
+            today_for(&settings(tz, 3), utc(2024, 3, 1, 2)),
+            NaiveDate::from_ymd_opt(2024, 2, 29).unwrap()
+        );
+        assert_eq!(
+            today_for(&settings(tz, 3), utc(2024, 3, 1, 3)),
+            NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()
+        );
+        assert_eq!(
+            today_for(&settings(tz, 0), utc(2024, 3, 1, 0)),
+            NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()
+        );
+        // An unknown stored timezone falls back to the system clock rather
+        // than failing.
+        let now = utc(2024, 3, 1, 2);
+        assert_eq!(
+            today_for(&settings(Some("Not/AZone"), 3), now),
+            today_for(&settings(None, 3), now)
+        );
+    }
+}
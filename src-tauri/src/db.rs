@@ -15,6 +15,12 @@ pub fn init_db(app: AppHandle) -> Result<(), String> {
     let path = db_path(&app)?;
     let conn = Connection::open(path).map_err(|e| e.to_string())?;
     conn.pragma_update(None, "journal_mode", "WAL").map_err(|e| e.to_string())?;
+    migrate(&conn)
+}
+
+// Creates the schema and brings an older database up to date. Also used on
+// in-memory databases in tests.
+pub(crate) fn migrate(conn: &Connection) -> Result<(), String> {
     conn.execute_batch(
         r#"
         CREATE TABLE IF NOT EXISTS user (
@@ -23,19 +29,197 @@ pub fn init_db(app: AppHandle) -> Result<(), String> {
             created_at TEXT NOT NULL DEFAULT (datetime('now'))
         );
 
+        CREATE TABLE IF NOT EXISTS app_setting (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+
         CREATE TABLE IF NOT EXISTS identity (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             name TEXT NOT NULL,
             description TEXT NOT NULL DEFAULT '',
             user_id INTEGER NOT NULL,
+            prompt_template_id INTEGER REFERENCES prompt_template(id) ON DELETE SET NULL,
+            language TEXT,
             created_at TEXT NOT NULL DEFAULT (datetime('now')),
             FOREIGN KEY (user_id) REFERENCES user(id)
         );
 
+        CREATE TABLE IF NOT EXISTS prompt_template (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            system_template TEXT NOT NULL,
+            user_template TEXT NOT NULL,
+            version INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS prompt_template_version (
+            template_id INTEGER NOT NULL,
+            version INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            system_template TEXT NOT NULL,
+            user_template TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            PRIMARY KEY (template_id, version)
+        );
+
+        CREATE TABLE IF NOT EXISTS reflection_feedback (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            reflection_id INTEGER NOT NULL UNIQUE,
+            rating INTEGER CHECK (rating BETWEEN 1 AND 5),
+            comment TEXT,
+            observations TEXT NOT NULL DEFAULT '[]',
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (reflection_id) REFERENCES daily_reflection(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS commitment (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            identity_id INTEGER NOT NULL,
+            reflection_id INTEGER,
+            text TEXT NOT NULL,
+            due_date TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'kept', 'broken')),
+            note TEXT,
+            resolved_at TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (identity_id) REFERENCES identity(id) ON DELETE CASCADE,
+            FOREIGN KEY (reflection_id) REFERENCES daily_reflection(id) ON DELETE SET NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_commitment_identity ON commitment(identity_id, due_date);
+
+        CREATE TABLE IF NOT EXISTS commitment_behavior (
+            commitment_id INTEGER NOT NULL,
+            behavior_id INTEGER NOT NULL,
+            PRIMARY KEY (commitment_id, behavior_id),
+            FOREIGN KEY (commitment_id) REFERENCES commitment(id) ON DELETE CASCADE,
+            FOREIGN KEY (behavior_id) REFERENCES behavior_log(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS planned_action (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            identity_id INTEGER NOT NULL,
+            date TEXT NOT NULL,
+            description TEXT NOT NULL,
+            reason TEXT,
+            expected_score INTEGER CHECK (expected_score BETWEEN 1 AND 10),
+            status TEXT NOT NULL DEFAULT 'planned' CHECK (status IN ('planned', 'done', 'skipped')),
+            behavior_id INTEGER,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (identity_id) REFERENCES identity(id) ON DELETE CASCADE,
+            FOREIGN KEY (behavior_id) REFERENCES behavior_log(id) ON DELETE SET NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_planned_action ON planned_action(identity_id, date);
+
+        CREATE TABLE IF NOT EXISTS planned_action_trait (
+            planned_action_id INTEGER NOT NULL,
+            trait_id INTEGER NOT NULL,
+            PRIMARY KEY (planned_action_id, trait_id),
+            FOREIGN KEY (planned_action_id) REFERENCES planned_action(id) ON DELETE CASCADE,
+            FOREIGN KEY (trait_id) REFERENCES trait(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS quick_log_preset (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            identity_id INTEGER NOT NULL,
+            description TEXT NOT NULL,
+            alignment_score INTEGER NOT NULL CHECK (alignment_score BETWEEN 1 AND 10),
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (identity_id) REFERENCES identity(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS quick_log_preset_trait (
+            preset_id INTEGER NOT NULL,
+            trait_id INTEGER NOT NULL,
+            PRIMARY KEY (preset_id, trait_id),
+            FOREIGN KEY (preset_id) REFERENCES quick_log_preset(id) ON DELETE CASCADE,
+            FOREIGN KEY (trait_id) REFERENCES trait(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS reflection_message (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            reflection_id INTEGER NOT NULL,
+            role TEXT NOT NULL CHECK (role IN ('user', 'assistant')),
+            content TEXT NOT NULL,
+            model TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (reflection_id) REFERENCES daily_reflection(id) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_reflection_message ON reflection_message(reflection_id);
+
+        CREATE TABLE IF NOT EXISTS reflection_queue (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            identity_id INTEGER NOT NULL,
+            date TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending',
+            attempts INTEGER NOT NULL DEFAULT 0,
+            last_error TEXT,
+            reflection_id INTEGER,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (identity_id) REFERENCES identity(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS ai_usage (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            date TEXT NOT NULL,
+            provider TEXT NOT NULL,
+            model TEXT NOT NULL,
+            prompt_tokens INTEGER,
+            completion_tokens INTEGER,
+            latency_ms INTEGER NOT NULL,
+            cost REAL,
+            error TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE INDEX IF NOT EXISTS idx_ai_usage_date ON ai_usage(date);
+
+        CREATE TABLE IF NOT EXISTS redaction_rule (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL CHECK (kind IN ('term', 'pattern')),
+            label TEXT NOT NULL,
+            value TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE (kind, label, value)
+        );
+
+        CREATE TABLE IF NOT EXISTS ai_price (
+            model TEXT PRIMARY KEY,
+            prompt_per_million REAL NOT NULL,
+            completion_per_million REAL NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS llm_config (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            identity_id INTEGER UNIQUE,
+            provider TEXT NOT NULL,
+            model TEXT NOT NULL,
+            temperature REAL NOT NULL,
+            base_url TEXT,
+            FOREIGN KEY (identity_id) REFERENCES identity(id) ON DELETE CASCADE
+        );
+
         CREATE TABLE IF NOT EXISTS trait (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             name TEXT NOT NULL,
             identity_id INTEGER NOT NULL,
+            definition TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (identity_id) REFERENCES identity(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS metric (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            unit TEXT NOT NULL,
+            target REAL NOT NULL CHECK (target > 0),
+            period TEXT NOT NULL CHECK (period IN ('daily', 'weekly')),
+            identity_id INTEGER NOT NULL,
             created_at TEXT NOT NULL DEFAULT (datetime('now')),
             FOREIGN KEY (identity_id) REFERENCES identity(id) ON DELETE CASCADE
         );
@@ -46,17 +230,62 @@ pub fn init_db(app: AppHandle) -> Result<(), String> {
             description TEXT NOT NULL,
             identity_id INTEGER NOT NULL,
             alignment_score INTEGER NOT NULL CHECK (alignment_score >= 1 AND alignment_score <= 10),
+            suggested_score INTEGER CHECK (suggested_score >= 1 AND suggested_score <= 10),
+            value REAL,
+            unit TEXT,
             created_at TEXT NOT NULL DEFAULT (datetime('now')),
             FOREIGN KEY (identity_id) REFERENCES identity(id) ON DELETE CASCADE
         );
 
+        CREATE TABLE IF NOT EXISTS behavior_trait (
+            behavior_id INTEGER NOT NULL,
+            trait_id INTEGER NOT NULL,
+            PRIMARY KEY (behavior_id, trait_id),
+            FOREIGN KEY (behavior_id) REFERENCES behavior_log(id) ON DELETE CASCADE,
+            FOREIGN KEY (trait_id) REFERENCES trait(id) ON DELETE CASCADE
+        );
+
         CREATE TABLE IF NOT EXISTS daily_reflection (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             date TEXT NOT NULL,
             content TEXT NOT NULL,
             identity_id INTEGER NOT NULL,
+            title TEXT,
+            alignment_summary TEXT,
+            observations TEXT,
+            identity_correction TEXT,
+            closing_statement TEXT,
+            version INTEGER NOT NULL DEFAULT 1,
+            model TEXT,
+            prompt_hash TEXT,
+            pinned INTEGER NOT NULL DEFAULT 0,
+            follow_up_status TEXT,
+            follow_up_note TEXT,
+            prompt_template_id INTEGER,
+            prompt_template_version INTEGER,
+            local INTEGER NOT NULL DEFAULT 0,
+            fallback_reason TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (identity_id) REFERENCES identity(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS period_reflection (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            identity_id INTEGER NOT NULL,
+            period TEXT NOT NULL CHECK (period IN ('week', 'month')),
+            start_date TEXT NOT NULL,
+            end_date TEXT NOT NULL,
+            content TEXT NOT NULL,
+            title TEXT NOT NULL,
+            wins TEXT NOT NULL,
+            misalignments TEXT NOT NULL,
+            trend TEXT NOT NULL,
+            trend_summary TEXT NOT NULL,
+            focus TEXT NOT NULL,
+            average_alignment REAL,
+            model TEXT,
+            prompt_hash TEXT,
             created_at TEXT NOT NULL DEFAULT (datetime('now')),
-            UNIQUE(date, identity_id),
             FOREIGN KEY (identity_id) REFERENCES identity(id) ON DELETE CASCADE
         );
 
@@ -64,9 +293,181 @@ pub fn init_db(app: AppHandle) -> Result<(), String> {
         CREATE INDEX IF NOT EXISTS idx_behavior_identity ON behavior_log(identity_id);
         CREATE INDEX IF NOT EXISTS idx_reflection_date ON daily_reflection(date);
         CREATE INDEX IF NOT EXISTS idx_reflection_identity ON daily_reflection(identity_id);
+        CREATE INDEX IF NOT EXISTS idx_period_reflection ON period_reflection(identity_id, period, start_date);
         "#,
     )
     .map_err(|e| e.to_string())?;
+
+    add_column_if_missing(
+        conn,
+        "behavior_log",
+        "suggested_score",
+        "INTEGER CHECK (suggested_score >= 1 AND suggested_score <= 10)",
+    )?;
+    add_column_if_missing(conn, "behavior_log", "value", "REAL")?;
+    add_column_if_missing(conn, "trait", "definition", "TEXT")?;
+    add_column_if_missing(conn, "behavior_log", "unit", "TEXT")?;
+    // Observations are stored as a JSON array of strings.
+    for column in [
+        "title",
+        "alignment_summary",
+        "observations",
+        "identity_correction",
+        "closing_statement",
+    ] {
+        add_column_if_missing(conn, "daily_reflection", column, "TEXT")?;
+    }
+    add_column_if_missing(conn, "daily_reflection", "version", "INTEGER NOT NULL DEFAULT 1")?;
+    add_column_if_missing(conn, "daily_reflection", "model", "TEXT")?;
+    add_column_if_missing(conn, "daily_reflection", "prompt_hash", "TEXT")?;
+    add_column_if_missing(conn, "daily_reflection", "pinned", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "daily_reflection", "follow_up_status", "TEXT")?;
+    add_column_if_missing(conn, "daily_reflection", "follow_up_note", "TEXT")?;
+    add_column_if_missing(conn, "daily_reflection", "prompt_template_id", "INTEGER")?;
+    add_column_if_missing(conn, "daily_reflection", "prompt_template_version", "INTEGER")?;
+    add_column_if_missing(conn, "daily_reflection", "local", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "daily_reflection", "fallback_reason", "TEXT")?;
+    add_column_if_missing(
+        conn,
+        "identity",
+        "prompt_template_id",
+        "INTEGER REFERENCES prompt_template(id) ON DELETE SET NULL",
+    )?;
+    add_column_if_missing(conn, "identity", "language", "TEXT")?;
+    crate::commands::prompt_template::seed_personas(conn)?;
+    // Templates from before versions were kept start their history at their
+    // current version.
+    conn.execute(
+        "INSERT OR IGNORE INTO prompt_template_version (template_id, version, name, system_template, \
+         user_template, created_at) SELECT id, version, name, system_template, user_template, updated_at \
+         FROM prompt_template",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    crate::llm::usage::seed_prices(conn)?;
+    drop_reflection_day_unique(conn)?;
+    // At most one pinned version per day and identity.
+    conn.execute_batch(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_reflection_pinned ON daily_reflection(date, identity_id) WHERE pinned = 1",
+    )
+    .map_err(|e| e.to_string())?;
+    unique_metric_units(conn)?;
+    Ok(())
+}
+
+// Behaviors count toward a metric by unit, so an identity may have only one
+// metric per unit. Databases that already hold two keep working without the
+// index; create_metric refuses new duplicates either way.
+fn unique_metric_units(conn: &Connection) -> Result<(), String> {
+    let duplicates: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM (SELECT 1 FROM metric GROUP BY identity_id, unit COLLATE NOCASE HAVING COUNT(*) > 1)",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if duplicates == 0 {
+        conn.execute_batch(
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_metric_unit ON metric(identity_id, unit COLLATE NOCASE)",
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// A migrated in-memory database with one user and two identities (ids 1 and
+// 2), for tests.
+#[cfg(test)]
+pub(crate) fn test_conn() -> Connection {
+    let conn = Connection::open_in_memory().expect("in-memory database");
+    migrate(&conn).expect("migrate");
+    conn.execute_batch(
+        "INSERT INTO user (id, name) VALUES (1, 'Sam');
+         INSERT INTO identity (id, name, user_id) VALUES (1, 'a writer', 1), (2, 'a runner', 1);",
+    )
+    .expect("fixtures");
+    conn
+}
+
+// Databases created before reflections were versioned have UNIQUE(date,
+// identity_id) on daily_reflection. SQLite cannot drop a table constraint, so
+// the table is rebuilt without it.
+fn drop_reflection_day_unique(conn: &Connection) -> Result<(), String> {
+    let sql: String = conn
+        .query_row(
+            "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'daily_reflection'",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if !sql.contains("UNIQUE(date, identity_id)") {
+        return Ok(());
+    }
+    let columns = "id, date, content, identity_id, title, alignment_summary, observations, \
+                   identity_correction, closing_statement, version, model, prompt_hash, pinned, \
+                   follow_up_status, follow_up_note, prompt_template_id, prompt_template_version, \
+                   local, fallback_reason, created_at";
+    conn.execute_batch(&format!(
+        r#"
+        BEGIN;
+        CREATE TABLE daily_reflection_new (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            date TEXT NOT NULL,
+            content TEXT NOT NULL,
+            identity_id INTEGER NOT NULL,
+            title TEXT,
+            alignment_summary TEXT,
+            observations TEXT,
+            identity_correction TEXT,
+            closing_statement TEXT,
+            version INTEGER NOT NULL DEFAULT 1,
+            model TEXT,
+            prompt_hash TEXT,
+            pinned INTEGER NOT NULL DEFAULT 0,
+            follow_up_status TEXT,
+            follow_up_note TEXT,
+            prompt_template_id INTEGER,
+            prompt_template_version INTEGER,
+            local INTEGER NOT NULL DEFAULT 0,
+            fallback_reason TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (identity_id) REFERENCES identity(id) ON DELETE CASCADE
+        );
+        INSERT INTO daily_reflection_new ({0}) SELECT {0} FROM daily_reflection;
+        DROP TABLE daily_reflection;
+        ALTER TABLE daily_reflection_new RENAME TO daily_reflection;
+        CREATE INDEX IF NOT EXISTS idx_reflection_date ON daily_reflection(date);
+        CREATE INDEX IF NOT EXISTS idx_reflection_identity ON daily_reflection(identity_id);
+        COMMIT;
+        "#,
+        columns
+    ))
+    .map_err(|e| e.to_string())
+}
+
+// CREATE TABLE IF NOT EXISTS leaves tables from older versions untouched, so
+// columns added after the first release are patched in here.
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare(&format!("PRAGMA table_info({})", table))
+        .map_err(|e| e.to_string())?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|e| e.to_string())?
+        .filter_map(Result::ok)
+        .any(|name| name == column);
+    if !exists {
+        conn.execute_batch(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table, column, definition
+        ))
+        .map_err(|e| e.to_string())?;
+    }
     Ok(())
 }
 
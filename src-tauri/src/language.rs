@@ -0,0 +1,172 @@
+use rusqlite::Connection;
+
+use crate::commands::identity::Identity;
+use crate::commands::settings::load_settings;
+
+pub const DEFAULT: &str = "en";
+
+// Output languages reflections can be written in, by ISO 639-1 code.
+pub const LANGUAGES: [(&str, &str); 7] = [
+    ("en", "English"),
+    ("de", "German"),
+    ("es", "Spanish"),
+    ("fr", "French"),
+    ("it", "Italian"),
+    ("pt", "Portuguese"),
+    ("nl", "Dutch"),
+];
+
+// Frequent function words per language, for telling which language a reply
+// was written in. Words shared between languages count for each of them.
+const STOPWORDS: [(&str, &[&str]); 7] = [
+    (
+        "en",
+        &[
+            "the", "and", "is", "are", "was", "you", "your", "of", "to", "with", "that", "this", "it", "for",
+            "not", "but", "on", "be", "have", "today",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "und", "der", "die", "das", "ist", "nicht", "mit", "ich", "du", "dein", "deine", "ein", "eine",
+            "auf", "für", "zu", "sich", "heute", "aber", "wie",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "el", "la", "los", "las", "y", "que", "es", "con", "por", "para", "una", "tu", "tus", "del", "pero",
+            "hoy", "no", "se", "lo", "más",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "le", "la", "les", "et", "est", "des", "une", "pour", "pas", "que", "qui", "dans", "avec", "tu",
+            "ton", "ta", "vous", "aujourd", "sur", "du",
+        ],
+    ),
+    (
+        "it",
+        &[
+            "il", "lo", "gli", "e", "è", "che", "non", "per", "una", "con", "di", "del", "della", "oggi", "ma",
+            "sei", "tuo", "tua", "più", "come",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            "o", "os", "as", "e", "é", "que", "não", "para", "com", "uma", "um", "do", "da", "dos", "hoje",
+            "mas", "seu", "sua", "você", "mais",
+        ],
+    ),
+    (
+        "nl",
+        &[
+            "het", "een", "en", "is", "van", "niet", "met", "voor", "op", "je", "jouw", "dat", "die", "maar",
+            "vandaag", "ook", "zijn", "naar", "te", "wat",
+        ],
+    ),
+];
+
+// Fewer stopword hits than this is too little text to judge.
+const MIN_HITS: usize = 8;
+
+pub fn name(code: &str) -> Option<&'static str> {
+    LANGUAGES.iter().find(|(c, _)| *c == code).map(|(_, n)| *n)
+}
+
+pub fn check(code: &str) -> Result<(), String> {
+    match name(code) {
+        Some(_) => Ok(()),
+        None => Err(format!(
+            "Unsupported language '{}'; expected one of {}",
+            code,
+            LANGUAGES.iter().map(|(c, _)| *c).collect::<Vec<_>>().join(", ")
+        )),
+    }
+}
+
+// The identity's own language, otherwise the global setting.
+pub fn for_identity(conn: &Connection, identity: &Identity) -> Result<String, String> {
+    match &identity.language {
+        Some(code) => Ok(code.clone()),
+        None => Ok(load_settings(conn)?.language),
+    }
+}
+
+// Appended to system prompts, after the response format where there is one.
+pub fn instruction(code: &str) -> String {
+    format!(
+        "Write in {}, even when the behaviors or earlier notes are written in another language.",
+        name(code).unwrap_or("English")
+    )
+}
+
+// For prompts that ask for JSON: the text is translated, the structure not.
+pub const KEEP_KEYS: &str = "Keep the JSON keys and enum values exactly as given, in English.";
+
+// The language the text is most likely written in, or None when there is too
+// little text or no clear winner.
+pub fn detect(text: &str) -> Option<&'static str> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect();
+    let mut scores: Vec<(&'static str, usize)> = STOPWORDS
+        .iter()
+        .map(|(code, list)| (*code, words.iter().filter(|w| list.contains(&w.as_str())).count()))
+        .collect();
+    scores.sort_by_key(|s| std::cmp::Reverse(s.1));
+    let (best, hits) = scores[0];
+    if hits < MIN_HITS || hits < scores[1].1 * 2 {
+        return None;
+    }
+    Some(best)
+}
+
+// Rejects output clearly written in a language other than `code`. Text that
+// cannot be judged passes.
+pub fn validate(code: &str, texts: &[&str]) -> Result<(), String> {
+    match detect(&texts.join("\n")) {
+        Some(found) if found != code => Err(format!(
+            "the text is written in {}, expected {}",
+            name(found).unwrap_or(found),
+            name(code).unwrap_or(code)
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EN: &str = "You kept your promise to write before checking email, and that is the habit of a \
+        writer. The afternoon was not as focused, but you still finished the chapter with time to spare.";
+    const DE: &str = "Du hast heute dein Versprechen gehalten und vor den E-Mails geschrieben. Das ist die \
+        Gewohnheit einer Autorin, aber der Nachmittag war nicht so konzentriert wie der Morgen.";
+    const ES: &str = "Hoy cumpliste tu promesa de escribir antes de revisar el correo, y eso es lo que hace \
+        una escritora. La tarde no fue tan enfocada, pero terminaste el capítulo con tiempo de sobra.";
+
+    #[test]
+    fn detects_languages() {
+        assert_eq!(detect(EN), Some("en"));
+        assert_eq!(detect(DE), Some("de"));
+        assert_eq!(detect(ES), Some("es"));
+        // Too short to judge.
+        assert_eq!(detect("Deep work: 2h"), None);
+    }
+
+    #[test]
+    fn validate_rejects_the_wrong_language_only() {
+        assert!(validate("de", &[DE]).is_ok());
+        assert!(validate("de", &["Deep work"]).is_ok());
+        // English behaviors quoted in a German reflection do not flip it.
+        assert!(validate("de", &[DE, "\"Wrote 500 words\""]).is_ok());
+        let err = validate("de", &[EN]).unwrap_err();
+        assert!(err.contains("English"), "{}", err);
+    }
+}
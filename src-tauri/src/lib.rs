@@ -1,5 +1,9 @@
 mod commands;
+mod credentials;
+mod dates;
 mod db;
+mod language;
+mod llm;
 
 use tauri::Manager;
 
@@ -7,12 +11,16 @@ use tauri::Manager;
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
+        .manage(commands::reflection::ReflectionJobs::default())
+        .manage(commands::backfill::BackfillJobs::default())
         .setup(|app| {
             let handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
-                if let Err(e) = db::init_db(handle) {
+                if let Err(e) = db::init_db(handle.clone()) {
                     eprintln!("DB init error: {}", e);
+                    return;
                 }
+                commands::reflection_queue::run(handle).await;
             });
             Ok(())
         })
@@ -23,17 +31,94 @@ pub fn run() {
             commands::identity::list_identities,
             commands::identity::get_identity,
             commands::identity::update_identity,
+            commands::identity::set_identity_language,
             commands::trait_::create_trait,
             commands::trait_::list_traits,
             commands::trait_::delete_trait,
+            commands::trait_::suggest_traits,
+            commands::trait_::accept_trait_suggestions,
+            commands::metric::create_metric,
+            commands::metric::list_metrics,
+            commands::metric::delete_metric,
             commands::behavior::log_behavior,
+            commands::behavior::log_behaviors,
+            commands::behavior::parse_behaviors,
             commands::behavior::get_behaviors_for_date,
             commands::behavior::list_behaviors_for_identity,
+            commands::behavior::suggest_alignment,
+            commands::import::import_behaviors,
             commands::reflection::generate_reflection,
+            commands::reflection::cancel_reflection,
             commands::reflection::get_reflection_for_date,
             commands::reflection::list_reflections,
+            commands::reflection::list_reflection_versions,
+            commands::reflection::pin_reflection,
+            commands::reflection::diff_reflections,
+            commands::reflection::preview_reflection_payload,
+            commands::reflection_chat::chat_with_reflection,
+            commands::reflection_chat::list_reflection_messages,
+            commands::reflection_chat::clear_reflection_messages,
+            commands::backfill::backfill_reflections,
+            commands::backfill::cancel_backfill,
+            commands::commitment::create_commitment,
+            commands::commitment::create_commitment_from_reflection,
+            commands::commitment::list_commitments,
+            commands::commitment::resolve_commitment,
+            commands::commitment::link_commitment_behaviors,
+            commands::commitment::delete_commitment,
+            commands::action::suggest_actions,
+            commands::action::plan_action,
+            commands::action::list_planned_actions,
+            commands::action::complete_planned_action,
+            commands::action::set_planned_action_status,
+            commands::action::delete_planned_action,
+            commands::preset::create_quick_log_preset,
+            commands::preset::list_quick_log_presets,
+            commands::preset::delete_quick_log_preset,
+            commands::preset::log_quick_log_preset,
+            commands::reflection_queue::get_reflection_queue,
+            commands::reflection_queue::process_reflection_queue,
+            commands::reflection_queue::retry_queued_reflection,
+            commands::reflection_queue::remove_queued_reflection,
+            commands::usage::get_ai_usage,
+            commands::usage::list_ai_prices,
+            commands::usage::set_ai_price,
+            commands::usage::delete_ai_price,
+            commands::usage::set_ai_budget,
+            commands::feedback::set_reflection_feedback,
+            commands::feedback::get_reflection_feedback,
+            commands::feedback::delete_reflection_feedback,
+            commands::feedback::list_reflection_feedback,
+            commands::prompt_template::list_prompt_templates,
+            commands::prompt_template::get_default_prompt_template,
+            commands::prompt_template::create_prompt_template,
+            commands::prompt_template::update_prompt_template,
+            commands::prompt_template::get_prompt_template_version,
+            commands::prompt_template::delete_prompt_template,
+            commands::prompt_template::preview_prompt_template,
+            commands::prompt_template::set_identity_prompt_template,
+            commands::redaction::list_redaction_rules,
+            commands::redaction::add_redaction_rule,
+            commands::redaction::delete_redaction_rule,
+            commands::period_reflection::generate_period_reflection,
+            commands::period_reflection::get_period_reflection,
+            commands::period_reflection::list_period_reflections,
+            commands::analytics::get_today,
             commands::analytics::get_weekly_alignment,
             commands::analytics::get_alignment_trends,
+            commands::analytics::get_follow_up_stats,
+            commands::analytics::get_commitment_stats,
+            commands::settings::get_settings,
+            commands::settings::list_languages,
+            commands::settings::update_settings,
+            commands::settings::get_llm_config,
+            commands::settings::get_effective_llm_config,
+            commands::settings::set_llm_config,
+            commands::settings::clear_llm_config,
+            commands::credentials::set_api_key,
+            commands::credentials::get_api_key_status,
+            commands::credentials::delete_api_key,
+            commands::credentials::test_ai_connection,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
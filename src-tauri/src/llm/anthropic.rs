@@ -0,0 +1,308 @@
+use async_trait::async_trait;
+
+use super::{http, sse, ChatRequest, Completion, LlmConfig, ReflectionProvider, Usage};
+
+pub const DEFAULT_BASE_URL: &str = "https://api.anthropic.com";
+const API_VERSION: &str = "2023-06-01";
+const MAX_TOKENS: u32 = 2048;
+
+pub struct AnthropicProvider {
+    base_url: String,
+    api_key: String,
+    model: String,
+    temperature: f64,
+}
+
+impl AnthropicProvider {
+    fn body(&self, request: &ChatRequest) -> serde_json::Value {
+        // The Messages API caps temperature at 1.0.
+        let temperature = request.temperature.unwrap_or(self.temperature).min(1.0);
+        let mut body = serde_json::json!({
+            "model": self.model,
+            "max_tokens": MAX_TOKENS,
+            "system": request.system,
+            "messages": request.messages,
+            "temperature": temperature
+        });
+        // Anthropic has no JSON mode; forcing a single tool call whose input
+        // schema is the requested one gives the same guarantee.
+        if let Some(schema) = &request.schema {
+            body["tools"] = serde_json::json!([{
+                "name": schema.name,
+                "description": "Record the structured response.",
+                "input_schema": schema.schema
+            }]);
+            body["tool_choice"] = serde_json::json!({ "type": "tool", "name": schema.name });
+        }
+        body
+    }
+
+    async fn send(&self, body: &serde_json::Value) -> Result<reqwest::Response, String> {
+        let url = format!("{}/v1/messages", self.base_url);
+        let res = http::send_with_retry(|| {
+            http::client()
+                .post(&url)
+                .header("x-api-key", &self.api_key)
+                .header("anthropic-version", API_VERSION)
+                .header("Content-Type", "application/json")
+                .json(body)
+        })
+        .await?;
+
+        if !res.status().is_success() {
+            let err_text = res.text().await.unwrap_or_default();
+            return Err(format!("Anthropic API error: {}", err_text));
+        }
+        Ok(res)
+    }
+
+    fn usage(json: &serde_json::Value) -> Option<Usage> {
+        let usage = json.get("usage")?;
+        Some(Usage {
+            prompt_tokens: usage.get("input_tokens")?.as_i64()?,
+            completion_tokens: usage.get("output_tokens")?.as_i64()?,
+        })
+    }
+
+    pub fn new(base_url: &str, api_key: &str, config: &LlmConfig) -> Self {
+        AnthropicProvider {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: api_key.to_string(),
+            model: config.model.clone(),
+            temperature: config.temperature,
+        }
+    }
+}
+
+#[async_trait]
+impl ReflectionProvider for AnthropicProvider {
+    async fn complete(&self, request: &ChatRequest) -> Result<Completion, String> {
+        let res = self.send(&self.body(request)).await?;
+        let json: serde_json::Value = res.json().await.map_err(|e| e.to_string())?;
+        let usage = Self::usage(&json);
+        if request.schema.is_some() {
+            if let Some(input) = json
+                .get("content")
+                .and_then(|c| c.as_array())
+                .and_then(|blocks| {
+                    blocks
+                        .iter()
+                        .find(|b| b.get("type").and_then(|t| t.as_str()) == Some("tool_use"))
+                })
+                .and_then(|b| b.get("input"))
+            {
+                return Ok(Completion {
+                    text: input.to_string(),
+                    usage,
+                });
+            }
+        }
+        let text = json
+            .get("content")
+            .and_then(|c| c.as_array())
+            .map(|blocks| {
+                blocks
+                    .iter()
+                    .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("text"))
+                    .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+                    .collect::<String>()
+            })
+            .filter(|t| !t.is_empty())
+            .ok_or("Invalid Anthropic response")?;
+        Ok(Completion { text, usage })
+    }
+
+    async fn stream(
+        &self,
+        request: &ChatRequest,
+        on_delta: &mut (dyn for<'s> FnMut(&'s str) + Send),
+    ) -> Result<Completion, String> {
+        let mut body = self.body(request);
+        body["stream"] = serde_json::Value::Bool(true);
+        let res = self.send(&body).await?;
+
+        // With a schema the answer arrives as tool input JSON rather than text.
+        let mut text = String::new();
+        let mut tool_input = String::new();
+        // Input tokens come with message_start, the output count with the
+        // closing message_delta.
+        let mut usage = Usage::default();
+        let mut reported = false;
+        sse::read_events(res, |data| {
+            let event: serde_json::Value =
+                serde_json::from_str(data).map_err(|_| "Invalid Anthropic stream event".to_string())?;
+            match event.get("type").and_then(|t| t.as_str()) {
+                Some("message_start") => {
+                    let counts = event.get("message").and_then(|m| m.get("usage"));
+                    if let Some(n) = counts.and_then(|u| u.get("input_tokens")).and_then(|n| n.as_i64()) {
+                        usage.prompt_tokens = n;
+                        reported = true;
+                    }
+                }
+                Some("message_delta") => {
+                    let counts = event.get("usage");
+                    if let Some(n) = counts.and_then(|u| u.get("output_tokens")).and_then(|n| n.as_i64()) {
+                        usage.completion_tokens = n;
+                        reported = true;
+                    }
+                }
+                Some("content_block_delta") => {
+                    let delta = event.get("delta");
+                    if let Some(t) = delta.and_then(|d| d.get("text")).and_then(|t| t.as_str()) {
+                        on_delta(t);
+                        text.push_str(t);
+                    }
+                    if let Some(j) = delta.and_then(|d| d.get("partial_json")).and_then(|j| j.as_str()) {
+                        on_delta(j);
+                        tool_input.push_str(j);
+                    }
+                }
+                Some("error") => {
+                    return Err(format!(
+                        "Anthropic API error: {}",
+                        event.get("error").map(|e| e.to_string()).unwrap_or_default()
+                    ))
+                }
+                _ => {}
+            }
+            Ok(())
+        })
+        .await?;
+        let usage = reported.then_some(usage);
+        if request.schema.is_some() && !tool_input.is_empty() {
+            return Ok(Completion {
+                text: tool_input,
+                usage,
+            });
+        }
+        if text.is_empty() {
+            return Err("Invalid Anthropic response".to_string());
+        }
+        Ok(Completion { text, usage })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::ProviderKind;
+    use mockito::Matcher;
+
+    fn config() -> LlmConfig {
+        LlmConfig {
+            provider: ProviderKind::Anthropic,
+            model: "claude-test".to_string(),
+            temperature: 0.7,
+            base_url: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn sends_messages_request_and_joins_text_blocks() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/v1/messages")
+            .match_header("x-api-key", "key")
+            .match_header("anthropic-version", API_VERSION)
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "model": "claude-test",
+                "system": "sys",
+                "messages": [{ "role": "user", "content": "hi" }]
+            })))
+            .with_body(
+                r#"{"content":[{"type":"text","text":"{\"a\":"},{"type":"text","text":"1}"}],"usage":{"input_tokens":3,"output_tokens":2}}"#,
+            )
+            .create_async()
+            .await;
+
+        let provider = AnthropicProvider::new(&server.url(), "key", &config());
+        let out = provider.complete(&ChatRequest::new("sys", "hi")).await.unwrap();
+
+        assert_eq!(out.text, r#"{"a":1}"#);
+        assert_eq!(
+            out.usage,
+            Some(Usage {
+                prompt_tokens: 3,
+                completion_tokens: 2
+            })
+        );
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn forces_tool_use_for_json_schema() {
+        let mut server = mockito::Server::new_async().await;
+        let schema = serde_json::json!({ "type": "object" });
+        let mock = server
+            .mock("POST", "/v1/messages")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "tools": [{ "name": "thing", "input_schema": schema }],
+                "tool_choice": { "type": "tool", "name": "thing" }
+            })))
+            .with_body(r#"{"content":[{"type":"tool_use","name":"thing","input":{"a":1}}]}"#)
+            .create_async()
+            .await;
+
+        let provider = AnthropicProvider::new(&server.url(), "key", &config());
+        let request = ChatRequest::new("sys", "hi").json_schema("thing", schema);
+        assert_eq!(provider.complete(&request).await.unwrap().text, r#"{"a":1}"#);
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn streams_tool_input_deltas() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/v1/messages")
+            .match_body(Matcher::PartialJson(serde_json::json!({ "stream": true })))
+            .with_header("content-type", "text/event-stream")
+            .with_body(concat!(
+                "event: message_start\n",
+                "data: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":9,\"output_tokens\":1}}}\n\n",
+                "event: content_block_start\n",
+                "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"tool_use\",\"input\":{}}}\n\n",
+                "event: content_block_delta\n",
+                "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{\\\"a\\\":\"}}\n\n",
+                "event: content_block_delta\n",
+                "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"1}\"}}\n\n",
+                "event: message_delta\n",
+                "data: {\"type\":\"message_delta\",\"usage\":{\"output_tokens\":4}}\n\n",
+                "event: message_stop\n",
+                "data: {\"type\":\"message_stop\"}\n\n"
+            ))
+            .create_async()
+            .await;
+
+        let provider = AnthropicProvider::new(&server.url(), "key", &config());
+        let request = ChatRequest::new("sys", "hi").json_schema("thing", serde_json::json!({ "type": "object" }));
+        let mut deltas = 0;
+        let out = provider.stream(&request, &mut |_| deltas += 1).await.unwrap();
+
+        assert_eq!(out.text, r#"{"a":1}"#);
+        assert_eq!(
+            out.usage,
+            Some(Usage {
+                prompt_tokens: 9,
+                completion_tokens: 4
+            })
+        );
+        assert_eq!(deltas, 2);
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn clamps_temperature_to_api_maximum() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/v1/messages")
+            .match_body(Matcher::PartialJson(serde_json::json!({ "temperature": 1.0 })))
+            .with_body(r#"{"content":[{"type":"text","text":"ok"}]}"#)
+            .create_async()
+            .await;
+
+        let provider = AnthropicProvider::new(&server.url(), "key", &config());
+        let request = ChatRequest::new("sys", "hi").temperature(1.5);
+        assert_eq!(provider.complete(&request).await.unwrap().text, "ok");
+        mock.assert_async().await;
+    }
+}
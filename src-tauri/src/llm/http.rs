@@ -0,0 +1,163 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use reqwest::{RequestBuilder, Response, StatusCode};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+// Longest silence between bytes; a streamed reply may take minutes in total.
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_RETRIES: u32 = 3;
+const BASE_DELAY: Duration = Duration::from_secs(1);
+// A server asking to wait longer than this gets its error returned instead.
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+// Prefix of errors caused by the network rather than the provider, so the
+// work can be queued until connectivity returns.
+pub const OFFLINE: &str = "Network unavailable";
+
+pub fn is_offline(error: &str) -> bool {
+    error.starts_with(OFFLINE)
+}
+
+// One client for every provider call, so connections are reused.
+pub fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .read_timeout(READ_TIMEOUT)
+            .build()
+            .expect("HTTP client with timeouts could not be built")
+    })
+}
+
+// Rate limits and overloaded or failing upstreams; 529 is Anthropic's
+// "overloaded".
+fn retryable(status: StatusCode) -> bool {
+    matches!(status.as_u16(), 429 | 500 | 502 | 503 | 504 | 529)
+}
+
+fn retry_after(res: &Response) -> Option<Duration> {
+    parse_retry_after(res.headers().get("retry-after")?.to_str().ok()?, Utc::now())
+}
+
+// Retry-After is either a number of seconds or an HTTP date; a date already
+// passed means retry now.
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?;
+    Some((at.with_timezone(&Utc) - now).to_std().unwrap_or(Duration::ZERO))
+}
+
+// Whether `e` means the connection could not be made or was lost, as
+// opposed to a slow server or a bad reply.
+pub(crate) fn is_connection_failure(e: &reqwest::Error) -> bool {
+    if e.is_connect() {
+        return true;
+    }
+    let mut source = std::error::Error::source(e);
+    while let Some(err) = source {
+        if let Some(io) = err.downcast_ref::<std::io::Error>() {
+            use std::io::ErrorKind::*;
+            return matches!(
+                io.kind(),
+                ConnectionReset | ConnectionAborted | NotConnected | BrokenPipe | UnexpectedEof
+            );
+        }
+        source = err.source();
+    }
+    false
+}
+
+fn backoff(attempt: u32) -> Duration {
+    (BASE_DELAY * 2u32.saturating_pow(attempt)).min(MAX_DELAY)
+}
+
+// Sends the request `build` makes, retrying retryable statuses and failed
+// connections with exponential backoff, or after Retry-After when the server
+// sends it. Once retries run out the last response is returned for the
+// caller to report; connection failures come back prefixed with OFFLINE.
+// A timeout means the server was reached, so it is returned as is.
+pub async fn send_with_retry(build: impl Fn() -> RequestBuilder) -> Result<Response, String> {
+    let mut attempt = 0;
+    loop {
+        let delay = match build().send().await {
+            Ok(res) if retryable(res.status()) && attempt < MAX_RETRIES => match retry_after(&res) {
+                Some(delay) if delay > MAX_DELAY => return Ok(res),
+                Some(delay) => delay,
+                None => backoff(attempt),
+            },
+            Ok(res) => return Ok(res),
+            Err(e) if e.is_connect() => {
+                if attempt >= MAX_RETRIES {
+                    return Err(format!("{}: {}", OFFLINE, e));
+                }
+                backoff(attempt)
+            }
+            Err(e) => return Err(e.to_string()),
+        };
+        attempt += 1;
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn retries_after_retry_after_then_returns_last_response() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/x")
+            .with_status(429)
+            .with_header("retry-after", "0")
+            .expect(MAX_RETRIES as usize + 1)
+            .create_async()
+            .await;
+
+        let url = format!("{}/x", server.url());
+        let res = send_with_retry(|| client().post(&url)).await.unwrap();
+
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors_or_long_waits() {
+        let mut server = mockito::Server::new_async().await;
+        let bad = server.mock("POST", "/bad").with_status(400).expect(1).create_async().await;
+        let busy = server
+            .mock("POST", "/busy")
+            .with_status(503)
+            .with_header("retry-after", "3600")
+            .expect(1)
+            .create_async()
+            .await;
+
+        let bad_url = format!("{}/bad", server.url());
+        let busy_url = format!("{}/busy", server.url());
+        assert_eq!(send_with_retry(|| client().post(&bad_url)).await.unwrap().status(), 400);
+        assert_eq!(send_with_retry(|| client().post(&busy_url)).await.unwrap().status(), 503);
+        bad.assert_async().await;
+        busy.assert_async().await;
+    }
+
+    #[test]
+    fn retry_after_accepts_seconds_and_http_dates() {
+        let now = DateTime::parse_from_rfc2822("Wed, 21 Oct 2015 07:28:00 GMT")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(parse_retry_after(" 120 ", now), Some(Duration::from_secs(120)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+}
@@ -0,0 +1,431 @@
+mod anthropic;
+pub mod http;
+mod openai;
+pub mod redact;
+mod sse;
+pub mod usage;
+
+use async_trait::async_trait;
+use rusqlite::{Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+
+use crate::credentials;
+
+pub use anthropic::AnthropicProvider;
+pub use openai::OpenAiProvider;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProviderKind {
+    #[serde(rename = "openai")]
+    OpenAi,
+    // Any server speaking the OpenAI chat completions API (Ollama, llama.cpp, ...).
+    #[serde(rename = "openai_compatible")]
+    OpenAiCompatible,
+    #[serde(rename = "anthropic")]
+    Anthropic,
+}
+
+impl ProviderKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProviderKind::OpenAi => "openai",
+            ProviderKind::OpenAiCompatible => "openai_compatible",
+            ProviderKind::Anthropic => "anthropic",
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "openai" => Ok(ProviderKind::OpenAi),
+            "openai_compatible" => Ok(ProviderKind::OpenAiCompatible),
+            "anthropic" => Ok(ProviderKind::Anthropic),
+            other => Err(format!("Unknown AI provider: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmConfig {
+    pub provider: ProviderKind,
+    pub model: String,
+    pub temperature: f64,
+    pub base_url: Option<String>,
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        LlmConfig {
+            provider: ProviderKind::OpenAi,
+            model: "gpt-4o-mini".to_string(),
+            temperature: 0.7,
+            base_url: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn user(content: impl Into<String>) -> Self {
+        ChatMessage {
+            role: "user".to_string(),
+            content: content.into(),
+        }
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        ChatMessage {
+            role: "assistant".to_string(),
+            content: content.into(),
+        }
+    }
+}
+
+// Asks the provider for output matching a JSON schema, using its native
+// structured output mode (OpenAI response_format, Anthropic forced tool use).
+#[derive(Debug, Clone)]
+pub struct JsonSchema {
+    pub name: String,
+    pub schema: serde_json::Value,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChatRequest {
+    pub system: String,
+    pub messages: Vec<ChatMessage>,
+    // Overrides the configured temperature, e.g. for scoring calls that
+    // should be close to deterministic.
+    pub temperature: Option<f64>,
+    pub schema: Option<JsonSchema>,
+}
+
+impl ChatRequest {
+    pub fn new(system: impl Into<String>, user: impl Into<String>) -> Self {
+        ChatRequest {
+            system: system.into(),
+            messages: vec![ChatMessage::user(user)],
+            temperature: None,
+            schema: None,
+        }
+    }
+
+    pub fn temperature(mut self, temperature: f64) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    pub fn json_schema(mut self, name: &str, schema: serde_json::Value) -> Self {
+        self.schema = Some(JsonSchema {
+            name: name.to_string(),
+            schema,
+        });
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Usage {
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+}
+
+// A reply and the tokens it used, when the provider reports them.
+#[derive(Debug, Clone)]
+pub struct Completion {
+    pub text: String,
+    pub usage: Option<Usage>,
+}
+
+#[async_trait]
+pub trait ReflectionProvider: Send + Sync {
+    async fn complete(&self, request: &ChatRequest) -> Result<Completion, String>;
+
+    // Streams the reply, passing each text fragment to `on_delta` as it
+    // arrives, and returns the full reply. Providers without streaming
+    // deliver it as one fragment.
+    async fn stream(
+        &self,
+        request: &ChatRequest,
+        on_delta: &mut (dyn for<'s> FnMut(&'s str) + Send),
+    ) -> Result<Completion, String> {
+        let completion = self.complete(request).await?;
+        on_delta(&completion.text);
+        Ok(completion)
+    }
+}
+
+// Runs a structured request and parses the reply into `T`. If the reply does
+// not parse or fails `validate`, the model gets one repair attempt with the
+// error before giving up. Returns the value and the raw JSON it came from.
+// Fragments are streamed to `on_delta` along with the attempt number (1 or
+// 2), so a listener can discard the rejected first reply.
+pub async fn complete_json<T, F>(
+    provider: &dyn ReflectionProvider,
+    request: &ChatRequest,
+    validate: F,
+    on_delta: &mut (dyn FnMut(u32, &str) + Send),
+) -> Result<(T, String), String>
+where
+    T: DeserializeOwned,
+    F: Fn(&T) -> Result<(), String>,
+{
+    let raw = provider.stream(request, &mut |d| on_delta(1, d)).await?.text;
+    let error = match parse_json::<T>(&raw).and_then(|v| validate(&v).map(|_| v)) {
+        Ok(value) => return Ok((value, strip_code_fences(&raw).to_string())),
+        Err(e) => e,
+    };
+
+    let mut repair = request.clone();
+    repair.messages.push(ChatMessage::assistant(raw));
+    repair.messages.push(ChatMessage::user(format!(
+        "That response was invalid: {}. Reply again with only the corrected JSON, matching the required structure exactly.",
+        error
+    )));
+    let raw = provider.stream(&repair, &mut |d| on_delta(2, d)).await?.text;
+    let value = parse_json::<T>(&raw)?;
+    validate(&value).map_err(|e| format!("Malformed AI response: {}", e))?;
+    Ok((value, strip_code_fences(&raw).to_string()))
+}
+
+pub fn provider_for(config: &LlmConfig, api_key: &str) -> Result<Box<dyn ReflectionProvider>, String> {
+    if config.model.trim().is_empty() {
+        return Err("An AI model must be configured".to_string());
+    }
+    let base_url = config.base_url.as_deref().map(str::trim).filter(|u| !u.is_empty());
+    Ok(match config.provider {
+        ProviderKind::OpenAi => {
+            require_key(api_key, "OpenAI")?;
+            Box::new(OpenAiProvider::new(
+                base_url.unwrap_or(openai::DEFAULT_BASE_URL),
+                api_key,
+                config,
+            ))
+        }
+        ProviderKind::OpenAiCompatible => {
+            let base_url = base_url.ok_or("A base URL is required for OpenAI-compatible providers")?;
+            Box::new(OpenAiProvider::new(base_url, api_key, config))
+        }
+        ProviderKind::Anthropic => {
+            require_key(api_key, "Anthropic")?;
+            Box::new(AnthropicProvider::new(
+                base_url.unwrap_or(anthropic::DEFAULT_BASE_URL),
+                api_key,
+                config,
+            ))
+        }
+    })
+}
+
+pub fn provider_for_identity(
+    app: &AppHandle,
+    conn: &Connection,
+    identity_id: i64,
+) -> Result<Box<dyn ReflectionProvider>, String> {
+    metered_provider(app, &load_config(conn, Some(identity_id))?)
+}
+
+// The provider for `config` with every call logged to ai_usage and checked
+// against the monthly budget. Commands should call providers through this.
+pub fn metered_provider(app: &AppHandle, config: &LlmConfig) -> Result<Box<dyn ReflectionProvider>, String> {
+    let inner = provider_for(config, &api_key_for(app, config.provider)?)?;
+    Ok(Box::new(usage::MeteredProvider::new(app.clone(), inner, config)))
+}
+
+pub fn api_key_for(app: &AppHandle, provider: ProviderKind) -> Result<String, String> {
+    Ok(credentials::load_key(app, provider.as_str())?
+        .map(|(key, _)| key)
+        .unwrap_or_default())
+}
+
+fn require_key(api_key: &str, provider: &str) -> Result<(), String> {
+    if api_key.is_empty() {
+        return Err(format!("{} API key is not set. Add it in Settings.", provider));
+    }
+    Ok(())
+}
+
+// An identity-specific configuration wins over the global one; without
+// either, reflections go to OpenAI's gpt-4o-mini as before.
+pub fn load_config(conn: &Connection, identity_id: Option<i64>) -> Result<LlmConfig, String> {
+    if let Some(id) = identity_id {
+        if let Some(config) = stored_config(conn, Some(id))? {
+            return Ok(config);
+        }
+    }
+    Ok(stored_config(conn, None)?.unwrap_or_default())
+}
+
+pub fn stored_config(conn: &Connection, identity_id: Option<i64>) -> Result<Option<LlmConfig>, String> {
+    let row = conn
+        .query_row(
+            "SELECT provider, model, temperature, base_url FROM llm_config WHERE identity_id IS ?1",
+            [identity_id],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, f64>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                ))
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match row {
+        Some((provider, model, temperature, base_url)) => Ok(Some(LlmConfig {
+            provider: ProviderKind::parse(&provider)?,
+            model,
+            temperature,
+            base_url,
+        })),
+        None => Ok(None),
+    }
+}
+
+pub fn save_config(conn: &Connection, identity_id: Option<i64>, config: &LlmConfig) -> Result<(), String> {
+    if !(0.0..=2.0).contains(&config.temperature) {
+        return Err("temperature must be between 0 and 2".to_string());
+    }
+    if config.model.trim().is_empty() {
+        return Err("model is required".to_string());
+    }
+    delete_config(conn, identity_id)?;
+    conn.execute(
+        "INSERT INTO llm_config (identity_id, provider, model, temperature, base_url) VALUES (?1, ?2, ?3, ?4, ?5)",
+        (
+            identity_id,
+            config.provider.as_str(),
+            config.model.trim(),
+            config.temperature,
+            config.base_url.as_deref().map(str::trim).filter(|u| !u.is_empty()),
+        ),
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn delete_config(conn: &Connection, identity_id: Option<i64>) -> Result<(), String> {
+    conn.execute("DELETE FROM llm_config WHERE identity_id IS ?1", [identity_id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Identifies the exact prompt a response was generated from, so versions can
+// be compared by input as well as output.
+pub fn prompt_hash(request: &ChatRequest) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(request.system.as_bytes());
+    for message in &request.messages {
+        hasher.update(b"\n");
+        hasher.update(message.role.as_bytes());
+        hasher.update(b":");
+        hasher.update(message.content.as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+pub fn strip_code_fences(content: &str) -> &str {
+    let content = content.trim();
+    let content = content.strip_prefix("```json").unwrap_or(content);
+    let content = content.strip_prefix("```").unwrap_or(content).trim();
+    content.strip_suffix("```").unwrap_or(content).trim()
+}
+
+pub fn parse_json<T: DeserializeOwned>(content: &str) -> Result<T, String> {
+    serde_json::from_str(strip_code_fences(content))
+        .map_err(|e| format!("Malformed AI response: {}", e))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    // Replays canned replies and records the requests it was sent. Shared
+    // with the command tests.
+    pub(crate) struct Scripted {
+        replies: Mutex<Vec<&'static str>>,
+        pub(crate) seen: Mutex<Vec<ChatRequest>>,
+    }
+
+    impl Scripted {
+        // Everything the provider was sent, system prompts included.
+        pub(crate) fn sent(&self) -> String {
+            let seen = self.seen.lock().unwrap();
+            let mut out = String::new();
+            for request in seen.iter() {
+                out.push_str(&request.system);
+                for message in &request.messages {
+                    out.push('\n');
+                    out.push_str(&message.content);
+                }
+                out.push('\n');
+            }
+            out
+        }
+    }
+
+    #[async_trait]
+    impl ReflectionProvider for Scripted {
+        async fn complete(&self, request: &ChatRequest) -> Result<Completion, String> {
+            self.seen.lock().unwrap().push(request.clone());
+            Ok(Completion {
+                text: self.replies.lock().unwrap().remove(0).to_string(),
+                usage: None,
+            })
+        }
+    }
+
+    pub(crate) fn scripted(replies: Vec<&'static str>) -> Scripted {
+        Scripted {
+            replies: Mutex::new(replies),
+            seen: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn positive(v: &serde_json::Value) -> Result<(), String> {
+        match v.get("a").and_then(|a| a.as_i64()) {
+            Some(a) if a > 0 => Ok(()),
+            _ => Err("a must be positive".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn repairs_invalid_output_once() {
+        let provider = scripted(vec![r#"{"a":0}"#, "```json\n{\"a\":2}\n```"]);
+        let mut attempts = Vec::new();
+        let (value, raw) = complete_json(
+            &provider,
+            &ChatRequest::new("sys", "hi"),
+            positive,
+            &mut |attempt, _| attempts.push(attempt),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(value["a"], 2);
+        assert_eq!(raw, r#"{"a":2}"#);
+        assert_eq!(attempts, vec![1, 2]);
+        let seen = provider.seen.lock().unwrap();
+        let repair = &seen[1].messages;
+        assert_eq!(repair.len(), 3);
+        assert_eq!(repair[1].role, "assistant");
+        assert!(repair[2].content.contains("a must be positive"));
+    }
+
+    #[tokio::test]
+    async fn fails_after_second_invalid_output() {
+        let provider = scripted(vec!["not json", r#"{"a":-1}"#]);
+        let err = complete_json(&provider, &ChatRequest::new("sys", "hi"), positive, &mut |_, _| {})
+            .await
+            .unwrap_err();
+        assert_eq!(err, "Malformed AI response: a must be positive");
+    }
+}
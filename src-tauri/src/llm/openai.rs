@@ -0,0 +1,271 @@
+use async_trait::async_trait;
+
+use super::{http, sse, ChatRequest, Completion, LlmConfig, ReflectionProvider, Usage};
+
+pub const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+
+pub struct OpenAiProvider {
+    base_url: String,
+    api_key: String,
+    model: String,
+    temperature: f64,
+}
+
+impl OpenAiProvider {
+    fn body(&self, request: &ChatRequest) -> serde_json::Value {
+        let mut messages = vec![serde_json::json!({ "role": "system", "content": request.system })];
+        messages.extend(
+            request
+                .messages
+                .iter()
+                .map(|m| serde_json::json!({ "role": m.role, "content": m.content })),
+        );
+        let mut body = serde_json::json!({
+            "model": self.model,
+            "messages": messages,
+            "temperature": request.temperature.unwrap_or(self.temperature)
+        });
+        if let Some(schema) = &request.schema {
+            body["response_format"] = serde_json::json!({
+                "type": "json_schema",
+                "json_schema": { "name": schema.name, "schema": schema.schema, "strict": true }
+            });
+        }
+        body
+    }
+
+    async fn send(&self, body: &serde_json::Value) -> Result<reqwest::Response, String> {
+        let url = format!("{}/chat/completions", self.base_url);
+        let res = http::send_with_retry(|| {
+            let req = http::client().post(&url).header("Content-Type", "application/json");
+            // Local OpenAI-compatible servers usually run without a key.
+            let req = if self.api_key.is_empty() {
+                req
+            } else {
+                req.header("Authorization", format!("Bearer {}", self.api_key))
+            };
+            req.json(body)
+        })
+        .await?;
+
+        if !res.status().is_success() {
+            let err_text = res.text().await.unwrap_or_default();
+            return Err(format!("OpenAI API error: {}", err_text));
+        }
+        Ok(res)
+    }
+
+    fn usage(json: &serde_json::Value) -> Option<Usage> {
+        let usage = json.get("usage")?;
+        Some(Usage {
+            prompt_tokens: usage.get("prompt_tokens")?.as_i64()?,
+            completion_tokens: usage.get("completion_tokens")?.as_i64()?,
+        })
+    }
+
+    pub fn new(base_url: &str, api_key: &str, config: &LlmConfig) -> Self {
+        OpenAiProvider {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: api_key.to_string(),
+            model: config.model.clone(),
+            temperature: config.temperature,
+        }
+    }
+}
+
+#[async_trait]
+impl ReflectionProvider for OpenAiProvider {
+    async fn complete(&self, request: &ChatRequest) -> Result<Completion, String> {
+        let res = self.send(&self.body(request)).await?;
+        let json: serde_json::Value = res.json().await.map_err(|e| e.to_string())?;
+        let text = json
+            .get("choices")
+            .and_then(|c| c.get(0))
+            .and_then(|c| c.get("message"))
+            .and_then(|m| m.get("content"))
+            .and_then(|c| c.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| "Invalid OpenAI response".to_string())?;
+        Ok(Completion {
+            text,
+            usage: Self::usage(&json),
+        })
+    }
+
+    async fn stream(
+        &self,
+        request: &ChatRequest,
+        on_delta: &mut (dyn for<'s> FnMut(&'s str) + Send),
+    ) -> Result<Completion, String> {
+        let mut body = self.body(request);
+        body["stream"] = serde_json::Value::Bool(true);
+        // Usage then arrives in a final chunk with no choices.
+        body["stream_options"] = serde_json::json!({ "include_usage": true });
+        let res = self.send(&body).await?;
+
+        let mut text = String::new();
+        let mut usage = None;
+        sse::read_events(res, |data| {
+            if data == "[DONE]" {
+                return Ok(());
+            }
+            let event: serde_json::Value =
+                serde_json::from_str(data).map_err(|_| "Invalid OpenAI stream event".to_string())?;
+            if let Some(delta) = event
+                .get("choices")
+                .and_then(|c| c.get(0))
+                .and_then(|c| c.get("delta"))
+                .and_then(|d| d.get("content"))
+                .and_then(|c| c.as_str())
+            {
+                on_delta(delta);
+                text.push_str(delta);
+            }
+            usage = Self::usage(&event).or(usage);
+            Ok(())
+        })
+        .await?;
+        if text.is_empty() {
+            return Err("Invalid OpenAI response".to_string());
+        }
+        Ok(Completion { text, usage })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::ProviderKind;
+    use mockito::Matcher;
+
+    fn config() -> LlmConfig {
+        LlmConfig {
+            provider: ProviderKind::OpenAiCompatible,
+            model: "llama3".to_string(),
+            temperature: 0.3,
+            base_url: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn sends_chat_completion_and_returns_content() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/v1/chat/completions")
+            .match_header("authorization", "Bearer sk-test")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "model": "llama3",
+                "temperature": 0.3,
+                "messages": [
+                    { "role": "system", "content": "sys" },
+                    { "role": "user", "content": "hi" }
+                ]
+            })))
+            .with_body(
+                r#"{"choices":[{"message":{"role":"assistant","content":"hello"}}],
+                    "usage":{"prompt_tokens":12,"completion_tokens":3,"total_tokens":15}}"#,
+            )
+            .create_async()
+            .await;
+
+        let provider = OpenAiProvider::new(&format!("{}/v1/", server.url()), "sk-test", &config());
+        let out = provider.complete(&ChatRequest::new("sys", "hi")).await.unwrap();
+
+        assert_eq!(out.text, "hello");
+        assert_eq!(
+            out.usage,
+            Some(Usage {
+                prompt_tokens: 12,
+                completion_tokens: 3
+            })
+        );
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn omits_authorization_without_key() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .match_header("authorization", Matcher::Missing)
+            .match_body(Matcher::PartialJson(serde_json::json!({ "temperature": 0.0 })))
+            .with_body(r#"{"choices":[{"message":{"content":"ok"}}]}"#)
+            .create_async()
+            .await;
+
+        let provider = OpenAiProvider::new(&server.url(), "", &config());
+        let request = ChatRequest::new("sys", "hi").temperature(0.0);
+        assert_eq!(provider.complete(&request).await.unwrap().text, "ok");
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn requests_json_schema_response_format() {
+        let mut server = mockito::Server::new_async().await;
+        let schema = serde_json::json!({ "type": "object", "properties": { "a": { "type": "integer" } } });
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "response_format": {
+                    "type": "json_schema",
+                    "json_schema": { "name": "thing", "schema": schema, "strict": true }
+                }
+            })))
+            .with_body(r#"{"choices":[{"message":{"content":"{\"a\":1}"}}]}"#)
+            .create_async()
+            .await;
+
+        let provider = OpenAiProvider::new(&server.url(), "", &config());
+        let request = ChatRequest::new("sys", "hi").json_schema("thing", schema);
+        assert_eq!(provider.complete(&request).await.unwrap().text, r#"{"a":1}"#);
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn streams_content_deltas() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "stream": true,
+                "stream_options": { "include_usage": true }
+            })))
+            .with_header("content-type", "text/event-stream")
+            .with_body(concat!(
+                "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n",
+                "data: {\"choices\":[{\"delta\":{\"content\":\"hel\"}}]}\n\n",
+                "data: {\"choices\":[{\"delta\":{\"content\":\"lo\"}}]}\n\n",
+                "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":5,\"completion_tokens\":2}}\n\n",
+                "data: [DONE]\n\n"
+            ))
+            .create_async()
+            .await;
+
+        let provider = OpenAiProvider::new(&server.url(), "", &config());
+        let mut deltas = Vec::new();
+        let out = provider
+            .stream(&ChatRequest::new("sys", "hi"), &mut |d| deltas.push(d.to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(out.text, "hello");
+        assert_eq!(out.usage.map(|u| u.completion_tokens), Some(2));
+        assert_eq!(deltas, vec!["hel", "lo"]);
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn surfaces_api_errors() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/chat/completions")
+            .with_status(401)
+            .with_body("invalid key")
+            .create_async()
+            .await;
+
+        let provider = OpenAiProvider::new(&server.url(), "bad", &config());
+        let err = provider.complete(&ChatRequest::new("sys", "hi")).await.unwrap_err();
+        assert_eq!(err, "OpenAI API error: invalid key");
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+use serde::Serialize;
+
+// Built-in detectors for contact details.
+const EMAIL: &str = r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}";
+const URL: &str = r#"\b(?:https?://|www\.)[^\s<>()\[\]"']+[^\s<>()\[\]"'.,;:!?]"#;
+// At least 9 digits in groups, so dates such as 2024-03-04 and plain
+// quantities are left alone.
+const PHONE: &str = r"(?:\+\d{1,3}[\s.-]?)?(?:\(\d{2,4}\)[\s.-]?|\b\d{2,4}[\s.-])\d{3,4}[\s.-]?\d{3,4}\b";
+
+pub struct Rule {
+    // Placeholder name, e.g. "EMAIL" for [EMAIL_1].
+    label: String,
+    regex: Regex,
+}
+
+impl Rule {
+    pub fn pattern(label: &str, pattern: &str) -> Result<Self, String> {
+        let regex = Regex::new(pattern).map_err(|e| format!("Invalid pattern '{}': {}", pattern, e))?;
+        Ok(Rule {
+            label: normalize_label(label),
+            regex,
+        })
+    }
+
+    // Matches any of `terms` as whole words, ignoring case, longest first so
+    // "Anna Smith" wins over "Anna". A word boundary is only required where a
+    // term starts or ends with a word character, so "C++" and "@handle" match.
+    pub fn terms(label: &str, terms: &[String]) -> Result<Option<Self>, String> {
+        let mut terms: Vec<&str> = terms.iter().map(|t| t.trim()).filter(|t| !t.is_empty()).collect();
+        if terms.is_empty() {
+            return Ok(None);
+        }
+        terms.sort_by_key(|t| std::cmp::Reverse(t.len()));
+        let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+        let alternatives: Vec<String> = terms
+            .iter()
+            .map(|t| {
+                let start = if is_word(t.chars().next()) { r"\b" } else { "" };
+                let end = if is_word(t.chars().last()) { r"\b" } else { "" };
+                format!("{}{}{}", start, regex::escape(t), end)
+            })
+            .collect();
+        Rule::pattern(label, &format!("(?i)(?:{})", alternatives.join("|"))).map(Some)
+    }
+}
+
+pub fn builtin_rules() -> Vec<Rule> {
+    [("EMAIL", EMAIL), ("URL", URL), ("PHONE", PHONE)]
+        .into_iter()
+        .filter_map(|(label, pattern)| Rule::pattern(label, pattern).ok())
+        .collect()
+}
+
+// Upper-case letters, digits and underscores, so a placeholder can never be
+// mistaken for ordinary text.
+fn normalize_label(label: &str) -> String {
+    let label: String = label
+        .trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+    if label.is_empty() {
+        "REDACTED".to_string()
+    } else {
+        label
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Replacement {
+    pub placeholder: String,
+    pub original: String,
+}
+
+// Replaces sensitive text with placeholders before a request is sent and puts
+// the originals back into the reply. The same text, in any case, gets the
+// same placeholder across every call on one redactor.
+pub struct Redactor {
+    rules: Vec<Rule>,
+    replacements: Vec<Replacement>,
+    // Placeholder for each label and case-folded original.
+    seen: HashMap<(String, String), String>,
+}
+
+impl Redactor {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        Redactor {
+            rules,
+            replacements: Vec::new(),
+            seen: HashMap::new(),
+        }
+    }
+
+    pub fn replacements(&self) -> &[Replacement] {
+        &self.replacements
+    }
+
+    pub fn redact(&mut self, text: &str) -> String {
+        // Where matches overlap the longest wins, so a name inside an email
+        // address does not split it; on a tie the earlier rule wins.
+        let mut found: Vec<(usize, usize, usize)> = Vec::new();
+        for (rule, r) in self.rules.iter().enumerate() {
+            found.extend(r.regex.find_iter(text).map(|m| (m.start(), m.end(), rule)));
+        }
+        found.sort_by_key(|&(start, end, rule)| (std::cmp::Reverse(end - start), rule, start));
+        let mut matches: Vec<(usize, usize, usize)> = Vec::new();
+        for (start, end, rule) in found {
+            if !matches.iter().any(|&(s, e, _)| start < e && s < end) {
+                matches.push((start, end, rule));
+            }
+        }
+        matches.sort();
+
+        let mut out = String::with_capacity(text.len());
+        let mut last = 0;
+        for (start, end, rule) in matches {
+            out.push_str(&text[last..start]);
+            let placeholder = self.placeholder(rule, &text[start..end]);
+            out.push_str(&placeholder);
+            last = end;
+        }
+        out.push_str(&text[last..]);
+        out
+    }
+
+    // Reuses the placeholder of an earlier match differing only in case; the
+    // reply is restored with the first spelling seen.
+    fn placeholder(&mut self, rule: usize, original: &str) -> String {
+        let label = &self.rules[rule].label;
+        let key = (label.clone(), original.to_lowercase());
+        if let Some(placeholder) = self.seen.get(&key) {
+            return placeholder.clone();
+        }
+        let prefix = format!("[{}_", label);
+        let n = self.replacements.iter().filter(|r| r.placeholder.starts_with(&prefix)).count() + 1;
+        let placeholder = format!("{}{}]", prefix, n);
+        self.replacements.push(Replacement {
+            placeholder: placeholder.clone(),
+            original: original.to_string(),
+        });
+        self.seen.insert(key, placeholder.clone());
+        placeholder
+    }
+
+    pub fn restore(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for r in &self.replacements {
+            text = text.replace(&r.placeholder, &r.original);
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_with_stable_placeholders_and_restores() {
+        let terms = Rule::terms("person", &["Anna".to_string(), "Anna Smith".to_string()])
+            .unwrap()
+            .unwrap();
+        let mut rules = vec![terms];
+        rules.extend(builtin_rules());
+        let mut redactor = Redactor::new(rules);
+
+        let sent = redactor.redact(
+            "Called anna smith, then emailed anna@example.com about https://example.com/plan. \
+             Anna Smith again at +1 555-123-4567 on 2024-03-04, ran 5 km. Anna came too.",
+        );
+        assert_eq!(
+            sent,
+            "Called [PERSON_1], then emailed [EMAIL_1] about [URL_1]. \
+             [PERSON_1] again at [PHONE_1] on 2024-03-04, ran 5 km. [PERSON_2] came too."
+        );
+
+        // A later call reuses the placeholders already handed out, whatever
+        // the case.
+        assert_eq!(redactor.redact("ANNA SMITH and anna"), "[PERSON_1] and [PERSON_2]");
+        assert_eq!(
+            redactor.restore("Good call with [PERSON_1]; follow up via [EMAIL_1]."),
+            "Good call with anna smith; follow up via anna@example.com."
+        );
+    }
+
+    #[test]
+    fn terms_edged_with_symbols_match() {
+        let terms = ["C++", "@handle", "#proj", "Go"].map(String::from);
+        let mut redactor = Redactor::new(vec![Rule::terms("term", &terms).unwrap().unwrap()]);
+        assert_eq!(
+            redactor.redact("Shipped C++ for @handle in #proj; Gopher and go."),
+            "Shipped [TERM_1] for [TERM_2] in [TERM_3]; Gopher and [TERM_4]."
+        );
+    }
+
+    #[test]
+    fn longest_match_wins_overlaps_and_bad_patterns_are_rejected() {
+        let mut redactor = Redactor::new(vec![
+            Rule::pattern("word", r"\w+").unwrap(),
+            Rule::pattern("project", r"Project \w+").unwrap(),
+            Rule::pattern("code", r"\w+").unwrap(),
+        ]);
+        assert_eq!(redactor.redact("Project Falcon go"), "[PROJECT_1] [WORD_1]");
+        assert!(Rule::pattern("x", "(").is_err());
+        assert!(Rule::terms("x", &["  ".to_string()]).unwrap().is_none());
+    }
+}
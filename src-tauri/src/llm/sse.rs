@@ -0,0 +1,59 @@
+// Splits a server-sent events body into `data:` payloads as chunks arrive.
+// Bytes are buffered until a full line is available so multi-byte characters
+// split across chunks survive.
+#[derive(Default)]
+pub struct SseReader {
+    buf: Vec<u8>,
+}
+
+impl SseReader {
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buf.extend_from_slice(chunk);
+        let mut out = Vec::new();
+        while let Some(pos) = self.buf.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buf.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(data) = line.trim_end().strip_prefix("data:") {
+                out.push(data.trim_start().to_string());
+            }
+        }
+        out
+    }
+}
+
+// Reads a streaming response to the end, handing each `data:` payload to
+// `on_data`. Dropping the future drops the response, which aborts the request.
+pub async fn read_events(
+    mut res: reqwest::Response,
+    mut on_data: impl FnMut(&str) -> Result<(), String> + Send,
+) -> Result<(), String> {
+    let mut reader = SseReader::default();
+    // A connection lost mid-reply is a network failure like any other; a
+    // stalled one is the server's problem and reported as is.
+    while let Some(chunk) = res.chunk().await.map_err(|e| {
+        if super::http::is_connection_failure(&e) {
+            format!("{}: {}", super::http::OFFLINE, e)
+        } else {
+            e.to_string()
+        }
+    })? {
+        for data in reader.push(&chunk) {
+            on_data(&data)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joins_lines_split_across_chunks() {
+        let mut reader = SseReader::default();
+        let text = "data: {\"t\":\"é\"}\n\nevent: ping\ndata: [DONE]\n".as_bytes();
+        let (a, b) = text.split_at(13);
+        assert!(reader.push(a).is_empty());
+        assert_eq!(reader.push(b), vec!["{\"t\":\"é\"}", "[DONE]"]);
+    }
+}
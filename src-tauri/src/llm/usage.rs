@@ -0,0 +1,406 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::{Datelike, NaiveDate};
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::AppHandle;
+
+use super::{ChatRequest, Completion, LlmConfig, ProviderKind, ReflectionProvider, Usage};
+use crate::commands::settings::get_setting;
+use crate::{dates, db};
+
+pub(crate) const MONTHLY_BUDGET: &str = "monthly_budget";
+
+// Prefix of errors refusing a call because of the monthly budget. Callers
+// with a fallback pass these on, so the user learns the budget stopped them.
+pub const BUDGET: &str = "Monthly AI budget";
+
+pub fn is_budget_error(error: &str) -> bool {
+    error.starts_with(BUDGET)
+}
+
+// USD per million prompt and completion tokens, written to ai_price on first
+// run. Models match the longest priced prefix, so dated snapshots such as
+// gpt-4o-mini-2024-07-18 use their family's price.
+const DEFAULT_PRICES: [(&str, f64, f64); 8] = [
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("gpt-4o", 2.50, 10.00),
+    ("gpt-4.1-mini", 0.40, 1.60),
+    ("gpt-4.1", 2.00, 8.00),
+    ("claude-3-haiku", 0.25, 1.25),
+    ("claude-3-5-haiku", 0.80, 4.00),
+    ("claude-3-5-sonnet", 3.00, 15.00),
+    ("claude-3-7-sonnet", 3.00, 15.00),
+];
+
+#[derive(Debug, Serialize)]
+pub struct Price {
+    pub model: String,
+    pub prompt_per_million: f64,
+    pub completion_per_million: f64,
+}
+
+impl Price {
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        (usage.prompt_tokens as f64 * self.prompt_per_million
+            + usage.completion_tokens as f64 * self.completion_per_million)
+            / 1_000_000.0
+    }
+}
+
+pub(crate) fn seed_prices(conn: &Connection) -> Result<(), String> {
+    // Seed once, so prices the user removed stay removed.
+    let seeded: i64 = conn
+        .query_row("SELECT COUNT(*) FROM ai_price", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if seeded > 0 {
+        return Ok(());
+    }
+    for (model, prompt, completion) in DEFAULT_PRICES {
+        conn.execute(
+            "INSERT INTO ai_price (model, prompt_per_million, completion_per_million) VALUES (?1, ?2, ?3)",
+            (model, prompt, completion),
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+pub(crate) fn price_for(conn: &Connection, model: &str) -> Result<Option<Price>, String> {
+    conn.query_row(
+        "SELECT model, prompt_per_million, completion_per_million FROM ai_price \
+         WHERE substr(?1, 1, length(model)) = model ORDER BY length(model) DESC LIMIT 1",
+        [model],
+        |row| {
+            Ok(Price {
+                model: row.get(0)?,
+                prompt_per_million: row.get(1)?,
+                completion_per_million: row.get(2)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+pub(crate) fn monthly_budget(conn: &Connection) -> Result<Option<f64>, String> {
+    match get_setting(conn, MONTHLY_BUDGET)? {
+        Some(v) => v
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("Invalid stored monthly_budget: {}", v)),
+        None => Ok(None),
+    }
+}
+
+pub(crate) fn month_start(conn: &Connection) -> Result<NaiveDate, String> {
+    let today = dates::local_today(conn)?;
+    Ok(today.with_day(1).unwrap_or(today))
+}
+
+// Estimated spend in the current calendar month.
+pub(crate) fn month_spent(conn: &Connection) -> Result<f64, String> {
+    conn.query_row(
+        "SELECT COALESCE(SUM(cost), 0) FROM ai_usage WHERE date >= ?1",
+        [dates::format_date(month_start(conn)?)],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+// With a budget set, calls to a model without a price are refused: they
+// would be logged at no cost and could never reach the cap.
+fn check_budget(conn: &Connection, model: &str) -> Result<(), String> {
+    let Some(budget) = monthly_budget(conn)? else {
+        return Ok(());
+    };
+    if price_for(conn, model)?.is_none() {
+        return Err(format!(
+            "{} is set, but model '{}' has no price, so its calls cannot be counted. \
+             Add a price for it (0 for a free local model) or remove the budget.",
+            BUDGET, model
+        ));
+    }
+    let spent = month_spent(conn)?;
+    if spent >= budget {
+        return Err(format!(
+            "{} of ${:.2} is used up (${:.2} spent since {}). Raise the budget or wait until next month.",
+            BUDGET,
+            budget,
+            spent,
+            dates::format_date(month_start(conn)?)
+        ));
+    }
+    Ok(())
+}
+
+// Rough token count for text the provider did not report usage for.
+const CHARS_PER_TOKEN: usize = 4;
+
+const CANCELLED: &str = "Cancelled before the reply finished";
+
+fn estimate_usage(request: &ChatRequest, received: usize) -> Usage {
+    let sent = request.system.len() + request.messages.iter().map(|m| m.content.len()).sum::<usize>();
+    Usage {
+        prompt_tokens: ((sent + CHARS_PER_TOKEN - 1) / CHARS_PER_TOKEN) as i64,
+        completion_tokens: ((received + CHARS_PER_TOKEN - 1) / CHARS_PER_TOKEN) as i64,
+    }
+}
+
+// How a metered call ended.
+enum Outcome<'a> {
+    Done(&'a Result<Completion, String>),
+    Cancelled,
+}
+
+// `received` is how much reply text was streamed before the call ended.
+fn log_call(
+    conn: &Connection,
+    provider: ProviderKind,
+    model: &str,
+    request: &ChatRequest,
+    outcome: Outcome,
+    received: usize,
+    latency: Duration,
+) -> Result<(), String> {
+    let (usage, error) = match outcome {
+        Outcome::Done(Ok(c)) => (c.usage, None),
+        Outcome::Done(Err(e)) => (None, Some(e.as_str())),
+        Outcome::Cancelled => (None, Some(CANCELLED)),
+    };
+    // A call that got as far as a reply, or was dropped while one may have
+    // been generating, is billed even without a usage report. Under a budget
+    // it is priced from an estimate rather than slipping past the cap.
+    let billed = error.is_none() || error == Some(CANCELLED) || received > 0;
+    let cost = match price_for(conn, model)? {
+        Some(price) => match usage {
+            Some(usage) => Some(price.cost(&usage)),
+            None if billed && monthly_budget(conn)?.is_some() => {
+                Some(price.cost(&estimate_usage(request, received)))
+            }
+            None => None,
+        },
+        None => None,
+    };
+    conn.execute(
+        "INSERT INTO ai_usage (date, provider, model, prompt_tokens, completion_tokens, latency_ms, cost, error) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        (
+            dates::format_date(dates::local_today(conn)?),
+            provider.as_str(),
+            model,
+            usage.map(|u| u.prompt_tokens),
+            usage.map(|u| u.completion_tokens),
+            latency.as_millis() as i64,
+            cost,
+            error,
+        ),
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Logs every call to ai_usage with its tokens, latency and estimated cost,
+// and refuses new calls once the monthly budget is spent.
+pub struct MeteredProvider {
+    app: AppHandle,
+    inner: Box<dyn ReflectionProvider>,
+    provider: ProviderKind,
+    model: String,
+}
+
+impl MeteredProvider {
+    pub fn new(app: AppHandle, inner: Box<dyn ReflectionProvider>, config: &LlmConfig) -> Self {
+        MeteredProvider {
+            app,
+            inner,
+            provider: config.provider,
+            model: config.model.clone(),
+        }
+    }
+
+    fn before(&self) -> Result<(), String> {
+        check_budget(&db::get_conn(&self.app)?, &self.model)
+    }
+
+    fn start<'a>(&'a self, request: &'a ChatRequest) -> Call<'a> {
+        Call {
+            meter: self,
+            request,
+            started: Instant::now(),
+            received: AtomicUsize::new(0),
+            finished: false,
+        }
+    }
+}
+
+// Records a call when it ends. Created before the call is awaited, so a
+// caller dropping the future midway still leaves a cancelled row behind.
+struct Call<'a> {
+    meter: &'a MeteredProvider,
+    request: &'a ChatRequest,
+    started: Instant,
+    received: AtomicUsize,
+    finished: bool,
+}
+
+impl Call<'_> {
+    fn record(&self, outcome: Outcome) {
+        let meter = self.meter;
+        // A failed log write must not lose a reply that was already paid for.
+        let _ = db::get_conn(&meter.app).and_then(|conn| {
+            log_call(
+                &conn,
+                meter.provider,
+                &meter.model,
+                self.request,
+                outcome,
+                self.received.load(Ordering::Relaxed),
+                self.started.elapsed(),
+            )
+        });
+    }
+
+    fn finish(mut self, result: &Result<Completion, String>) {
+        self.finished = true;
+        self.record(Outcome::Done(result));
+    }
+}
+
+impl Drop for Call<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.record(Outcome::Cancelled);
+        }
+    }
+}
+
+#[async_trait]
+impl ReflectionProvider for MeteredProvider {
+    async fn complete(&self, request: &ChatRequest) -> Result<Completion, String> {
+        self.before()?;
+        let call = self.start(request);
+        let result = self.inner.complete(request).await;
+        call.finish(&result);
+        result
+    }
+
+    async fn stream(
+        &self,
+        request: &ChatRequest,
+        on_delta: &mut (dyn for<'s> FnMut(&'s str) + Send),
+    ) -> Result<Completion, String> {
+        self.before()?;
+        let call = self.start(request);
+        let result = {
+            let received = &call.received;
+            let mut counting = |delta: &str| {
+                received.fetch_add(delta.len(), Ordering::Relaxed);
+                on_delta(delta)
+            };
+            self.inner.stream(request, &mut counting).await
+        };
+        call.finish(&result);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::settings::set_setting;
+
+    #[test]
+    fn models_use_their_longest_priced_prefix() {
+        let conn = db::test_conn();
+        let model = |name: &str| price_for(&conn, name).unwrap().map(|p| p.model);
+        assert_eq!(model("gpt-4o").as_deref(), Some("gpt-4o"));
+        assert_eq!(model("gpt-4o-2024-08-06").as_deref(), Some("gpt-4o"));
+        assert_eq!(model("gpt-4o-mini-2024-07-18").as_deref(), Some("gpt-4o-mini"));
+        assert_eq!(model("claude-3-5-haiku-latest").as_deref(), Some("claude-3-5-haiku"));
+        // A prefix of a priced model is not that model.
+        assert_eq!(model("gpt-4"), None);
+        assert_eq!(model("llama3.1:8b"), None);
+
+        let price = price_for(&conn, "gpt-4o-mini").unwrap().unwrap();
+        let usage = Usage {
+            prompt_tokens: 1_000_000,
+            completion_tokens: 500_000,
+        };
+        assert!((price.cost(&usage) - 0.45).abs() < 1e-9);
+    }
+
+    #[test]
+    fn budget_caps_spend_and_refuses_unpriced_models() {
+        let conn = db::test_conn();
+        // No budget: anything goes.
+        check_budget(&conn, "llama3.1:8b").unwrap();
+
+        set_setting(&conn, MONTHLY_BUDGET, Some("1")).unwrap();
+        check_budget(&conn, "gpt-4o").unwrap();
+        let err = check_budget(&conn, "llama3.1:8b").unwrap_err();
+        assert!(is_budget_error(&err) && err.contains("no price"), "{}", err);
+
+        // A zero price counts the model as free on purpose.
+        conn.execute(
+            "INSERT INTO ai_price (model, prompt_per_million, completion_per_million) VALUES ('llama', 0, 0)",
+            [],
+        )
+        .unwrap();
+        check_budget(&conn, "llama3.1:8b").unwrap();
+
+        let spend = |cost: f64| {
+            conn.execute(
+                "INSERT INTO ai_usage (date, provider, model, latency_ms, cost) VALUES (?1, 'openai', 'gpt-4o', 0, ?2)",
+                (dates::format_date(dates::local_today(&conn).unwrap()), cost),
+            )
+            .unwrap();
+        };
+        spend(0.6);
+        check_budget(&conn, "gpt-4o").unwrap();
+        spend(0.4);
+        let err = check_budget(&conn, "gpt-4o").unwrap_err();
+        assert!(is_budget_error(&err) && err.contains("used up"), "{}", err);
+    }
+
+    #[test]
+    fn calls_without_usage_are_estimated_under_a_budget() {
+        let conn = db::test_conn();
+        let request = ChatRequest::new("s".repeat(400), "u".repeat(400));
+        let log = |outcome: Outcome, received: usize| {
+            log_call(&conn, ProviderKind::OpenAi, "gpt-4o", &request, outcome, received, Duration::ZERO).unwrap();
+        };
+        let last = || -> (Option<f64>, Option<String>) {
+            conn.query_row("SELECT cost, error FROM ai_usage ORDER BY id DESC LIMIT 1", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap()
+        };
+        let reply = Ok(Completion {
+            text: "ok".to_string(),
+            usage: None,
+        });
+
+        // Without a budget an unreported call stays unpriced.
+        log(Outcome::Done(&reply), 0);
+        assert_eq!(last(), (None, None));
+
+        set_setting(&conn, MONTHLY_BUDGET, Some("1")).unwrap();
+        log(Outcome::Done(&reply), 0);
+        // 200 prompt tokens at $2.50 per million.
+        let (cost, _) = last();
+        assert!((cost.unwrap() - 0.0005).abs() < 1e-9);
+
+        // A dropped stream is logged as cancelled and priced with what it got.
+        log(Outcome::Cancelled, 4000);
+        let (cost, error) = last();
+        assert!((cost.unwrap() - (0.0005 + 0.01)).abs() < 1e-9);
+        assert_eq!(error.as_deref(), Some(CANCELLED));
+
+        // A call refused before any reply costs nothing.
+        log(Outcome::Done(&Err("HTTP 500".to_string())), 0);
+        assert_eq!(last(), (None, Some("HTTP 500".to_string())));
+    }
+}